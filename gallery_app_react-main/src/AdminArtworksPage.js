@@ -50,7 +50,7 @@ const AdminArtworksPage = () => {
       const addedArtwork = await createArtwork({
         ...newArtwork,
         yearCreated: parseInt(newArtwork.yearCreated),
-        price: parseFloat(newArtwork.price),
+        price: { amount: newArtwork.price, currency: 'ILS' },
       });
 
       setArtworks([...artworks, addedArtwork]);
@@ -73,7 +73,7 @@ const AdminArtworksPage = () => {
   };
 
   const handleEditClick = (artwork) => {
-    setEditingArtwork({ ...artwork }); // Set the artwork to be edited
+    setEditingArtwork({ ...artwork, priceAmount: artwork.price.amount }); // Set the artwork to be edited
   };
 
   const handleEditFormChange = (e) => {
@@ -89,7 +89,7 @@ const AdminArtworksPage = () => {
       const updatedArtwork = await updateArtwork(editingArtwork.idArtwork, {
         ...editingArtwork,
         yearCreated: parseInt(editingArtwork.yearCreated),
-        price: parseFloat(editingArtwork.price),
+        price: { ...editingArtwork.price, amount: editingArtwork.priceAmount },
       });
 
       setArtworks(artworks.map(art => (art.idArtwork === updatedArtwork.idArtwork ? updatedArtwork : art)));
@@ -143,7 +143,7 @@ const AdminArtworksPage = () => {
             <input type="text" name="title" placeholder="כותרת" value={editingArtwork.title} onChange={handleEditFormChange} required />
             <textarea name="description" placeholder="תיאור" value={editingArtwork.description} onChange={handleEditFormChange} required />
             <input type="number" name="yearCreated" placeholder="שנת יצירה" value={editingArtwork.yearCreated} onChange={handleEditFormChange} required />
-            <input type="number" name="price" placeholder="מחיר" value={editingArtwork.price} onChange={handleEditFormChange} required />
+            <input type="number" name="priceAmount" placeholder="מחיר" value={editingArtwork.priceAmount} onChange={handleEditFormChange} required />
             <input type="text" name="idArtist" placeholder="מזהה אמן" value={editingArtwork.idArtist} onChange={handleEditFormChange} required />
             <input type="text" name="artistName" placeholder="שם אמן" value={editingArtwork.artistName} onChange={handleEditFormChange} required />
             <input type="text" name="artType" placeholder="סוג אומנות" value={editingArtwork.artType} onChange={handleEditFormChange} required />
@@ -163,7 +163,7 @@ const AdminArtworksPage = () => {
             <ul>
               {artworks.map(artwork => (
                 <li key={artwork.idArtwork}>
-                  {artwork.title} by {artwork.artistName} ({artwork.yearCreated}) - ${artwork.price.toLocaleString()}
+                  {artwork.title} by {artwork.artistName} ({artwork.yearCreated}) - {artwork.price.amount} {artwork.price.currency}
                   <button onClick={() => handleEditClick(artwork)}>ערוך</button>
                   <button onClick={() => handleDeleteArtwork(artwork.idArtwork)}>מחק</button>
                 </li>
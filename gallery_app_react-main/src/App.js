@@ -6,6 +6,7 @@ import AuthModal from './AuthModal';
 import CartPage from './CartPage'; // Import CartPage
 import LoginPage from './LoginPage'; // Import LoginPage
 import ThankYouModal from './ThankYouModal'; // Import ThankYouModal
+import { addCartItem, logoutCustomer, removeCartItem } from './api';
 import './App.css';
 import './ArtworksPage.css';
 import './LoginPage.css';
@@ -29,6 +30,7 @@ function App() {
   };
 
   const handleLogout = () => {
+    logoutCustomer().catch((error) => console.error("Error during logout:", error));
     setIsAuthenticated(false);
     setUser(null); // Clear user data on logout
     setCartItems([]); // Clear cart on logout
@@ -42,7 +44,8 @@ function App() {
     setShowAuthModal(false);
   };
 
-  const handleAddToCart = (artwork) => {
+  const handleAddToCart = async (artwork) => {
+    await addCartItem(artwork.idArtwork); // Reserves the artwork on the server; throws if someone else holds it
     setCartItems((prevItems) => {
       const existingItem = prevItems.find(item => item.idArtwork === artwork.idArtwork);
       if (existingItem) {
@@ -56,6 +59,7 @@ function App() {
   };
 
   const handleRemoveFromCart = (idArtwork) => {
+    removeCartItem(idArtwork).catch((error) => console.error("Error releasing cart item:", error));
     setCartItems((prevItems) => prevItems.filter(item => item.idArtwork !== idArtwork));
   };
 
@@ -98,13 +98,13 @@ const ArtworksPage = ({ isAuthenticated, onShowAuth, onLogout, onAddToCart, cart
 
     if (minPriceFilter) {
       currentFiltered = currentFiltered.filter(artwork =>
-        artwork.price >= parseFloat(minPriceFilter)
+        parseFloat(artwork.price.amount) >= parseFloat(minPriceFilter)
       );
     }
 
     if (maxPriceFilter) {
       currentFiltered = currentFiltered.filter(artwork =>
-        artwork.price <= parseFloat(maxPriceFilter)
+        parseFloat(artwork.price.amount) <= parseFloat(maxPriceFilter)
       );
     }
 
@@ -114,13 +114,18 @@ const ArtworksPage = ({ isAuthenticated, onShowAuth, onLogout, onAddToCart, cart
   // Extract unique art types for the dropdown
   const uniqueArtTypes = [...new Set(artworks.map(artwork => artwork.artType))];
 
-  const handleAddToCart = (artwork) => {
+  const handleAddToCart = async (artwork) => {
     if (!isAuthenticated) {
       onShowAuth();
       alert('אנא התחבר או הירשם כדי להוסיף פריטים לסל.');
-    } else {
-      onAddToCart(artwork); // Call the prop function to add to global cart
+      return;
+    }
+    try {
+      await onAddToCart(artwork); // Call the prop function to add to global cart
       alert(`הפריט "${artwork.title}" נוסף לסל בהצלחה!`);
+    } catch (error) {
+      console.error("Error adding to cart:", error);
+      alert('היצירה שמורה כרגע בסל של לקוח אחר או שכבר נמכרה.');
     }
   };
 
@@ -216,7 +221,7 @@ const ArtworksPage = ({ isAuthenticated, onShowAuth, onLogout, onAddToCart, cart
                   <p><strong>סוג:</strong> {artwork.artType}</p>
                   <p><strong>שנה:</strong> {artwork.yearCreated}</p>
                   <p><strong>תיאור:</strong> {artwork.description}</p>
-                  <p className="artwork-price"><strong>מחיר:</strong> ${artwork.price.toLocaleString()}</p>
+                  <p className="artwork-price"><strong>מחיר:</strong> {artwork.price.amount} {artwork.price.currency}</p>
                   <button
                     className="add-to-cart-button"
                     onClick={() => handleAddToCart(artwork)}
@@ -1,9 +1,9 @@
 import React from 'react';
-import { deleteArtwork } from './api'; // Import deleteArtwork instead of checkoutArtworks
+import { checkoutArtworks } from './api';
 
 const CartPage = ({ cartItems, onRemoveFromCart, onGoBack, onCheckoutSuccess }) => {
   const calculateTotal = () => {
-    return cartItems.reduce((total, item) => total + item.price, 0);
+    return cartItems.reduce((total, item) => total + parseFloat(item.price.amount), 0);
   };
 
   const handleCheckout = async () => {
@@ -13,15 +13,18 @@ const CartPage = ({ cartItems, onRemoveFromCart, onGoBack, onCheckoutSuccess })
     }
 
     try {
-      // Delete each artwork individually
-      for (const item of cartItems) {
-        await deleteArtwork(item.idArtwork);
-      }
+      await checkoutArtworks(cartItems.map(item => item.idArtwork));
       alert("הזמנתך בוצעה בהצלחה!");
       onCheckoutSuccess(); // Notify App.js to show thank you and clear cart
     } catch (error) {
       console.error("Error during checkout:", error);
-      alert("אירעה שגיאה במהלך התשלום. אנא נסה שוב.");
+      if (error.message.includes("no longer available")) {
+        alert("אחת היצירות בסל כבר נמכרה. אנא הסר אותה ונסה שוב.");
+      } else if (error.message.includes("Authentication required")) {
+        alert("יש להתחבר לפני ביצוע הזמנה.");
+      } else {
+        alert("אירעה שגיאה במהלך התשלום. אנא נסה שוב.");
+      }
     }
   };
 
@@ -42,7 +45,7 @@ const CartPage = ({ cartItems, onRemoveFromCart, onGoBack, onCheckoutSuccess })
               <div className="cart-item-details">
                 <h2>{item.title}</h2>
                 <p><strong>אמן:</strong> {item.artistName}</p>
-                <p><strong>מחיר:</strong> ${item.price.toLocaleString()}</p>
+                <p><strong>מחיר:</strong> {item.price.amount} {item.price.currency}</p>
                 <button onClick={() => onRemoveFromCart(item.idArtwork)} className="remove-from-cart-button">
                   הסר
                 </button>
@@ -50,7 +53,7 @@ const CartPage = ({ cartItems, onRemoveFromCart, onGoBack, onCheckoutSuccess })
             </div>
           ))}
           <div className="cart-summary">
-            <h2>סה"כ: ${calculateTotal().toLocaleString()}</h2>
+            <h2>סה"כ: {calculateTotal().toFixed(2)}</h2>
             <button onClick={handleCheckout} className="checkout-button">לתשלום</button>
           </div>
         </div>
@@ -23,6 +23,8 @@ const LoginPage = ({ onLoginSuccess }) => {
       console.error("Error message:", error.message);
       if (error.message.includes("Customer not found or invalid credentials") || error.message.includes("Invalid credentials")) {
         alert('שם משתמש או סיסמה שגויים.');
+      } else if (error.message.includes("Too many failed login attempts")) {
+        alert('יותר מדי ניסיונות התחברות כושלים. החשבון ננעל זמנית, אנא נסה שוב מאוחר יותר.');
       } else {
         alert("ההתחברות נכשלה. אנא נסה שוב מאוחר יותר.");
       }
@@ -1,4 +1,11 @@
 const API_BASE_URL = 'http://127.0.0.1:3007';
+const TOKEN_STORAGE_KEY = 'authToken';
+
+// Mutating requests must carry the session token returned by the login endpoint.
+const authHeaders = () => {
+  const token = localStorage.getItem(TOKEN_STORAGE_KEY);
+  return token ? { Authorization: `Bearer ${token}` } : {};
+};
 
 export const getArtworks = async () => {
   const response = await fetch(`${API_BASE_URL}/artworks/`);
@@ -15,6 +22,7 @@ export const createArtwork = async (artworkData) => {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
+      ...authHeaders(),
     },
     body: JSON.stringify(artworkData),
   });
@@ -29,6 +37,7 @@ export const updateArtwork = async (idArtwork, artworkData) => {
     method: 'PUT',
     headers: {
       'Content-Type': 'application/json',
+      ...authHeaders(),
     },
     body: JSON.stringify(artworkData),
   });
@@ -41,6 +50,7 @@ export const updateArtwork = async (idArtwork, artworkData) => {
 export const deleteArtwork = async (idArtwork) => {
   const response = await fetch(`${API_BASE_URL}/artworks/${idArtwork}`, {
     method: 'DELETE',
+    headers: authHeaders(),
   });
   if (!response.ok) {
     throw new Error(`HTTP error! status: ${response.status}`);
@@ -64,13 +74,13 @@ export const createCustomer = async (customerData) => {
 };
 
 export const loginCustomer = async (email, password) => {
-  const params = new URLSearchParams({
-    email,
-    password,
-  }).toString();
-  const loginUrl = `${API_BASE_URL}/customers/login?${params}`;
-  console.log("Attempting login to URL:", loginUrl); // Log the full URL
-  const response = await fetch(loginUrl);
+  const response = await fetch(`${API_BASE_URL}/customers/login`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+    },
+    body: JSON.stringify({ email, password }),
+  });
   if (!response.ok) {
     // The server returns a specific status code for invalid credentials or not found
     let errorMessage = `HTTP error! status: ${response.status}`;
@@ -89,7 +99,21 @@ export const loginCustomer = async (email, password) => {
     console.error("Login API error message before throwing:", errorMessage); // Added for debugging
     throw new Error(errorMessage);
   }
-  return response.json();
+  const session = await response.json();
+  localStorage.setItem(TOKEN_STORAGE_KEY, session.token);
+  return session.customer;
+};
+
+export const logoutCustomer = async () => {
+  const response = await fetch(`${API_BASE_URL}/customers/logout`, {
+    method: 'POST',
+    headers: authHeaders(),
+  });
+  localStorage.removeItem(TOKEN_STORAGE_KEY);
+  if (!response.ok) {
+    throw new Error(`HTTP error! status: ${response.status}`);
+  }
+  return response.text();
 };
 
 export const getArtists = async () => {
@@ -102,23 +126,49 @@ export const getArtists = async () => {
   return data;
 };
 
-// The checkoutArtworks function is no longer needed as we'll be deleting items individually.
-// export const checkoutArtworks = async (artworkIds) => {
-//   const queryParams = new URLSearchParams();
-//   artworkIds.forEach(id => queryParams.append('artwork_ids', id));
+// Reserves the artwork in the customer's server cart for a limited time.
+export const addCartItem = async (idArtwork) => {
+  const response = await fetch(`${API_BASE_URL}/cart/items`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+      ...authHeaders(),
+    },
+    body: JSON.stringify({ id_artwork: idArtwork }),
+  });
+  if (!response.ok) {
+    const errorText = await response.text();
+    throw new Error(errorText || `HTTP error! status: ${response.status}`);
+  }
+  return response.json();
+};
+
+export const removeCartItem = async (idArtwork) => {
+  const response = await fetch(`${API_BASE_URL}/cart/items/${idArtwork}`, {
+    method: 'DELETE',
+    headers: authHeaders(),
+  });
+  if (!response.ok) {
+    const errorText = await response.text();
+    throw new Error(errorText || `HTTP error! status: ${response.status}`);
+  }
+  return response.json();
+};
 
-//   const response = await fetch(`${API_BASE_URL}/artworks/bulk?${queryParams.toString()}`, {
-//     method: 'DELETE',
-//     headers: {
-//       'Content-Type': 'application/json',
-//     },
-//     // DELETE requests typically do not have a body
-//     // body: JSON.stringify({ artwork_ids: artworkIds }),
-//   });
-//   if (!response.ok) {
-//     const errorText = await response.text();
-//     console.error(`Checkout API error: HTTP status ${response.status}, message: ${errorText}`);
-//     throw new Error(`HTTP error! status: ${response.status}, message: ${errorText}`);
-//   }
-//   return response.json();
-// };
\ No newline at end of file
+// Places one order for the whole cart on the server; nothing is bought if any artwork is gone.
+export const checkoutArtworks = async (artworkIds) => {
+  const response = await fetch(`${API_BASE_URL}/checkout`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+      ...authHeaders(),
+    },
+    body: JSON.stringify({ artwork_ids: artworkIds }),
+  });
+  if (!response.ok) {
+    const errorText = await response.text();
+    console.error(`Checkout API error: HTTP status ${response.status}, message: ${errorText}`);
+    throw new Error(errorText || `HTTP error! status: ${response.status}`);
+  }
+  return response.json();
+};
\ No newline at end of file
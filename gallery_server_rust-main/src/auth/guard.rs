@@ -0,0 +1,151 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{forward_ready, ResourceDef, Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    Error, HttpMessage, HttpResponse,
+};
+use crate::auth::principal::{Credential, Principal};
+use crate::models::role::Role;
+
+pub const ADMIN: &[Role] = &[Role::Admin];
+pub const STAFF: &[Role] = &[Role::Admin, Role::Staff];
+pub const ARTIST: &[Role] = &[Role::Artist];
+
+/// Who may call a route.
+#[derive(Debug, Clone, Copy)]
+pub enum Access {
+    Public,
+    Authenticated,
+    Roles(&'static [Role]),
+}
+
+#[derive(Clone)]
+struct Rule {
+    method: Method,
+    path: ResourceDef,
+    access: Access,
+}
+
+/// Access rules for one scope, declared next to the scope in `routes/*`.
+/// Reads (GET/HEAD) and writes get a default each; `route` overrides a single
+/// method + path pattern (full path, e.g. "/customers/{customer_id}").
+/// `resource` names the API-key scopes (`<resource>:read` / `<resource>:write`)
+/// that open the non-public routes of this scope to API keys.
+#[derive(Clone)]
+pub struct ScopeGuard {
+    resource: &'static str,
+    reads: Access,
+    writes: Access,
+    rules: Vec<Rule>,
+}
+
+impl ScopeGuard {
+    pub fn new(resource: &'static str, reads: Access, writes: Access) -> Self {
+        ScopeGuard { resource, reads, writes, rules: Vec::new() }
+    }
+
+    pub fn route(mut self, method: Method, path: &str, access: Access) -> Self {
+        self.rules.push(Rule { method, path: ResourceDef::new(path), access });
+        self
+    }
+
+    fn access_for(&self, req: &ServiceRequest) -> Access {
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| rule.method == req.method() && rule.path.is_match(req.path()))
+        {
+            return rule.access;
+        }
+        if req.method() == Method::GET || req.method() == Method::HEAD {
+            self.reads
+        } else {
+            self.writes
+        }
+    }
+
+    fn check(&self, req: &ServiceRequest) -> Result<(), HttpResponse> {
+        if req.method() == Method::OPTIONS {
+            return Ok(());
+        }
+        let extensions = req.extensions();
+        let principal = extensions.get::<Principal>();
+        match (self.access_for(req), principal) {
+            (Access::Public, _) => Ok(()),
+            (_, None) => Err(HttpResponse::Unauthorized().body("Authentication required")),
+            (access, Some(Principal { credential: Credential::ApiKey { id_api_key, scopes }, .. })) => {
+                self.check_api_key(req, access, id_api_key, scopes)
+            }
+            (Access::Authenticated, Some(_)) => Ok(()),
+            (Access::Roles(roles), Some(principal)) if roles.contains(&principal.role) => Ok(()),
+            (Access::Roles(roles), Some(principal)) => Err(HttpResponse::Forbidden().body(format!(
+                "Forbidden: this action requires one of the roles [{}], but you are signed in as '{}'",
+                roles.iter().map(Role::as_str).collect::<Vec<_>>().join(", "),
+                principal.role
+            ))),
+        }
+    }
+
+    fn check_api_key(&self, req: &ServiceRequest, access: Access, id_api_key: &str, scopes: &[String]) -> Result<(), HttpResponse> {
+        // Keys act as staff at most; admin-only routes stay closed to them.
+        if let Access::Roles(roles) = access {
+            if !roles.contains(&Role::Staff) {
+                return Err(HttpResponse::Forbidden().body("Forbidden: this action is not available to API keys"));
+            }
+        }
+        let action = if req.method() == Method::GET || req.method() == Method::HEAD { "read" } else { "write" };
+        let required = format!("{}:{}", self.resource, action);
+        if scopes.contains(&required) {
+            if action == "write" {
+                println!("🗝️ API key {} {} {}", id_api_key, req.method(), req.path());
+            }
+            Ok(())
+        } else {
+            Err(HttpResponse::Forbidden().body(format!("Forbidden: this API key lacks the '{}' scope", required)))
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ScopeGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ScopeGuardMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ScopeGuardMiddleware { service, guard: Rc::new(self.clone()) }))
+    }
+}
+
+pub struct ScopeGuardMiddleware<S> {
+    service: S,
+    guard: Rc<ScopeGuard>,
+}
+
+impl<S, B> Service<ServiceRequest> for ScopeGuardMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Err(denied) = self.guard.check(&req) {
+            return Box::pin(ready(Ok(req.into_response(denied).map_into_right_body())));
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
@@ -0,0 +1,59 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error, HttpMessage, HttpResponse,
+};
+use sqlx::SqlitePool;
+use crate::controllers::{api_key_controller, session_controller};
+
+enum Presented {
+    Session(String),
+    ApiKey(String),
+}
+
+/// Resolves the `Authorization` header (`Bearer <session token>` or
+/// `ApiKey <key>`) into a `Principal` on the request. Credentials that are
+/// present but unknown, revoked or expired are rejected outright; which routes
+/// need a principal at all is decided by the `ScopeGuard` of each scope.
+pub async fn authenticate(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let presented = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            if let Some(token) = value.strip_prefix("Bearer ") {
+                Some(Presented::Session(token.trim().to_string()))
+            } else {
+                value.strip_prefix("ApiKey ").map(|key| Presented::ApiKey(key.trim().to_string()))
+            }
+        });
+
+    if let Some(presented) = presented {
+        let pool = match req.app_data::<web::Data<SqlitePool>>() {
+            Some(pool) => pool.clone(),
+            None => return Ok(req.into_response(HttpResponse::InternalServerError().body("Database pool not configured"))),
+        };
+
+        let found = match &presented {
+            Presented::Session(token) => session_controller::find_session(&pool, token).await,
+            Presented::ApiKey(key) => api_key_controller::find_api_key(&pool, key).await,
+        };
+        match found {
+            Ok(Some(principal)) => {
+                req.extensions_mut().insert(principal);
+            }
+            Ok(None) => {
+                let message = match presented {
+                    Presented::Session(_) => "Invalid or expired token",
+                    Presented::ApiKey(_) => "Invalid, revoked or expired API key",
+                };
+                return Ok(req.into_response(HttpResponse::Unauthorized().body(message)));
+            }
+            Err(e) => return Ok(req.into_response(HttpResponse::InternalServerError().body(format!("Database error: {}", e)))),
+        }
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_boxed_body)
+}
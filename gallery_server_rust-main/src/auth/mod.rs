@@ -0,0 +1,5 @@
+pub mod guard;
+pub mod middleware;
+pub mod password;
+pub mod principal;
+pub mod token;
@@ -0,0 +1,27 @@
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// A hash of no one's password, made with the same parameters as `hash_password`.
+const DUMMY_PASSWORD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$ZOs0JhkGqivcZasRndb6Rg$XQE+yVpCe2pz0Wq3foegCBDRg/biQlVmnUW+ER5saUc";
+
+/// Hashes a plain-text password into a PHC string (argon2id, random salt).
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default().hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+/// Checks a plain-text password against a stored PHC string.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed) => Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Spends the time of a real check when there is no hash to check against, so a
+/// failed login does not reveal whether the account exists.
+pub fn verify_dummy_password(password: &str) {
+    verify_password(password, DUMMY_PASSWORD_HASH);
+}
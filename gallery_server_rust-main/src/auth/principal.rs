@@ -0,0 +1,55 @@
+use actix_web::{dev::Payload, error::ErrorUnauthorized, Error, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+use crate::models::role::Role;
+
+/// How the caller proved who they are.
+#[derive(Debug, Clone)]
+pub enum Credential {
+    Session { token_hash: String },
+    ApiKey { id_api_key: String, scopes: Vec<String> },
+}
+
+/// The authenticated caller, attached to the request by the `authenticate` middleware.
+/// For API keys, `customer_id` is the admin who issued the key and `role` is staff,
+/// narrowed further by the key's scopes in `ScopeGuard`.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub customer_id: String,
+    pub role: Role,
+    pub artist_id: Option<String>,
+    pub credential: Credential,
+}
+
+impl Principal {
+    /// Admins and staff manage the catalog and every customer's orders.
+    pub fn is_staff(&self) -> bool {
+        matches!(self.role, Role::Admin | Role::Staff)
+    }
+
+    /// Whether the caller may act on data belonging to `customer_id`.
+    pub fn can_access_customer(&self, customer_id: &str) -> bool {
+        self.is_staff() || self.customer_id == customer_id
+    }
+
+    /// The artist whose works this caller manages, if they sign in with the artist role.
+    pub fn managed_artist(&self) -> Option<&str> {
+        match self.role {
+            Role::Artist => self.artist_id.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl FromRequest for Principal {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<Principal>()
+                .cloned()
+                .ok_or_else(|| ErrorUnauthorized("Authentication required")),
+        )
+    }
+}
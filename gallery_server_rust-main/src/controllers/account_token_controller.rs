@@ -0,0 +1,81 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+use crate::auth::token;
+use crate::models::account_token::TokenPurpose;
+
+pub async fn init_account_tokens_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating account_tokens table if not exists...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS account_tokens (
+            token_hash TEXT PRIMARY KEY NOT NULL,
+            customer_id TEXT NOT NULL,
+            purpose TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            used_at TEXT,
+            FOREIGN KEY (customer_id) REFERENCES customers(customer_id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ account_tokens table ready");
+
+    Ok(())
+}
+
+/// Issues a new single-use token for the customer, revoking any earlier unused
+/// token with the same purpose. Returns the plain token, which is never stored.
+pub async fn issue_token(pool: &SqlitePool, customer_id: &str, purpose: TokenPurpose, ttl: Duration) -> Result<String, sqlx::Error> {
+    let plain_token = token::generate_token();
+    let now = Utc::now();
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM account_tokens WHERE customer_id = ? AND purpose = ? AND used_at IS NULL")
+        .bind(customer_id)
+        .bind(purpose)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO account_tokens (token_hash, customer_id, purpose, created_at, expires_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(token::hash_token(&plain_token))
+        .bind(customer_id)
+        .bind(purpose)
+        .bind(now)
+        .bind(now + ttl)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(plain_token)
+}
+
+/// Marks a token as used inside the caller's transaction and returns its
+/// customer id. Unknown, expired, already used or wrong-purpose tokens give `None`.
+pub async fn consume_token(tx: &mut Transaction<'_, Sqlite>, plain_token: &str, purpose: TokenPurpose) -> Result<Option<String>, sqlx::Error> {
+    let token_hash = token::hash_token(plain_token);
+    let row = sqlx::query("SELECT customer_id, expires_at FROM account_tokens WHERE token_hash = ? AND purpose = ? AND used_at IS NULL")
+        .bind(&token_hash)
+        .bind(purpose)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let expires_at: DateTime<Utc> = row.get("expires_at");
+    if expires_at <= Utc::now() {
+        return Ok(None);
+    }
+
+    let result = sqlx::query("UPDATE account_tokens SET used_at = ? WHERE token_hash = ? AND used_at IS NULL")
+        .bind(Utc::now())
+        .bind(&token_hash)
+        .execute(&mut **tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(row.get("customer_id")))
+}
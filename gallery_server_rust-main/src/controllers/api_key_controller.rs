@@ -0,0 +1,166 @@
+use actix_web::{delete, get, http::header, post, web, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use crate::auth::principal::{Credential, Principal};
+use crate::auth::token;
+use crate::models::api_key::{ApiKey, ApiScopes, CreatedApiKey, NewApiKey, API_SCOPES};
+use crate::models::role::Role;
+
+pub const API_KEY_PREFIX: &str = "gk_";
+pub const DEFAULT_API_KEY_TTL_DAYS: i64 = 90;
+
+pub async fn init_api_keys_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating api_keys table if not exists...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id_api_key TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            key_prefix TEXT NOT NULL,
+            scopes TEXT NOT NULL,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            last_used_at TEXT,
+            revoked_at TEXT,
+            FOREIGN KEY (created_by) REFERENCES customers(customer_id)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ api_keys table ready");
+
+    Ok(())
+}
+
+/// Resolves a plain API key into a principal, stamping its last use.
+/// Unknown, revoked and expired keys give `None`.
+pub async fn find_api_key(pool: &SqlitePool, plain_key: &str) -> Result<Option<Principal>, sqlx::Error> {
+    let api_key = sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys WHERE key_hash = ?")
+        .bind(token::hash_token(plain_key))
+        .fetch_optional(pool)
+        .await?;
+
+    let Some(api_key) = api_key else {
+        return Ok(None);
+    };
+    let now = Utc::now();
+    if api_key.revoked_at.is_some() || api_key.expires_at <= now {
+        return Ok(None);
+    }
+
+    sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE id_api_key = ?")
+        .bind(now)
+        .bind(&api_key.id_api_key)
+        .execute(pool)
+        .await?;
+
+    Ok(Some(Principal {
+        customer_id: api_key.created_by,
+        role: Role::Staff,
+        artist_id: None,
+        credential: Credential::ApiKey {
+            id_api_key: api_key.id_api_key,
+            scopes: api_key.scopes.0,
+        },
+    }))
+}
+
+#[get("/")]
+pub async fn get_api_keys(pool: web::Data<SqlitePool>) -> impl Responder {
+    match sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys ORDER BY created_at DESC")
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(api_keys) => HttpResponse::Ok().json(api_keys),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/")]
+pub async fn create_api_key(pool: web::Data<SqlitePool>, principal: Principal, new_key: web::Json<NewApiKey>) -> impl Responder {
+    if new_key.name.trim().is_empty() {
+        return HttpResponse::BadRequest().body("API key name is required");
+    }
+    if new_key.scopes.is_empty() {
+        return HttpResponse::BadRequest().body("At least one scope is required");
+    }
+    if let Some(unknown) = new_key.scopes.iter().find(|scope| !API_SCOPES.contains(&scope.as_str())) {
+        return HttpResponse::BadRequest().body(format!("Unknown scope '{}'. Valid scopes: {}", unknown, API_SCOPES.join(", ")));
+    }
+
+    let now = Utc::now();
+    let expires_at = new_key.expires_at.unwrap_or(now + Duration::days(DEFAULT_API_KEY_TTL_DAYS));
+    if expires_at <= now {
+        return HttpResponse::BadRequest().body("expires_at must be in the future");
+    }
+
+    let plain_key = format!("{}{}", API_KEY_PREFIX, token::generate_token());
+    let mut scopes = new_key.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let api_key = ApiKey {
+        id_api_key: Uuid::new_v4().to_string(),
+        name: new_key.name.trim().to_string(),
+        key_prefix: plain_key[..API_KEY_PREFIX.len() + 8].to_string(),
+        scopes: ApiScopes(scopes),
+        created_by: principal.customer_id.clone(),
+        created_at: now,
+        expires_at,
+        last_used_at: None,
+        revoked_at: None,
+    };
+
+    match sqlx::query(
+        r#"
+        INSERT INTO api_keys (id_api_key, name, key_hash, key_prefix, scopes, created_by, created_at, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&api_key.id_api_key)
+    .bind(&api_key.name)
+    .bind(token::hash_token(&plain_key))
+    .bind(&api_key.key_prefix)
+    .bind(api_key.scopes.0.join(" "))
+    .bind(&api_key.created_by)
+    .bind(api_key.created_at)
+    .bind(api_key.expires_at)
+    .execute(&**pool)
+    .await
+    {
+        Ok(_) => {
+            println!("🗝️ {} created API key {} ({})", principal.customer_id, api_key.name, api_key.scopes.0.join(" "));
+            // The plain key is shown once; no-store also keeps it out of idempotency replays.
+            HttpResponse::Created()
+                .insert_header(header::CacheControl(vec![header::CacheDirective::NoStore]))
+                .json(CreatedApiKey { key: plain_key, api_key })
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[delete("/{id_api_key}")]
+pub async fn revoke_api_key(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_api_key = path.into_inner();
+
+    match sqlx::query("UPDATE api_keys SET revoked_at = ? WHERE id_api_key = ? AND revoked_at IS NULL")
+        .bind(Utc::now())
+        .bind(&id_api_key)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                println!("🗝️ {} revoked API key {}", principal.customer_id, id_api_key);
+                HttpResponse::Ok().body("API key revoked")
+            } else {
+                HttpResponse::NotFound().body("API key not found or already revoked")
+            }
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
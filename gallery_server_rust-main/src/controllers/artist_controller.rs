@@ -51,7 +51,7 @@ pub async fn create_artist(pool: web::Data<SqlitePool>, artist: web::Json<Artist
     .bind(&id)
     .bind(&artist.first_name)
     .bind(&artist.last_name)
-    .bind(&artist.birth_year)
+    .bind(artist.birth_year)
     .execute(&**pool)
     .await
     {
@@ -110,7 +110,7 @@ pub async fn update_artist(
             )
             .bind(&updated.first_name)
             .bind(&updated.last_name)
-            .bind(&updated.birth_year)
+            .bind(updated.birth_year)
             .bind(&artist_id)
             .execute(&**pool)
             .await
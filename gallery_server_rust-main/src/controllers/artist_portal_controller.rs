@@ -0,0 +1,194 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::models::artist_portal::{ArtistSale, ArtworkEdit, ArtworkSubmission};
+use crate::models::artwork::{Artwork, ArtworkStatus};
+use crate::models::money::Money;
+
+// Every query below is filtered by the caller's own artist id, so an artist can
+// neither see nor change another artist's rows; foreign ids simply come back as 404.
+
+fn artist_of(principal: &Principal) -> Result<&str, HttpResponse> {
+    principal
+        .managed_artist()
+        .ok_or_else(|| HttpResponse::Forbidden().body("Forbidden: this account is not linked to an artist"))
+}
+
+#[get("/artworks")]
+pub async fn get_my_artworks(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    let artist_id = match artist_of(&principal) {
+        Ok(artist_id) => artist_id,
+        Err(denied) => return denied,
+    };
+    match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS WHERE id_artist = ? ORDER BY is_draft DESC, title")
+        .bind(artist_id)
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(artworks) => HttpResponse::Ok().json(artworks),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/artworks")]
+pub async fn submit_artwork(pool: web::Data<SqlitePool>, principal: Principal, submission: web::Json<ArtworkSubmission>) -> impl Responder {
+    let artist_id = match artist_of(&principal) {
+        Ok(artist_id) => artist_id,
+        Err(denied) => return denied,
+    };
+    if submission.price.is_negative() {
+        return HttpResponse::BadRequest().body("Price cannot be negative");
+    }
+
+    let artwork = Artwork {
+        id_artwork: Some(Uuid::new_v4().to_string()),
+        title: submission.title.clone(),
+        description: submission.description.clone(),
+        year_created: submission.year_created,
+        price: submission.price.clone(),
+        id_artist: artist_id.to_string(),
+        art_type: submission.art_type.clone(),
+        is_draft: true,
+        status: ArtworkStatus::Available,
+        // Tax settings are the gallery's to choose when it publishes the work.
+        id_tax_rate: None,
+        price_includes_tax: true,
+        width_cm: None,
+        height_cm: None,
+        depth_cm: None,
+        weight_kg: None,
+    };
+    match sqlx::query(
+        r#"
+        INSERT INTO ARTWORKS (id_artwork, title, description, year_created, price_minor, currency, id_artist, art_type, is_draft)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&artwork.id_artwork)
+    .bind(&artwork.title)
+    .bind(&artwork.description)
+    .bind(artwork.year_created)
+    .bind(artwork.price.minor)
+    .bind(&artwork.price.currency)
+    .bind(&artwork.id_artist)
+    .bind(&artwork.art_type)
+    .bind(artwork.is_draft)
+    .execute(&**pool)
+    .await
+    {
+        Ok(_) => {
+            println!("🎨 Artist {} submitted draft {}", artist_id, artwork.title);
+            HttpResponse::Created().json(artwork)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[put("/artworks/{id_artwork}")]
+pub async fn edit_my_artwork(
+    pool: web::Data<SqlitePool>,
+    principal: Principal,
+    path: web::Path<String>,
+    edit: web::Json<ArtworkEdit>,
+) -> impl Responder {
+    let artist_id = match artist_of(&principal) {
+        Ok(artist_id) => artist_id,
+        Err(denied) => return denied,
+    };
+    let id_artwork = path.into_inner();
+
+    let existing = match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS WHERE id_artwork = ? AND id_artist = ?")
+        .bind(&id_artwork)
+        .bind(artist_id)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(artwork)) => artwork,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let touches_listing = edit.title.is_some() || edit.year_created.is_some() || edit.price.is_some() || edit.art_type.is_some();
+    if !existing.is_draft && touches_listing {
+        return HttpResponse::Conflict().body("Published artworks only accept description changes; ask the gallery staff for anything else");
+    }
+    if edit.price.as_ref().is_some_and(Money::is_negative) {
+        return HttpResponse::BadRequest().body("Price cannot be negative");
+    }
+
+    let updated = Artwork {
+        title: edit.title.clone().unwrap_or(existing.title),
+        description: edit.description.clone().unwrap_or(existing.description),
+        year_created: edit.year_created.unwrap_or(existing.year_created),
+        price: edit.price.clone().unwrap_or(existing.price),
+        art_type: edit.art_type.clone().unwrap_or(existing.art_type),
+        ..existing
+    };
+    match sqlx::query(
+        r#"
+        UPDATE ARTWORKS SET title = ?, description = ?, year_created = ?, price_minor = ?, currency = ?, art_type = ?
+        WHERE id_artwork = ? AND id_artist = ?
+        "#
+    )
+    .bind(&updated.title)
+    .bind(&updated.description)
+    .bind(updated.year_created)
+    .bind(updated.price.minor)
+    .bind(&updated.price.currency)
+    .bind(&updated.art_type)
+    .bind(&id_artwork)
+    .bind(artist_id)
+    .execute(&**pool)
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json(updated),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[delete("/artworks/{id_artwork}")]
+pub async fn withdraw_draft(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let artist_id = match artist_of(&principal) {
+        Ok(artist_id) => artist_id,
+        Err(denied) => return denied,
+    };
+    let id_artwork = path.into_inner();
+
+    // Published works may already be on orders; only staff can remove those.
+    match sqlx::query("DELETE FROM ARTWORKS WHERE id_artwork = ? AND id_artist = ? AND is_draft = 1")
+        .bind(&id_artwork)
+        .bind(artist_id)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => HttpResponse::Ok().body(format!("Draft {} withdrawn", id_artwork)),
+        Ok(_) => HttpResponse::NotFound().body(format!("Draft artwork with id {} not found", id_artwork)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/sales")]
+pub async fn get_my_sales(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    let artist_id = match artist_of(&principal) {
+        Ok(artist_id) => artist_id,
+        Err(denied) => return denied,
+    };
+    match sqlx::query_as::<_, ArtistSale>(
+        r#"
+        SELECT o.id_order, o.order_date, aio.id_artwork, aio.title, aio.amount, aio.price_minor, aio.currency
+        FROM artworks_in_order aio
+        JOIN ORDERS o ON o.id_order = aio.id_order
+        WHERE aio.id_artist = ? AND o.status NOT IN ('cancelled', 'refunded')
+          AND NOT EXISTS (SELECT 1 FROM order_returns r WHERE r.id_artwork_in_order = aio.id_artwork_in_order AND r.status = 'refunded')
+        ORDER BY o.order_date DESC, o.id_order
+        "#
+    )
+    .bind(artist_id)
+    .fetch_all(&**pool)
+    .await
+    {
+        Ok(sales) => HttpResponse::Ok().json(sales),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
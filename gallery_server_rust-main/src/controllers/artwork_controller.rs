@@ -1,7 +1,17 @@
 use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sqlx::{sqlite::SqlitePool, Error};
+use sqlx::{sqlite::{SqliteConnection, SqlitePool}, Error};
 use uuid::Uuid;
-use crate::models::artwork::Artwork;
+use crate::auth::principal::Principal;
+use crate::controllers::{cart_controller, init_db};
+use crate::models::artwork::{Artwork, ArtworkListQuery, ArtworkStatus, ArtworkStatusUpdate};
+use crate::models::money::CATALOG_CURRENCY;
+
+/// Outcome of asking an artwork to move to another status.
+pub enum Transition {
+    Moved(ArtworkStatus),
+    Refused(ArtworkStatus),
+    NotFound,
+}
 
 pub async fn init_artwork_table(pool: &SqlitePool) -> Result<(), Error> {
     sqlx::query(
@@ -11,21 +21,103 @@ pub async fn init_artwork_table(pool: &SqlitePool) -> Result<(), Error> {
             title TEXT NOT NULL,
             description TEXT NOT NULL,
             year_created INTEGER NOT NULL,
-            price REAL NOT NULL,
+            price_minor INTEGER NOT NULL,
+            currency TEXT NOT NULL DEFAULT 'ILS',
             id_artist TEXT NOT NULL,
             art_type TEXT NOT NULL,
+            is_draft BOOLEAN NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'available',
+            id_tax_rate TEXT REFERENCES tax_rates(id_tax_rate) ON DELETE SET NULL,
+            price_includes_tax BOOLEAN NOT NULL DEFAULT 1,
+            width_cm REAL,
+            height_cm REAL,
+            depth_cm REAL,
+            weight_kg REAL,
             FOREIGN KEY (id_artist) REFERENCES artists(artist_id) ON DELETE CASCADE
         );
         "#,
     )
     .execute(pool)
     .await?;
+    init_db::add_column_if_missing(pool, "ARTWORKS", "is_draft", "BOOLEAN NOT NULL DEFAULT 0").await?;
+    if init_db::add_column_if_missing(pool, "ARTWORKS", "status", "TEXT NOT NULL DEFAULT 'available'").await? {
+        // Pieces that are already on an order were sold before statuses existed.
+        sqlx::query("UPDATE ARTWORKS SET status = ? WHERE id_artwork IN (SELECT id_artwork FROM artworks_in_order)")
+            .bind(ArtworkStatus::Sold)
+            .execute(pool)
+            .await?;
+    }
+    init_db::add_column_if_missing(pool, "ARTWORKS", "id_tax_rate", "TEXT REFERENCES tax_rates(id_tax_rate) ON DELETE SET NULL").await?;
+    init_db::add_column_if_missing(pool, "ARTWORKS", "price_includes_tax", "BOOLEAN NOT NULL DEFAULT 1").await?;
+    for measurement in ["width_cm", "height_cm", "depth_cm", "weight_kg"] {
+        init_db::add_column_if_missing(pool, "ARTWORKS", measurement, "REAL").await?;
+    }
+    init_db::add_column_if_missing(pool, "ARTWORKS", "currency", &format!("TEXT NOT NULL DEFAULT '{}'", CATALOG_CURRENCY)).await?;
+    if init_db::add_column_if_missing(pool, "ARTWORKS", "price_minor", "INTEGER NOT NULL DEFAULT 0").await?
+        && init_db::has_column(pool, "ARTWORKS", "price").await?
+    {
+        // Legacy REAL prices were in the catalog currency, which has two minor digits.
+        let migrated = sqlx::query("UPDATE ARTWORKS SET price_minor = CAST(ROUND(price * 100) AS INTEGER), currency = ?")
+            .bind(CATALOG_CURRENCY)
+            .execute(pool)
+            .await?;
+        println!("💰 Migrated {} artwork prices to minor units", migrated.rows_affected());
+        init_db::drop_column_if_exists(pool, "ARTWORKS", "price").await?;
+    }
+    Ok(())
+}
+
+/// Staff see drafts in the catalog; everyone else only published works.
+fn include_drafts(principal: &Option<Principal>) -> bool {
+    principal.as_ref().is_some_and(Principal::is_staff)
+}
+
+/// Sold and archived works are listed only when staff ask for them.
+fn include_unavailable(principal: &Option<Principal>, query: &ArtworkListQuery) -> bool {
+    query.include_unavailable && include_drafts(principal)
+}
+
+/// Moves an artwork to `next` when its lifecycle allows it. Runs on a pool
+/// connection or inside the caller's transaction.
+pub async fn transition_artwork(conn: &mut SqliteConnection, id_artwork: &str, next: ArtworkStatus) -> Result<Transition, Error> {
+    let Some(current) = sqlx::query_scalar::<_, ArtworkStatus>("SELECT status FROM ARTWORKS WHERE id_artwork = ?")
+        .bind(id_artwork)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(Transition::NotFound);
+    };
+    if !current.can_transition_to(next) {
+        return Ok(Transition::Refused(current));
+    }
+
+    // Guarded by the status we read, so a concurrent change cannot be overwritten.
+    let result = sqlx::query("UPDATE ARTWORKS SET status = ? WHERE id_artwork = ? AND status = ?")
+        .bind(next)
+        .bind(id_artwork)
+        .bind(current)
+        .execute(&mut *conn)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(Transition::Refused(current));
+    }
+    Ok(Transition::Moved(current))
+}
+
+/// Puts a piece back on sale after its order or order line is cancelled.
+/// Pieces staff have since moved elsewhere (e.g. archived) are left alone.
+pub async fn release_artwork(conn: &mut SqliteConnection, id_artwork: &str) -> Result<(), Error> {
+    if let Transition::Refused(current) = transition_artwork(conn, id_artwork, ArtworkStatus::Available).await? {
+        println!("⚠️ Artwork {} stays {} after its order line was removed", id_artwork, current);
+    }
     Ok(())
 }
 
 #[get("/")]
-pub async fn get_all_artworks(pool: web::Data<SqlitePool>) -> impl Responder {
-    match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS")
+pub async fn get_all_artworks(pool: web::Data<SqlitePool>, principal: Option<Principal>, query: web::Query<ArtworkListQuery>) -> impl Responder {
+    match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS WHERE (? OR is_draft = 0) AND (? OR status NOT IN ('sold', 'archived'))")
+        .bind(include_drafts(&principal))
+        .bind(include_unavailable(&principal, &query))
         .fetch_all(&**pool)
         .await
     {
@@ -38,10 +130,11 @@ pub async fn get_all_artworks(pool: web::Data<SqlitePool>) -> impl Responder {
 }
 
 #[get("/{id}")]
-pub async fn get_artwork_by_id(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+pub async fn get_artwork_by_id(pool: web::Data<SqlitePool>, principal: Option<Principal>, path: web::Path<String>) -> impl Responder {
     let id_artwork = path.into_inner();
-    match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS WHERE id_artwork = ?")
+    match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS WHERE id_artwork = ? AND (? OR is_draft = 0)")
         .bind(&id_artwork)
+        .bind(include_drafts(&principal))
         .fetch_one(&**pool)
         .await
     {
@@ -56,21 +149,33 @@ pub async fn get_artwork_by_id(pool: web::Data<SqlitePool>, path: web::Path<Stri
 
 #[post("/")]
 pub async fn create_artwork(pool: web::Data<SqlitePool>, mut artwork: web::Json<Artwork>) -> impl Responder {
+    if artwork.price.is_negative() {
+        return HttpResponse::BadRequest().body("Price cannot be negative");
+    }
     let id = Uuid::new_v4().to_string();
     artwork.id_artwork = Some(id.clone());
     let result = sqlx::query(
         r#"
-        INSERT INTO artworks (id_artwork, title, description, year_created, price, id_artist, art_type)
-        VALUES (?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO artworks (id_artwork, title, description, year_created, price_minor, currency, id_artist, art_type, is_draft, id_tax_rate, price_includes_tax,
+            width_cm, height_cm, depth_cm, weight_kg)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(artwork.id_artwork.as_ref().unwrap())
     .bind(&artwork.title)
     .bind(&artwork.description)
-    .bind(&artwork.year_created)
-    .bind(&artwork.price)
+    .bind(artwork.year_created)
+    .bind(artwork.price.minor)
+    .bind(&artwork.price.currency)
     .bind(&artwork.id_artist)
     .bind(&artwork.art_type)
+    .bind(artwork.is_draft)
+    .bind(&artwork.id_tax_rate)
+    .bind(artwork.price_includes_tax)
+    .bind(artwork.width_cm)
+    .bind(artwork.height_cm)
+    .bind(artwork.depth_cm)
+    .bind(artwork.weight_kg)
     .execute(&**pool)
     .await;
 
@@ -80,10 +185,21 @@ pub async fn create_artwork(pool: web::Data<SqlitePool>, mut artwork: web::Json<
             title: artwork.title.clone(),
             description: artwork.description.clone(),
             year_created: artwork.year_created,
-            price: artwork.price,
+            price: artwork.price.clone(),
             id_artist: artwork.id_artist.clone(),
             art_type: artwork.art_type.clone(),
+            is_draft: artwork.is_draft,
+            status: ArtworkStatus::Available,
+            id_tax_rate: artwork.id_tax_rate.clone(),
+            price_includes_tax: artwork.price_includes_tax,
+            width_cm: artwork.width_cm,
+            height_cm: artwork.height_cm,
+            depth_cm: artwork.depth_cm,
+            weight_kg: artwork.weight_kg,
         }),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            HttpResponse::BadRequest().body("Unknown artist or tax rate")
+        }
         Err(e) => {
             eprintln!("Failed to create artwork: {}", e);
             HttpResponse::InternalServerError().body(format!("Database error: {}", e))
@@ -94,17 +210,30 @@ pub async fn create_artwork(pool: web::Data<SqlitePool>, mut artwork: web::Json<
 #[put("/{id}")]
 pub async fn update_artwork(pool: web::Data<SqlitePool>, path: web::Path<String>, artwork: web::Json<Artwork>) -> impl Responder {
     let id_artwork = path.into_inner();
+    if artwork.price.is_negative() {
+        return HttpResponse::BadRequest().body("Price cannot be negative");
+    }
     let result = sqlx::query(
         r#"
-        UPDATE ARTWORKS SET title = ?, description = ?, year_created = ?, price = ?, id_artist = ?, art_type = ? WHERE id_artwork = ?
+        UPDATE ARTWORKS SET title = ?, description = ?, year_created = ?, price_minor = ?, currency = ?, id_artist = ?, art_type = ?, is_draft = ?, id_tax_rate = ?, price_includes_tax = ?,
+            width_cm = ?, height_cm = ?, depth_cm = ?, weight_kg = ?
+        WHERE id_artwork = ?
         "#
     )
     .bind(&artwork.title)
     .bind(&artwork.description)
-    .bind(&artwork.year_created)
-    .bind(&artwork.price)
+    .bind(artwork.year_created)
+    .bind(artwork.price.minor)
+    .bind(&artwork.price.currency)
     .bind(&artwork.id_artist)
     .bind(&artwork.art_type)
+    .bind(artwork.is_draft)
+    .bind(&artwork.id_tax_rate)
+    .bind(artwork.price_includes_tax)
+    .bind(artwork.width_cm)
+    .bind(artwork.height_cm)
+    .bind(artwork.depth_cm)
+    .bind(artwork.weight_kg)
     .bind(&id_artwork)
     .execute(&**pool)
     .await;
@@ -117,6 +246,9 @@ pub async fn update_artwork(pool: web::Data<SqlitePool>, path: web::Path<String>
                 HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork))
             }
         },
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            HttpResponse::BadRequest().body("Unknown artist or tax rate")
+        }
         Err(e) => {
             eprintln!("Failed to update artwork with id {}: {}", id_artwork, e);
             HttpResponse::InternalServerError().body(format!("Database error: {}", e))
@@ -128,41 +260,63 @@ pub async fn update_artwork(pool: web::Data<SqlitePool>, path: web::Path<String>
 pub async fn delete_artwork(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
     let id_artwork = path.into_inner();
 
-    // Delete related artworks in order first
-    match sqlx::query("DELETE FROM artworks_in_order WHERE id_artwork = ?")
+    // Order lines, auctions and offers are the sales record; a piece that appears
+    // in any of them stays in the catalog and is archived instead.
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let referenced = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM artworks_in_order WHERE id_artwork = ?)
+                OR EXISTS (SELECT 1 FROM auctions WHERE id_artwork = ?)
+                OR EXISTS (SELECT 1 FROM offers WHERE id_artwork = ?)
+            "#
+        )
         .bind(&id_artwork)
-        .execute(&**pool)
-        .await
-    {
-        Ok(_) => {
-            // Now delete the artwork
-            match sqlx::query("DELETE FROM ARTWORKS WHERE id_artwork = ?")
-                .bind(&id_artwork)
-                .execute(&**pool)
-                .await
-            {
-                Ok(res) => {
-                    if res.rows_affected() > 0 {
-                        HttpResponse::Ok().body(format!("Artwork with id {} deleted successfully", id_artwork))
-                    } else {
-                        HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork))
-                    }
-                },
-                Err(e) => {
-                    eprintln!("Failed to delete artwork with id {}: {}", id_artwork, e);
-                    HttpResponse::InternalServerError().body(format!("Database error: {}", e))
-                }
-            }
+        .bind(&id_artwork)
+        .bind(&id_artwork)
+        .fetch_one(&mut *tx)
+        .await?;
+        if referenced {
+            return Ok(None);
+        }
+        let deleted = sqlx::query("DELETE FROM ARTWORKS WHERE id_artwork = ?")
+            .bind(&id_artwork)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Some(deleted))
+    }
+    .await;
+
+    match result {
+        Ok(Some(true)) => HttpResponse::Ok().body(format!("Artwork with id {} deleted successfully", id_artwork)),
+        Ok(Some(false)) => HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork)),
+        Ok(None) => HttpResponse::Conflict().body(format!(
+            "Artwork with id {} appears on orders, auctions or offers; set its status to {} instead",
+            id_artwork,
+            ArtworkStatus::Archived
+        )),
+        Err(e) => {
+            eprintln!("Failed to delete artwork with id {}: {}", id_artwork, e);
+            HttpResponse::InternalServerError().body(format!("Database error: {}", e))
         }
-        Err(e) => HttpResponse::InternalServerError().body(format!("Database error while deleting artworks in order: {}", e)),
     }
 }
 
 #[get("/type/{art_type}")]
-pub async fn get_artworks_by_type(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+pub async fn get_artworks_by_type(
+    pool: web::Data<SqlitePool>,
+    principal: Option<Principal>,
+    path: web::Path<String>,
+    query: web::Query<ArtworkListQuery>,
+) -> impl Responder {
     let art_type = path.into_inner();
-    match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS WHERE art_type = ?")
+    match sqlx::query_as::<_, Artwork>("SELECT * FROM ARTWORKS WHERE art_type = ? AND (? OR is_draft = 0) AND (? OR status NOT IN ('sold', 'archived'))")
         .bind(&art_type)
+        .bind(include_drafts(&principal))
+        .bind(include_unavailable(&principal, &query))
         .fetch_all(&**pool)
         .await
     {
@@ -173,3 +327,47 @@ pub async fn get_artworks_by_type(pool: web::Data<SqlitePool>, path: web::Path<S
         }
     }
 }
+
+#[put("/{id}/status")]
+pub async fn update_artwork_status(pool: web::Data<SqlitePool>, path: web::Path<String>, update: web::Json<ArtworkStatusUpdate>) -> impl Responder {
+    let id_artwork = path.into_inner();
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    // Auctions own the pieces they sell; they move them on and off auction themselves.
+    let on_auction = sqlx::query_scalar::<_, ArtworkStatus>("SELECT status FROM ARTWORKS WHERE id_artwork = ?")
+        .bind(&id_artwork)
+        .fetch_optional(&mut *conn)
+        .await
+        .map(|status| status == Some(ArtworkStatus::Auction));
+    match on_auction {
+        Ok(true) => return HttpResponse::Conflict().body("The artwork is on auction; cancel the auction instead"),
+        Ok(false) if update.status == ArtworkStatus::Auction => {
+            return HttpResponse::Conflict().body("Artworks go up for auction through /auctions")
+        }
+        Ok(false) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    let result = async {
+        let transition = transition_artwork(&mut conn, &id_artwork, update.status).await?;
+        // A piece staff take out of reservation by hand leaves the holder's cart.
+        if let Transition::Moved(ArtworkStatus::Reserved) = transition {
+            cart_controller::forget_hold(&mut conn, &id_artwork).await?;
+        }
+        Ok::<_, Error>(transition)
+    }
+    .await;
+    match result {
+        Ok(Transition::Moved(previous)) => {
+            println!("🏷️ Artwork {} moved from {} to {}", id_artwork, previous, update.status);
+            HttpResponse::Ok().body(format!("Artwork with id {} is now {}", id_artwork, update.status))
+        }
+        Ok(Transition::Refused(current)) => HttpResponse::Conflict().body(format!(
+            "Artwork with id {} cannot move from {} to {}",
+            id_artwork, current, update.status
+        )),
+        Ok(Transition::NotFound) => HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
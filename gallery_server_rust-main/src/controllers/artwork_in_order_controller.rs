@@ -1,6 +1,12 @@
 use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sqlx::SqlitePool;
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use crate::auth::principal::Principal;
+use crate::controllers::artwork_controller::{self, Transition};
+use crate::controllers::{customer_controller, init_db, payment_controller, tax_controller};
+use crate::models::artwork::ArtworkStatus;
+use crate::models::money::{Money, CATALOG_CURRENCY};
 use crate::models::artwork_in_order::{ArtworkInOrder};
+use crate::models::order::OrderStatus;
 use uuid::Uuid;
 
 pub async fn init_artworks_in_order_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
@@ -12,6 +18,16 @@ pub async fn init_artworks_in_order_table(pool: &SqlitePool) -> Result<(), sqlx:
             id_order TEXT NOT NULL,
             id_artwork TEXT NOT NULL,
             amount INTEGER NOT NULL,
+            price_minor INTEGER NOT NULL DEFAULT 0,
+            currency TEXT NOT NULL DEFAULT 'ILS',
+            title TEXT NOT NULL DEFAULT '',
+            id_artist TEXT NOT NULL DEFAULT '',
+            artist_name TEXT NOT NULL DEFAULT '',
+            discount_minor INTEGER NOT NULL DEFAULT 0,
+            tax_rate_basis_points INTEGER NOT NULL DEFAULT 0,
+            price_includes_tax BOOLEAN NOT NULL DEFAULT 1,
+            tax_minor INTEGER NOT NULL DEFAULT 0,
+            total_minor INTEGER NOT NULL DEFAULT 0,
             FOREIGN KEY (id_order) REFERENCES orders(id_order),
             FOREIGN KEY (id_artwork) REFERENCES artworks(id_artwork)
         )
@@ -19,20 +35,147 @@ pub async fn init_artworks_in_order_table(pool: &SqlitePool) -> Result<(), sqlx:
     )
     .execute(pool)
     .await?;
+    init_db::add_column_if_missing(pool, "artworks_in_order", "currency", "TEXT NOT NULL DEFAULT 'ILS'").await?;
+    init_db::add_column_if_missing(pool, "artworks_in_order", "title", "TEXT NOT NULL DEFAULT ''").await?;
+    init_db::add_column_if_missing(pool, "artworks_in_order", "id_artist", "TEXT NOT NULL DEFAULT ''").await?;
+    init_db::add_column_if_missing(pool, "artworks_in_order", "artist_name", "TEXT NOT NULL DEFAULT ''").await?;
+    init_db::add_column_if_missing(pool, "artworks_in_order", "discount_minor", "INTEGER NOT NULL DEFAULT 0").await?;
+    // Lines from before tax was tracked keep a zero rate: no tax was recorded on them.
+    init_db::add_column_if_missing(pool, "artworks_in_order", "tax_rate_basis_points", "INTEGER NOT NULL DEFAULT 0").await?;
+    init_db::add_column_if_missing(pool, "artworks_in_order", "price_includes_tax", "BOOLEAN NOT NULL DEFAULT 1").await?;
+    init_db::add_column_if_missing(pool, "artworks_in_order", "tax_minor", "INTEGER NOT NULL DEFAULT 0").await?;
+    if init_db::add_column_if_missing(pool, "artworks_in_order", "price_minor", "INTEGER NOT NULL DEFAULT 0").await? {
+        if init_db::has_column(pool, "artworks_in_order", "unit_price").await? {
+            // Snapshots taken while prices were REAL, all in the two-digit catalog currency.
+            let migrated = sqlx::query("UPDATE artworks_in_order SET price_minor = CAST(ROUND(unit_price * 100) AS INTEGER)")
+                .execute(pool)
+                .await?;
+            println!("💰 Migrated {} order line prices to minor units", migrated.rows_affected());
+            init_db::drop_column_if_exists(pool, "artworks_in_order", "unit_price").await?;
+        } else {
+            // Older lines never recorded what was paid; today's catalog is the best record left.
+            let backfilled = sqlx::query(&format!("UPDATE artworks_in_order SET {}", SNAPSHOT_ASSIGNMENTS))
+                .bind(CATALOG_CURRENCY)
+                .execute(pool)
+                .await?;
+            println!("🧾 Snapshotted prices on {} existing order lines", backfilled.rows_affected());
+        }
+    }
+    if init_db::add_column_if_missing(pool, "artworks_in_order", "total_minor", "INTEGER NOT NULL DEFAULT 0").await? {
+        let backfilled = sqlx::query("UPDATE artworks_in_order SET total_minor = price_minor * amount - discount_minor")
+            .execute(pool)
+            .await?;
+        println!("🧾 Backfilled totals on {} existing order lines", backfilled.rows_affected());
+    }
     println!("✅ artworks_in_order table ready");
     
     Ok(())
 }
 
+// Copies the artwork's current price, title and artist onto an order line.
+// The one placeholder is the fallback currency for lines whose artwork is gone.
+const SNAPSHOT_ASSIGNMENTS: &str = r#"
+    price_minor = COALESCE((SELECT a.price_minor FROM ARTWORKS a WHERE a.id_artwork = artworks_in_order.id_artwork), 0),
+    currency = COALESCE((SELECT a.currency FROM ARTWORKS a WHERE a.id_artwork = artworks_in_order.id_artwork), ?),
+    title = COALESCE((SELECT a.title FROM ARTWORKS a WHERE a.id_artwork = artworks_in_order.id_artwork), ''),
+    id_artist = COALESCE((SELECT a.id_artist FROM ARTWORKS a WHERE a.id_artwork = artworks_in_order.id_artwork), ''),
+    artist_name = COALESCE((
+        SELECT ar.first_name || ' ' || ar.last_name
+        FROM ARTWORKS a JOIN artists ar ON ar.artist_id = a.id_artist
+        WHERE a.id_artwork = artworks_in_order.id_artwork
+    ), '')
+"#;
+
+/// Adds an order line carrying a snapshot of the artwork as it is now, so later
+/// catalog edits never rewrite what the customer bought.
+pub async fn insert_order_line(conn: &mut SqliteConnection, id_artwork_in_order: &str, id_order: &str, id_artwork: &str, amount: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO artworks_in_order (id_artwork_in_order, id_order, id_artwork, amount) VALUES (?, ?, ?, ?)")
+        .bind(id_artwork_in_order)
+        .bind(id_order)
+        .bind(id_artwork)
+        .bind(amount)
+        .execute(&mut *conn)
+        .await?;
+    resnapshot_order_line(conn, id_artwork_in_order).await
+}
+
+/// Prices a new order line at an amount agreed with the customer, such as a
+/// winning bid or an accepted offer, in place of the catalog price.
+pub async fn set_line_price(conn: &mut SqliteConnection, id_artwork_in_order: &str, price: &Money) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE artworks_in_order SET price_minor = ?, currency = ? WHERE id_artwork_in_order = ?")
+        .bind(price.minor)
+        .bind(&price.currency)
+        .bind(id_artwork_in_order)
+        .execute(&mut *conn)
+        .await?;
+    tax_controller::recompute_line_tax(conn, id_artwork_in_order).await
+}
+
+async fn resnapshot_order_line(conn: &mut SqliteConnection, id_artwork_in_order: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("UPDATE artworks_in_order SET {} WHERE id_artwork_in_order = ?", SNAPSHOT_ASSIGNMENTS))
+        .bind(CATALOG_CURRENCY)
+        .bind(id_artwork_in_order)
+        .execute(&mut *conn)
+        .await?;
+    tax_controller::snapshot_line_tax(conn, id_artwork_in_order).await
+}
+
+// Every artwork is one of a kind, so a line is always for exactly one piece;
+// any other amount would scale the line total to nothing or below.
+const SINGLE_PIECE: &str = "Each artwork is a single piece; amount must be 1";
+
+async fn order_owner_and_status(conn: &mut SqliteConnection, id_order: &str) -> Result<Option<(String, OrderStatus)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, OrderStatus)>("SELECT id_customer, status FROM ORDERS WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// Lines are only changed while the order waits for payment; after that it
+/// moves on through cancellation or returns.
+fn not_pending(id_order: &str, status: OrderStatus) -> HttpResponse {
+    HttpResponse::Conflict().body(format!("Order {} is {}; only pending orders can change their lines", id_order, status))
+}
+
+/// A payment's amount is fixed when it starts, so the lines it pays for cannot
+/// change until it settles or fails. A discount code was checked against and
+/// spread over the lines at checkout, so those lines stay as they are too.
+async fn lines_locked(conn: &mut SqliteConnection, id_order: &str) -> Result<Option<HttpResponse>, sqlx::Error> {
+    if payment_controller::payment_in_progress(conn, id_order).await? {
+        return Ok(Some(HttpResponse::Conflict().body(format!("Order {} has a payment in progress; its lines cannot change", id_order))));
+    }
+    let discount_code = sqlx::query_scalar::<_, Option<String>>("SELECT discount_code FROM ORDERS WHERE id_order = ? AND id_discount IS NOT NULL")
+        .bind(id_order)
+        .fetch_optional(&mut *conn)
+        .await?
+        .flatten();
+    if let Some(code) = discount_code {
+        return Ok(Some(HttpResponse::Conflict().body(format!("Order {} has discount code {} applied; its lines cannot change", id_order, code))));
+    }
+    Ok(None)
+}
+
+async fn fetch_order_line(conn: &mut SqliteConnection, id_artwork_in_order: &str) -> Result<Option<ArtworkInOrder>, sqlx::Error> {
+    sqlx::query_as::<_, ArtworkInOrder>("SELECT * FROM artworks_in_order WHERE id_artwork_in_order = ?")
+        .bind(id_artwork_in_order)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
 #[get("/")]
-pub async fn get_artworks_in_order(pool: web::Data<SqlitePool>) -> impl Responder {
+pub async fn get_artworks_in_order(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    let customer_filter = if principal.is_staff() { None } else { Some(principal.customer_id.as_str()) };
     match sqlx::query_as::<_, ArtworkInOrder>(
         r#"
-        SELECT id_artwork_in_order, id_order, id_artwork, amount
-        FROM artworks_in_order
-        ORDER BY id_order, id_artwork
+        SELECT aio.*
+        FROM artworks_in_order aio
+        JOIN ORDERS o ON o.id_order = aio.id_order
+        WHERE (? IS NULL OR o.id_customer = ?)
+        ORDER BY aio.id_order, aio.id_artwork
         "#
     )
+    .bind(customer_filter)
+    .bind(customer_filter)
     .fetch_all(&**pool)
     .await
     {
@@ -42,26 +185,59 @@ pub async fn get_artworks_in_order(pool: web::Data<SqlitePool>) -> impl Responde
 }
 
 #[post("/")]
-pub async fn create_artwork_in_order(pool: web::Data<SqlitePool>, artwork_in_order: web::Json<ArtworkInOrder>) -> impl Responder {
+pub async fn create_artwork_in_order(pool: web::Data<SqlitePool>, principal: Principal, artwork_in_order: web::Json<ArtworkInOrder>) -> impl Responder {
+    if artwork_in_order.amount != 1 {
+        return HttpResponse::BadRequest().body(SINGLE_PIECE);
+    }
+    // Adding a line places an order just as checkout does, so the same rule applies.
+    match customer_controller::is_email_verified(&pool, &principal.customer_id).await {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => return HttpResponse::Forbidden().body("Forbidden: the customer's email address must be verified before placing orders"),
+        Ok(None) => return HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
     let id = Uuid::new_v4().to_string();
-    
-    match sqlx::query("INSERT INTO artworks_in_order (id_artwork_in_order, id_order, id_artwork, amount) VALUES (?, ?, ?, ?)")
-        .bind(&id)
-        .bind(&artwork_in_order.id_order)
-        .bind(&artwork_in_order.id_artwork)
-        .bind(&artwork_in_order.amount)
-        .execute(&**pool)
-        .await
-    {
-        Ok(_result) => {
-            let new_artwork_in_order = ArtworkInOrder {
-                id_artwork_in_order: id,
-                id_order: artwork_in_order.id_order.clone(),
-                id_artwork: artwork_in_order.id_artwork.clone(),
-                amount: artwork_in_order.amount,
-            };
-            HttpResponse::Created().json(new_artwork_in_order)
+    let result = async {
+        let mut tx = pool.begin().await?;
+        match order_owner_and_status(&mut tx, &artwork_in_order.id_order).await? {
+            Some((owner, _)) if !principal.can_access_customer(&owner) => {
+                // Someone else's order looks exactly like a missing one.
+                return Ok(Err(HttpResponse::NotFound().body("Order not found")));
+            }
+            Some((_, OrderStatus::Pending)) => {}
+            Some((_, status)) => return Ok(Err(not_pending(&artwork_in_order.id_order, status))),
+            None => return Ok(Err(HttpResponse::NotFound().body("Order not found"))),
         }
+        if let Some(locked) = lines_locked(&mut tx, &artwork_in_order.id_order).await? {
+            return Ok(Err(locked));
+        }
+        // Drafts are not for sale yet and look like missing artworks.
+        let status = sqlx::query_scalar::<_, ArtworkStatus>("SELECT status FROM ARTWORKS WHERE id_artwork = ? AND is_draft = 0")
+            .bind(&artwork_in_order.id_artwork)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(status) = status else {
+            return Ok(Err(HttpResponse::NotFound().body("Artwork not found")));
+        };
+        if status != ArtworkStatus::Available {
+            return Ok(Err(HttpResponse::Conflict().body("Artwork is no longer available")));
+        }
+        if let Transition::Refused(_) | Transition::NotFound =
+            artwork_controller::transition_artwork(&mut tx, &artwork_in_order.id_artwork, ArtworkStatus::Sold).await?
+        {
+            return Ok(Err(HttpResponse::Conflict().body("Artwork is no longer available")));
+        }
+        insert_order_line(&mut tx, &id, &artwork_in_order.id_order, &artwork_in_order.id_artwork, artwork_in_order.amount).await?;
+        let line = fetch_order_line(&mut tx, &id).await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Ok(line))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(Some(new_artwork_in_order))) => HttpResponse::Created().json(new_artwork_in_order),
+        Ok(Ok(None)) => HttpResponse::InternalServerError().body("Order line vanished after insert"),
+        Ok(Err(refused)) => refused,
         Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
     }
 }
@@ -69,19 +245,41 @@ pub async fn create_artwork_in_order(pool: web::Data<SqlitePool>, artwork_in_ord
 #[delete("/{id_artwork_in_order}")]
 pub async fn delete_artwork_in_order(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
     let id_artwork_in_order = path.into_inner();
-    
-    match sqlx::query("DELETE FROM artworks_in_order WHERE id_artwork_in_order = ?")
-        .bind(&id_artwork_in_order)
-        .execute(&**pool)
-        .await
-    {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                HttpResponse::Ok().body("Artwork in order deleted successfully")
-            } else {
-                HttpResponse::NotFound().body("Artwork in order not found")
+
+    // The piece on a removed line goes back on sale.
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let Some((id_artwork, id_order)) =
+            sqlx::query_as::<_, (String, String)>("SELECT id_artwork, id_order FROM artworks_in_order WHERE id_artwork_in_order = ?")
+                .bind(&id_artwork_in_order)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(Err(HttpResponse::NotFound().body("Artwork in order not found")));
+        };
+        // Paid lines leave through returns, which refund the customer first.
+        match order_owner_and_status(&mut tx, &id_order).await? {
+            Some((_, OrderStatus::Pending)) => {
+                if let Some(locked) = lines_locked(&mut tx, &id_order).await? {
+                    return Ok(Err(locked));
+                }
             }
+            None => {}
+            Some((_, status)) => return Ok(Err(not_pending(&id_order, status))),
         }
+        artwork_controller::release_artwork(&mut tx, &id_artwork).await?;
+        sqlx::query("DELETE FROM artworks_in_order WHERE id_artwork_in_order = ?")
+            .bind(&id_artwork_in_order)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Ok(()))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => HttpResponse::Ok().body("Artwork in order deleted successfully"),
+        Ok(Err(refused)) => refused,
         Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
     }
 }
@@ -93,38 +291,69 @@ pub async fn update_artwork_in_order(
     updated: web::Json<ArtworkInOrder>,
 ) -> impl Responder {
     let id_artwork_in_order = path.into_inner();
-    
-    match sqlx::query("SELECT id_artwork_in_order FROM artworks_in_order WHERE id_artwork_in_order = ?")
-        .bind(&id_artwork_in_order)
-        .fetch_optional(&**pool)
-        .await
-    {
-        Ok(Some(_)) => {
-            match sqlx::query("UPDATE artworks_in_order SET id_order = ?, id_artwork = ?, amount = ? WHERE id_artwork_in_order = ?")
-                .bind(&updated.id_order)
+    if updated.amount != 1 {
+        return HttpResponse::BadRequest().body(SINGLE_PIECE);
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let Some(existing) = fetch_order_line(&mut tx, &id_artwork_in_order).await? else {
+            return Ok(Err(HttpResponse::NotFound().body("Artwork in order with provided ID does not exist")));
+        };
+        // Both the order the line is on and the one it moves to must still be open.
+        for id_order in [&existing.id_order, &updated.id_order] {
+            match order_owner_and_status(&mut tx, id_order).await? {
+                Some((_, OrderStatus::Pending)) => {}
+                Some((_, status)) => return Ok(Err(not_pending(id_order, status))),
+                None => return Ok(Err(HttpResponse::NotFound().body("Order not found"))),
+            }
+            if let Some(locked) = lines_locked(&mut tx, id_order).await? {
+                return Ok(Err(locked));
+            }
+        }
+        // Swapping the piece sells the new one and puts the old one back on sale.
+        if existing.id_artwork != updated.id_artwork {
+            let listed = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM ARTWORKS WHERE id_artwork = ? AND is_draft = 0)")
                 .bind(&updated.id_artwork)
-                .bind(&updated.amount)
-                .bind(&id_artwork_in_order)
-                .execute(&**pool)
-                .await
-            {
-                Ok(result) => {
-                    if result.rows_affected() > 0 {
-                        let updated_artwork_in_order = ArtworkInOrder {
-                            id_artwork_in_order: id_artwork_in_order.clone(),
-                            id_order: updated.id_order.clone(),
-                            id_artwork: updated.id_artwork.clone(),
-                            amount: updated.amount,
-                        };
-                        HttpResponse::Ok().json(updated_artwork_in_order)
-                    } else {
-                        HttpResponse::NotFound().body("Artwork in order not found")
-                    }
-                }
-                Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+                .fetch_one(&mut *tx)
+                .await?;
+            if !listed {
+                return Ok(Err(HttpResponse::NotFound().body("Artwork not found")));
             }
+            match artwork_controller::transition_artwork(&mut tx, &updated.id_artwork, ArtworkStatus::Sold).await? {
+                Transition::Moved(_) => {}
+                Transition::Refused(_) => return Ok(Err(HttpResponse::Conflict().body("Artwork is no longer available"))),
+                Transition::NotFound => return Ok(Err(HttpResponse::NotFound().body("Artwork not found"))),
+            }
+            artwork_controller::release_artwork(&mut tx, &existing.id_artwork).await?;
         }
-        Ok(None) => HttpResponse::NotFound().body("Artwork in order with provided ID does not exist"),
+        sqlx::query("UPDATE artworks_in_order SET id_order = ?, id_artwork = ?, amount = ? WHERE id_artwork_in_order = ?")
+            .bind(&updated.id_order)
+            .bind(&updated.id_artwork)
+            .bind(updated.amount)
+            .bind(&id_artwork_in_order)
+            .execute(&mut *tx)
+            .await?;
+        // The snapshot follows the artwork: swapping the piece re-prices the line, editing the amount does not.
+        if existing.id_artwork != updated.id_artwork {
+            // Any discount was worked out for the old piece's price.
+            sqlx::query("UPDATE artworks_in_order SET discount_minor = 0 WHERE id_artwork_in_order = ?")
+                .bind(&id_artwork_in_order)
+                .execute(&mut *tx)
+                .await?;
+            resnapshot_order_line(&mut tx, &id_artwork_in_order).await?;
+        } else {
+            tax_controller::recompute_line_tax(&mut tx, &id_artwork_in_order).await?;
+        }
+        let line = fetch_order_line(&mut tx, &id_artwork_in_order).await?.ok_or(sqlx::Error::RowNotFound)?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Ok(line))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(updated_artwork_in_order)) => HttpResponse::Ok().json(updated_artwork_in_order),
+        Ok(Err(refused)) => refused,
         Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
     }
 }
@@ -0,0 +1,485 @@
+use actix_web::{get, post, rt, web, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::controllers::{artwork_controller, artwork_in_order_controller, customer_controller, order_controller};
+use crate::controllers::artwork_controller::Transition;
+use crate::models::artwork::ArtworkStatus;
+use crate::models::auction::{Auction, AuctionListQuery, AuctionStatus, Bid, BidRequest, NewAuction};
+use crate::models::money::Money;
+use crate::models::order::OrderStatus;
+
+const CLOSE_INTERVAL_SECONDS: u64 = 15;
+
+/// Auctions with their highest bid, as `Auction::from_row` reads them.
+const SELECT_AUCTIONS: &str =
+    "SELECT a.*, (SELECT MAX(b.amount_minor) FROM bids b WHERE b.id_auction = a.id_auction) AS highest_minor FROM auctions a";
+
+pub async fn init_auction_tables(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating auction tables if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS auctions (
+            id_auction TEXT PRIMARY KEY NOT NULL,
+            id_artwork TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open',
+            starts_at TEXT NOT NULL,
+            ends_at TEXT NOT NULL,
+            starting_minor INTEGER NOT NULL,
+            reserve_minor INTEGER,
+            increment_minor INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            extension_seconds INTEGER NOT NULL,
+            bid_count INTEGER NOT NULL DEFAULT 0,
+            id_order TEXT REFERENCES ORDERS(id_order) ON DELETE SET NULL,
+            created_at TEXT NOT NULL,
+            closed_at TEXT,
+            FOREIGN KEY (id_artwork) REFERENCES ARTWORKS(id_artwork) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_auctions_open_artwork ON auctions (id_artwork) WHERE status = 'open'")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS bids (
+            id_bid TEXT PRIMARY KEY NOT NULL,
+            id_auction TEXT NOT NULL,
+            customer_id TEXT NOT NULL,
+            amount_minor INTEGER NOT NULL,
+            placed_at TEXT NOT NULL,
+            FOREIGN KEY (id_auction) REFERENCES auctions(id_auction) ON DELETE CASCADE,
+            FOREIGN KEY (customer_id) REFERENCES customers(customer_id)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_bids_auction ON bids (id_auction, amount_minor)")
+        .execute(pool)
+        .await?;
+    println!("✅ auction tables ready");
+
+    Ok(())
+}
+
+async fn fetch_auction(conn: &mut SqliteConnection, id_auction: &str) -> Result<Option<Auction>, sqlx::Error> {
+    sqlx::query_as::<_, Auction>(&format!("{} WHERE a.id_auction = ?", SELECT_AUCTIONS))
+        .bind(id_auction)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// The reserve price is for staff; everyone else only learns whether it was met.
+fn visible_to(mut auction: Auction, staff: bool) -> Auction {
+    if !staff {
+        auction.reserve_price = None;
+    }
+    auction
+}
+
+/// The leading bidder and their bid. Equal bids cannot happen, but the earlier one would lead.
+async fn highest_bid(conn: &mut SqliteConnection, id_auction: &str) -> Result<Option<(String, i64)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, i64)>("SELECT customer_id, amount_minor FROM bids WHERE id_auction = ? ORDER BY amount_minor DESC, placed_at LIMIT 1")
+        .bind(id_auction)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+enum BidRefusal {
+    NotFound,
+    NotOpen(String),
+    WrongCurrency(String),
+    TooLow(Money),
+    AlreadyLeading,
+}
+
+async fn place_bid(pool: &SqlitePool, id_auction: &str, customer_id: &str, amount: &Money) -> Result<Result<Auction, BidRefusal>, sqlx::Error> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+    // Writing first takes SQLite's write lock, so bids on an auction are placed
+    // one at a time and each is checked against the one before it.
+    let open = sqlx::query("UPDATE auctions SET bid_count = bid_count + 1 WHERE id_auction = ? AND status = ? AND starts_at <= ? AND ends_at > ?")
+        .bind(id_auction)
+        .bind(AuctionStatus::Open)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    let Some(auction) = fetch_auction(&mut tx, id_auction).await? else {
+        return Ok(Err(BidRefusal::NotFound));
+    };
+    if open.rows_affected() == 0 {
+        let reason = if auction.status != AuctionStatus::Open {
+            format!("The auction is {}", auction.status)
+        } else if auction.starts_at > now {
+            format!("The auction opens at {}", auction.starts_at)
+        } else {
+            "The auction has ended".to_string()
+        };
+        return Ok(Err(BidRefusal::NotOpen(reason)));
+    }
+    if amount.currency != auction.minimum_bid.currency {
+        return Ok(Err(BidRefusal::WrongCurrency(auction.minimum_bid.currency)));
+    }
+    if amount.minor < auction.minimum_bid.minor {
+        return Ok(Err(BidRefusal::TooLow(auction.minimum_bid)));
+    }
+    if highest_bid(&mut tx, id_auction).await?.is_some_and(|(leader, _)| leader == customer_id) {
+        return Ok(Err(BidRefusal::AlreadyLeading));
+    }
+
+    sqlx::query("INSERT INTO bids (id_bid, id_auction, customer_id, amount_minor, placed_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(Uuid::new_v4().to_string())
+        .bind(id_auction)
+        .bind(customer_id)
+        .bind(amount.minor)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    // A late bid keeps the auction open long enough for others to answer it.
+    let extension = Duration::seconds(auction.extension_seconds);
+    if auction.ends_at - now < extension {
+        sqlx::query("UPDATE auctions SET ends_at = ? WHERE id_auction = ?")
+            .bind(now + extension)
+            .bind(id_auction)
+            .execute(&mut *tx)
+            .await?;
+        println!("⏱️ Auction {} extended to {} by a late bid", id_auction, now + extension);
+    }
+    let auction = fetch_auction(&mut tx, id_auction).await?.ok_or(sqlx::Error::RowNotFound)?;
+    tx.commit().await?;
+    Ok(Ok(auction))
+}
+
+/// Places a pending order for the winner, priced at the winning bid rather than
+/// the catalog price. Returns the order id.
+async fn create_winning_order(conn: &mut SqliteConnection, auction: &Auction, customer_id: &str, amount_minor: i64) -> Result<String, sqlx::Error> {
+    let id_order = Uuid::new_v4().to_string();
+    sqlx::query(order_controller::INSERT_ORDER)
+        .bind(&id_order)
+        .bind(customer_id)
+        .bind(Utc::now().date_naive())
+        .bind(OrderStatus::Pending)
+        .bind(customer_id)
+        .execute(&mut *conn)
+        .await?;
+    let note = format!("Won at auction {}", auction.id_auction);
+    order_controller::record_status_change(conn, &id_order, None, OrderStatus::Pending, None, Some(&note)).await?;
+
+    let id_artwork_in_order = Uuid::new_v4().to_string();
+    artwork_in_order_controller::insert_order_line(conn, &id_artwork_in_order, &id_order, &auction.id_artwork, 1).await?;
+    let price = Money::new(amount_minor, &auction.starting_price.currency).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    artwork_in_order_controller::set_line_price(conn, &id_artwork_in_order, &price).await?;
+    Ok(id_order)
+}
+
+/// Closes an auction whose end has passed. The highest bid wins if it meets the
+/// reserve and its bidder gets a pending order to pay; otherwise the piece goes
+/// back on sale. Returns the outcome, or `None` when the auction was not due.
+pub async fn close_auction(pool: &SqlitePool, id_auction: &str) -> Result<Option<AuctionStatus>, sqlx::Error> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+    // Claiming the auction first takes the write lock, so no bid lands while it closes.
+    let claimed = sqlx::query("UPDATE auctions SET closed_at = ? WHERE id_auction = ? AND status = ? AND ends_at <= ?")
+        .bind(now)
+        .bind(id_auction)
+        .bind(AuctionStatus::Open)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(None);
+    }
+    let auction = fetch_auction(&mut tx, id_auction).await?.ok_or(sqlx::Error::RowNotFound)?;
+
+    let winner = if auction.reserve_met { highest_bid(&mut tx, id_auction).await? } else { None };
+    let id_order = match winner {
+        Some((customer_id, amount_minor)) => match artwork_controller::transition_artwork(&mut tx, &auction.id_artwork, ArtworkStatus::Sold).await? {
+            Transition::Moved(_) => Some(create_winning_order(&mut tx, &auction, &customer_id, amount_minor).await?),
+            Transition::Refused(current) => {
+                println!("⚠️ Artwork {} was {} when auction {} closed; no order was placed", auction.id_artwork, current, id_auction);
+                None
+            }
+            Transition::NotFound => None,
+        },
+        None => {
+            artwork_controller::release_artwork(&mut tx, &auction.id_artwork).await?;
+            None
+        }
+    };
+    let status = if id_order.is_some() { AuctionStatus::Sold } else { AuctionStatus::Unsold };
+    sqlx::query("UPDATE auctions SET status = ?, id_order = ? WHERE id_auction = ?")
+        .bind(status)
+        .bind(&id_order)
+        .bind(id_auction)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    match &id_order {
+        Some(id_order) => println!("🔨 Auction {} sold; order {} placed for the winner", id_auction, id_order),
+        None => println!("🔨 Auction {} closed unsold", id_auction),
+    }
+    Ok(Some(status))
+}
+
+pub async fn close_due_auctions(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query_scalar::<_, String>("SELECT id_auction FROM auctions WHERE status = ? AND ends_at <= ?")
+        .bind(AuctionStatus::Open)
+        .bind(Utc::now())
+        .fetch_all(pool)
+        .await?;
+    let mut closed = 0;
+    for id_auction in due {
+        if close_auction(pool, &id_auction).await?.is_some() {
+            closed += 1;
+        }
+    }
+    Ok(closed)
+}
+
+/// Runs `close_due_auctions` every few seconds for as long as the server is up.
+pub fn spawn_auction_closer(pool: SqlitePool) {
+    rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(CLOSE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = close_due_auctions(&pool).await {
+                eprintln!("Failed to close due auctions: {}", e);
+            }
+        }
+    });
+}
+
+/// Bid history, oldest bid first, for the auctions matching `filter` (a column of `auctions`).
+async fn load_bids(pool: &SqlitePool, filter: &str, value: &str, principal: &Option<Principal>) -> Result<Vec<Bid>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String, String, i64, String, DateTime<Utc>)>(&format!(
+        r#"
+        SELECT b.id_bid, b.id_auction, b.customer_id, b.amount_minor, a.currency, b.placed_at
+        FROM bids b
+        JOIN auctions a ON a.id_auction = b.id_auction
+        WHERE a.{} = ?
+        ORDER BY b.placed_at
+        "#,
+        filter
+    ))
+    .bind(value)
+    .fetch_all(pool)
+    .await?;
+
+    let staff = principal.as_ref().is_some_and(Principal::is_staff);
+    let viewer = principal.as_ref().map(|principal| principal.customer_id.as_str());
+    let mut bidders: HashMap<(String, String), usize> = HashMap::new();
+    let mut bidder_counts: HashMap<String, usize> = HashMap::new();
+    rows.into_iter()
+        .map(|(id_bid, id_auction, customer_id, amount_minor, currency, placed_at)| {
+            let number = *bidders.entry((id_auction.clone(), customer_id.clone())).or_insert_with(|| {
+                let count = bidder_counts.entry(id_auction.clone()).or_default();
+                *count += 1;
+                *count
+            });
+            Ok(Bid {
+                id_bid,
+                id_auction,
+                bidder: format!("Bidder {}", number),
+                is_mine: viewer == Some(customer_id.as_str()),
+                customer_id: staff.then_some(customer_id),
+                amount: Money::new(amount_minor, &currency).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+                placed_at,
+            })
+        })
+        .collect()
+}
+
+#[get("/")]
+pub async fn get_auctions(pool: web::Data<SqlitePool>, principal: Option<Principal>, query: web::Query<AuctionListQuery>) -> impl Responder {
+    match sqlx::query_as::<_, Auction>(&format!(
+        "{} WHERE (? IS NULL OR a.status = ?) AND (? IS NULL OR a.id_artwork = ?) ORDER BY a.ends_at",
+        SELECT_AUCTIONS
+    ))
+    .bind(query.status)
+    .bind(query.status)
+    .bind(&query.id_artwork)
+    .bind(&query.id_artwork)
+    .fetch_all(&**pool)
+    .await
+    {
+        Ok(auctions) => {
+            let staff = principal.as_ref().is_some_and(Principal::is_staff);
+            HttpResponse::Ok().json(auctions.into_iter().map(|auction| visible_to(auction, staff)).collect::<Vec<_>>())
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/{id_auction}")]
+pub async fn get_auction(pool: web::Data<SqlitePool>, principal: Option<Principal>, path: web::Path<String>) -> impl Responder {
+    let id_auction = path.into_inner();
+    let result = async {
+        let mut conn = pool.acquire().await?;
+        fetch_auction(&mut conn, &id_auction).await
+    }
+    .await;
+    match result {
+        Ok(Some(auction)) => HttpResponse::Ok().json(visible_to(auction, principal.as_ref().is_some_and(Principal::is_staff))),
+        Ok(None) => HttpResponse::NotFound().body(format!("Auction with id {} not found", id_auction)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/")]
+pub async fn create_auction(pool: web::Data<SqlitePool>, new_auction: web::Json<NewAuction>) -> impl Responder {
+    let now = Utc::now();
+    if let Err(reason) = new_auction.validate(now) {
+        return HttpResponse::BadRequest().body(reason);
+    }
+    let id_auction = Uuid::new_v4().to_string();
+    let id_artwork = &new_auction.id_artwork;
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let listed = sqlx::query_scalar::<_, String>("SELECT currency FROM ARTWORKS WHERE id_artwork = ? AND is_draft = 0")
+            .bind(id_artwork)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(currency) = listed else {
+            return Ok(Err(HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork))));
+        };
+        if currency != new_auction.starting_price.currency {
+            return Ok(Err(HttpResponse::BadRequest().body(format!("The artwork is priced in {}; auction it in the same currency", currency))));
+        }
+        match artwork_controller::transition_artwork(&mut tx, id_artwork, ArtworkStatus::Auction).await? {
+            Transition::Moved(_) => {}
+            Transition::Refused(current) => {
+                return Ok(Err(HttpResponse::Conflict().body(format!("Artwork with id {} is {} and cannot go up for auction", id_artwork, current))))
+            }
+            Transition::NotFound => return Ok(Err(HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork)))),
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO auctions (id_auction, id_artwork, status, starts_at, ends_at, starting_minor, reserve_minor, increment_minor, currency, extension_seconds, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&id_auction)
+        .bind(id_artwork)
+        .bind(AuctionStatus::Open)
+        .bind(new_auction.starts_at.unwrap_or(now))
+        .bind(new_auction.ends_at)
+        .bind(new_auction.starting_price.minor)
+        .bind(new_auction.reserve_price.as_ref().map(|reserve| reserve.minor))
+        .bind(new_auction.bid_increment.minor)
+        .bind(&currency)
+        .bind(new_auction.extension_seconds)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        let auction = fetch_auction(&mut tx, &id_auction).await?.ok_or(sqlx::Error::RowNotFound)?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Ok(auction))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(auction)) => {
+            println!("🔨 Artwork {} up for auction {} until {}", id_artwork, id_auction, auction.ends_at);
+            HttpResponse::Created().json(auction)
+        }
+        Ok(Err(refusal)) => refusal,
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Stops an open auction without a sale. Its bids stay in the history. Once
+/// the end has passed the bids decide the outcome, so it can no longer be cancelled.
+#[post("/{id_auction}/cancel")]
+pub async fn cancel_auction(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+    let id_auction = path.into_inner();
+    let now = Utc::now();
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let cancelled = sqlx::query("UPDATE auctions SET status = ?, closed_at = ? WHERE id_auction = ? AND status = ? AND ends_at > ?")
+            .bind(AuctionStatus::Cancelled)
+            .bind(now)
+            .bind(&id_auction)
+            .bind(AuctionStatus::Open)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        let Some(auction) = fetch_auction(&mut tx, &id_auction).await? else {
+            return Ok(None);
+        };
+        if cancelled.rows_affected() > 0 {
+            artwork_controller::release_artwork(&mut tx, &auction.id_artwork).await?;
+            tx.commit().await?;
+        }
+        Ok::<_, sqlx::Error>(Some((cancelled.rows_affected() > 0, auction)))
+    }
+    .await;
+
+    match result {
+        Ok(Some((true, auction))) => {
+            println!("🔨 Auction {} cancelled", id_auction);
+            HttpResponse::Ok().json(auction)
+        }
+        Ok(Some((false, auction))) if auction.status == AuctionStatus::Open => {
+            HttpResponse::Conflict().body("The auction has ended and is closing on its bids")
+        }
+        Ok(Some((false, auction))) => HttpResponse::Conflict().body(format!("The auction is already {}", auction.status)),
+        Ok(None) => HttpResponse::NotFound().body(format!("Auction with id {} not found", id_auction)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/{id_auction}/bids")]
+pub async fn create_bid(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>, bid: web::Json<BidRequest>) -> impl Responder {
+    let id_auction = path.into_inner();
+    match customer_controller::is_email_verified(&pool, &principal.customer_id).await {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => return HttpResponse::Forbidden().body("Forbidden: the customer's email address must be verified before bidding"),
+        Ok(None) => return HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    match place_bid(&pool, &id_auction, &principal.customer_id, &bid.amount).await {
+        Ok(Ok(auction)) => {
+            println!("🔨 Customer {} bid {} in auction {}", principal.customer_id, bid.amount, id_auction);
+            HttpResponse::Created().json(visible_to(auction, principal.is_staff()))
+        }
+        Ok(Err(BidRefusal::NotFound)) => HttpResponse::NotFound().body(format!("Auction with id {} not found", id_auction)),
+        Ok(Err(BidRefusal::NotOpen(reason))) => HttpResponse::Conflict().body(reason),
+        Ok(Err(BidRefusal::WrongCurrency(currency))) => HttpResponse::BadRequest().body(format!("Bids in this auction are in {}", currency)),
+        Ok(Err(BidRefusal::TooLow(minimum))) => HttpResponse::Conflict().body(format!("The next bid must be at least {}", minimum)),
+        Ok(Err(BidRefusal::AlreadyLeading)) => HttpResponse::Conflict().body("You already hold the highest bid"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/{id_auction}/bids")]
+pub async fn get_auction_bids(pool: web::Data<SqlitePool>, principal: Option<Principal>, path: web::Path<String>) -> impl Responder {
+    match load_bids(&pool, "id_auction", &path.into_inner(), &principal).await {
+        Ok(mut bids) => {
+            bids.reverse();
+            HttpResponse::Ok().json(bids)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Every bid ever placed on an artwork, newest first, across all its auctions.
+#[get("/{id}/bids")]
+pub async fn get_artwork_bids(pool: web::Data<SqlitePool>, principal: Option<Principal>, path: web::Path<String>) -> impl Responder {
+    match load_bids(&pool, "id_artwork", &path.into_inner(), &principal).await {
+        Ok(mut bids) => {
+            bids.reverse();
+            HttpResponse::Ok().json(bids)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
@@ -0,0 +1,290 @@
+use actix_web::{delete, get, post, rt, web, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::controllers::artwork_controller::{self, Transition};
+use crate::models::artwork::ArtworkStatus;
+use crate::models::cart::{Cart, CartAddition, CartItem};
+use crate::models::money::{Money, CATALOG_CURRENCY};
+
+pub const DEFAULT_HOLD_MINUTES: i64 = 15;
+const SWEEP_INTERVAL_SECONDS: u64 = 60;
+
+pub async fn init_carts_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating carts tables if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS carts (
+            id_cart TEXT PRIMARY KEY NOT NULL,
+            customer_id TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (customer_id) REFERENCES customers(customer_id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    // One row per held artwork: the UNIQUE constraint is what keeps a piece in a single cart.
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS cart_items (
+            id_cart_item TEXT PRIMARY KEY NOT NULL,
+            id_cart TEXT NOT NULL,
+            id_artwork TEXT NOT NULL UNIQUE,
+            added_at TEXT NOT NULL,
+            reserved_until TEXT NOT NULL,
+            FOREIGN KEY (id_cart) REFERENCES carts(id_cart) ON DELETE CASCADE,
+            FOREIGN KEY (id_artwork) REFERENCES artworks(id_artwork) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ carts tables ready");
+
+    Ok(())
+}
+
+/// How long an artwork stays reserved once added to a cart (`CART_HOLD_MINUTES`).
+pub fn hold_duration() -> Duration {
+    let minutes = std::env::var("CART_HOLD_MINUTES")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(DEFAULT_HOLD_MINUTES);
+    Duration::minutes(minutes)
+}
+
+async fn cart_id(conn: &mut SqliteConnection, customer_id: &str) -> Result<String, sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO carts (id_cart, customer_id, created_at) VALUES (?, ?, ?)")
+        .bind(Uuid::new_v4().to_string())
+        .bind(customer_id)
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query_scalar::<_, String>("SELECT id_cart FROM carts WHERE customer_id = ?")
+        .bind(customer_id)
+        .fetch_one(&mut *conn)
+        .await
+}
+
+async fn load_cart(conn: &mut SqliteConnection, customer_id: &str) -> Result<Cart, sqlx::Error> {
+    let id_cart = cart_id(conn, customer_id).await?;
+    let items = sqlx::query_as::<_, CartItem>(
+        r#"
+        SELECT a.id_artwork, a.title, a.price_minor, a.currency, a.id_artist, a.art_type, ci.added_at, ci.reserved_until
+        FROM cart_items ci
+        JOIN ARTWORKS a ON a.id_artwork = ci.id_artwork
+        WHERE ci.id_cart = ? AND ci.reserved_until > ?
+        ORDER BY ci.added_at
+        "#
+    )
+    .bind(&id_cart)
+    // Lapsed holds wait for the sweeper but are no longer the customer's.
+    .bind(Utc::now())
+    .fetch_all(&mut *conn)
+    .await?;
+    // add_to_cart keeps a cart to one currency, so the sum cannot mix them.
+    let currency = items.first().map_or(CATALOG_CURRENCY, |item| item.price.currency.as_str());
+    let total = Money::sum(currency, items.iter().map(|item| &item.price)).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    Ok(Cart { id_cart, items, total })
+}
+
+/// Whether `id_artwork` sits in this customer's cart with time left on its hold.
+pub async fn is_held_by(conn: &mut SqliteConnection, id_artwork: &str, customer_id: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM cart_items ci
+            JOIN carts c ON c.id_cart = ci.id_cart
+            WHERE ci.id_artwork = ? AND c.customer_id = ? AND ci.reserved_until > ?
+        )
+        "#
+    )
+    .bind(id_artwork)
+    .bind(customer_id)
+    .bind(Utc::now())
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Drops the hold row for an artwork without touching its status, e.g. once it is sold.
+pub async fn forget_hold(conn: &mut SqliteConnection, id_artwork: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM cart_items WHERE id_artwork = ?")
+        .bind(id_artwork)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Removes a held artwork from the cart and puts it back on sale.
+async fn release_hold(conn: &mut SqliteConnection, id_artwork: &str) -> Result<(), sqlx::Error> {
+    forget_hold(conn, id_artwork).await?;
+    if let Transition::Refused(current) = artwork_controller::transition_artwork(conn, id_artwork, ArtworkStatus::Available).await? {
+        // Staff already moved it elsewhere; only reserved pieces go back on sale.
+        if current != ArtworkStatus::Available {
+            println!("⚠️ Artwork {} stays {} after its cart hold was released", id_artwork, current);
+        }
+    }
+    Ok(())
+}
+
+/// Empties a customer's cart, releasing every hold in it.
+pub async fn release_customer_holds(conn: &mut SqliteConnection, customer_id: &str) -> Result<(), sqlx::Error> {
+    let held = sqlx::query_scalar::<_, String>(
+        "SELECT ci.id_artwork FROM cart_items ci JOIN carts c ON c.id_cart = ci.id_cart WHERE c.customer_id = ?",
+    )
+    .bind(customer_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    for id_artwork in &held {
+        release_hold(conn, id_artwork).await?;
+    }
+    Ok(())
+}
+
+/// Releases every hold whose time is up. Returns how many were released.
+pub async fn release_expired_holds(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let expired = sqlx::query_scalar::<_, String>("SELECT id_artwork FROM cart_items WHERE reserved_until <= ?")
+        .bind(Utc::now())
+        .fetch_all(&mut *tx)
+        .await?;
+    for id_artwork in &expired {
+        release_hold(&mut tx, id_artwork).await?;
+    }
+    tx.commit().await?;
+    Ok(expired.len())
+}
+
+/// Runs `release_expired_holds` every minute for as long as the server is up.
+pub fn spawn_hold_sweeper(pool: SqlitePool) {
+    rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            match release_expired_holds(&pool).await {
+                Ok(0) => {}
+                Ok(released) => println!("⏳ Released {} expired cart holds", released),
+                Err(e) => eprintln!("Failed to release expired cart holds: {}", e),
+            }
+        }
+    });
+}
+
+#[get("")]
+pub async fn get_cart(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    let result = async {
+        let mut conn = pool.acquire().await?;
+        load_cart(&mut conn, &principal.customer_id).await
+    }
+    .await;
+
+    match result {
+        Ok(cart) => HttpResponse::Ok().json(cart),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/items")]
+pub async fn add_to_cart(pool: web::Data<SqlitePool>, principal: Principal, addition: web::Json<CartAddition>) -> impl Responder {
+    let id_artwork = addition.id_artwork.as_str();
+    let reserved_until = Utc::now() + hold_duration();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let id_cart = cart_id(&mut tx, &principal.customer_id).await?;
+
+        // Adding a piece that is already in the cart keeps its hold as it is;
+        // renewing it would let one customer keep a piece off sale for good.
+        if !is_held_by(&mut tx, id_artwork, &principal.customer_id).await? {
+            let Some(currency) = sqlx::query_scalar::<_, String>("SELECT currency FROM ARTWORKS WHERE id_artwork = ? AND is_draft = 0")
+                .bind(id_artwork)
+                .fetch_optional(&mut *tx)
+                .await?
+            else {
+                return Ok(Err(HttpResponse::NotFound().body("Artwork not found")));
+            };
+            let cart_currency = sqlx::query_scalar::<_, String>(
+                "SELECT a.currency FROM cart_items ci JOIN ARTWORKS a ON a.id_artwork = ci.id_artwork WHERE ci.id_cart = ? LIMIT 1",
+            )
+            .bind(&id_cart)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some(cart_currency) = cart_currency.filter(|cart_currency| *cart_currency != currency) {
+                return Ok(Err(HttpResponse::Conflict().body(format!(
+                    "The cart holds artworks priced in {}; this one is priced in {}",
+                    cart_currency, currency
+                ))));
+            }
+            match artwork_controller::transition_artwork(&mut tx, id_artwork, ArtworkStatus::Reserved).await? {
+                Transition::Moved(_) => {}
+                Transition::Refused(ArtworkStatus::Reserved) => {
+                    return Ok(Err(HttpResponse::Conflict().body("Artwork is reserved in another cart")));
+                }
+                Transition::Refused(_) => return Ok(Err(HttpResponse::Conflict().body("Artwork is no longer available"))),
+                Transition::NotFound => return Ok(Err(HttpResponse::NotFound().body("Artwork not found"))),
+            }
+            // A leftover row from a hold staff ended by hand would block the UNIQUE slot.
+            forget_hold(&mut tx, id_artwork).await?;
+            sqlx::query("INSERT INTO cart_items (id_cart_item, id_cart, id_artwork, added_at, reserved_until) VALUES (?, ?, ?, ?, ?)")
+                .bind(Uuid::new_v4().to_string())
+                .bind(&id_cart)
+                .bind(id_artwork)
+                .bind(Utc::now())
+                .bind(reserved_until)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let cart = load_cart(&mut tx, &principal.customer_id).await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Ok(cart))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(cart)) => HttpResponse::Ok().json(cart),
+        Ok(Err(refused)) => refused,
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[delete("/items/{id_artwork}")]
+pub async fn remove_from_cart(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_artwork = path.into_inner();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        if !is_held_by(&mut tx, &id_artwork, &principal.customer_id).await? {
+            return Ok(None);
+        }
+        release_hold(&mut tx, &id_artwork).await?;
+        let cart = load_cart(&mut tx, &principal.customer_id).await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Some(cart))
+    }
+    .await;
+
+    match result {
+        Ok(Some(cart)) => HttpResponse::Ok().json(cart),
+        Ok(None) => HttpResponse::NotFound().body("Artwork is not in the cart"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[delete("")]
+pub async fn clear_cart(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        release_customer_holds(&mut tx, &principal.customer_id).await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => HttpResponse::Ok().body("Cart cleared"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
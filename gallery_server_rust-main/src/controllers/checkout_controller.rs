@@ -0,0 +1,155 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::controllers::{artwork_controller, artwork_in_order_controller, cart_controller, customer_controller, discount_controller, offer_controller, order_controller, shipping_controller};
+use crate::controllers::artwork_controller::Transition;
+use crate::models::artwork::ArtworkStatus;
+use crate::models::checkout::CheckoutRequest;
+use crate::models::order::OrderStatus;
+
+enum CheckoutError {
+    EmptyCart,
+    MixedCurrencies,
+    Unavailable(String),
+    Discount(String),
+    Shipping(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for CheckoutError {
+    fn from(e: sqlx::Error) -> Self {
+        CheckoutError::Database(e)
+    }
+}
+
+/// Writes the order and its lines. The caller commits; returning early drops the
+/// transaction, which rolls back everything written so far.
+async fn place_order(tx: &mut Transaction<'_, Sqlite>, id_customer: &str, request: &CheckoutRequest) -> Result<(String, usize), CheckoutError> {
+    let requested = &request.artwork_ids;
+    let artwork_ids = if requested.is_empty() {
+        sqlx::query_scalar::<_, String>(
+            "SELECT ci.id_artwork FROM cart_items ci JOIN carts c ON c.id_cart = ci.id_cart WHERE c.customer_id = ? AND ci.reserved_until > ? ORDER BY ci.added_at",
+        )
+        .bind(id_customer)
+        .bind(Utc::now())
+        .fetch_all(&mut **tx)
+        .await?
+    } else {
+        requested.to_vec()
+    };
+    if artwork_ids.is_empty() {
+        return Err(CheckoutError::EmptyCart);
+    }
+
+    let id_order = Uuid::new_v4().to_string();
+    // Writing first takes SQLite's write lock, so a concurrent checkout cannot
+    // pass the availability checks below for the same pieces at the same time.
+    sqlx::query(order_controller::INSERT_ORDER)
+        .bind(&id_order)
+        .bind(id_customer)
+        .bind(Utc::now().date_naive())
+        .bind(OrderStatus::Pending)
+        .bind(id_customer)
+        .execute(&mut **tx)
+        .await?;
+    order_controller::record_status_change(tx, &id_order, None, OrderStatus::Pending, Some(id_customer), None).await?;
+
+    let mut order_currency: Option<String> = None;
+    for id_artwork in &artwork_ids {
+        let listed = sqlx::query_as::<_, (ArtworkStatus, String)>("SELECT status, currency FROM ARTWORKS WHERE id_artwork = ? AND is_draft = 0")
+            .bind(id_artwork)
+            .fetch_optional(&mut **tx)
+            .await?;
+        let Some((status, currency)) = listed else {
+            return Err(CheckoutError::Unavailable(id_artwork.clone()));
+        };
+        let offer = offer_controller::accepted_offer(tx, id_artwork, id_customer).await?;
+        // Free pieces can be bought directly; reserved ones only by the customer
+        // holding them in their cart or through an accepted offer.
+        let purchasable = match status {
+            ArtworkStatus::Available => true,
+            ArtworkStatus::Reserved => offer.is_some() || cart_controller::is_held_by(tx, id_artwork, id_customer).await?,
+            _ => false,
+        };
+        if !purchasable {
+            return Err(CheckoutError::Unavailable(id_artwork.clone()));
+        }
+        // One order, one currency: its total has to be a single amount.
+        if order_currency.get_or_insert_with(|| currency.clone()) != &currency {
+            return Err(CheckoutError::MixedCurrencies);
+        }
+        match artwork_controller::transition_artwork(tx, id_artwork, ArtworkStatus::Sold).await? {
+            Transition::Moved(_) => {}
+            Transition::Refused(_) | Transition::NotFound => return Err(CheckoutError::Unavailable(id_artwork.clone())),
+        }
+        cart_controller::forget_hold(tx, id_artwork).await?;
+
+        let id_artwork_in_order = Uuid::new_v4().to_string();
+        artwork_in_order_controller::insert_order_line(tx, &id_artwork_in_order, &id_order, id_artwork, 1).await?;
+        if let Some((id_offer, agreed)) = &offer {
+            artwork_in_order_controller::set_line_price(tx, &id_artwork_in_order, agreed).await?;
+            offer_controller::complete_offer(tx, id_offer, &id_order).await?;
+        }
+    }
+
+    if let Some(code) = request.discount_code.as_deref() {
+        if let Err(reason) = discount_controller::apply_discount(tx, &id_order, code).await? {
+            return Err(CheckoutError::Discount(reason));
+        }
+    }
+    if let Some(shipping) = &request.shipping {
+        if let Err(reason) = shipping_controller::set_shipment(tx, &id_order, shipping).await? {
+            return Err(CheckoutError::Shipping(reason));
+        }
+    }
+
+    Ok((id_order, artwork_ids.len()))
+}
+
+#[post("")]
+pub async fn checkout(pool: web::Data<SqlitePool>, principal: Principal, cart: web::Json<CheckoutRequest>) -> impl Responder {
+    let mut artwork_ids = cart.artwork_ids.clone();
+    artwork_ids.sort();
+    artwork_ids.dedup();
+    if artwork_ids.len() != cart.artwork_ids.len() {
+        return HttpResponse::BadRequest().body("Each artwork can only be in the cart once");
+    }
+
+    match customer_controller::is_email_verified(&pool, &principal.customer_id).await {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => return HttpResponse::Forbidden().body("Forbidden: the customer's email address must be verified before placing orders"),
+        Ok(None) => return HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    let (id_order, line_count) = match place_order(&mut tx, &principal.customer_id, &cart).await {
+        Ok(placed) => placed,
+        Err(CheckoutError::EmptyCart) => return HttpResponse::BadRequest().body("The cart is empty"),
+        Err(CheckoutError::MixedCurrencies) => {
+            return HttpResponse::BadRequest().body("All artworks in one order must be priced in the same currency")
+        }
+        Err(CheckoutError::Unavailable(id_artwork)) => {
+            return HttpResponse::Conflict().body(format!("Artwork {} is no longer available", id_artwork))
+        }
+        Err(CheckoutError::Discount(reason) | CheckoutError::Shipping(reason)) => return HttpResponse::BadRequest().body(reason),
+        Err(CheckoutError::Database(e)) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    if let Err(e) = tx.commit().await {
+        return HttpResponse::InternalServerError().body(format!("Database error: {}", e));
+    }
+    println!("🛒 Customer {} checked out order {} with {} artworks", principal.customer_id, id_order, line_count);
+
+    match order_controller::fetch_detailed_orders(&pool, Some(&principal.customer_id), Some(&id_order)).await {
+        Ok(mut orders) => match orders.pop() {
+            Some(order) => HttpResponse::Created().json(order),
+            None => HttpResponse::InternalServerError().body("Order vanished after checkout"),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
@@ -1,6 +1,14 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sqlx::SqlitePool; // ודא ש-Row הוסר, כי נשתמש ב-query_as
-use crate::models::customer::{Customer};
+use actix_web::{get, post, put, delete, http::header, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::{FromRow, Row, SqlitePool}; // Row נדרש רק עבור password_hash בהתחברות
+use crate::auth::password;
+use crate::auth::principal::Principal;
+use crate::controllers::{email_verification_controller, init_db, login_throttle_controller, privacy_controller, session_controller};
+use crate::mail::MailSender;
+use crate::models::customer::{Customer, CustomerLogin};
+use crate::models::role::{Role, RoleUpdate};
+use crate::models::session::LoginResponse;
+use crate::models::tax::TaxExemptionUpdate;
 use uuid::Uuid;
 
 pub async fn init_customers_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
@@ -13,22 +21,85 @@ pub async fn init_customers_table(pool: &SqlitePool) -> Result<(), sqlx::Error>
             last_name TEXT NOT NULL,
             email TEXT NOT NULL,
             phone TEXT NOT NULL,
-            address TEXT NOT NULL
+            address TEXT NOT NULL,
+            password_hash TEXT,
+            role TEXT NOT NULL DEFAULT 'customer',
+            email_verified_at TEXT,
+            erased_at TEXT,
+            artist_id TEXT,
+            FOREIGN KEY (artist_id) REFERENCES artists(artist_id)
         )
         "#
     )
     .execute(pool)
     .await?;
+    init_db::add_column_if_missing(pool, "customers", "password_hash", "TEXT").await?;
+    init_db::add_column_if_missing(pool, "customers", "role", "TEXT NOT NULL DEFAULT 'customer'").await?;
+    if init_db::add_column_if_missing(pool, "customers", "email_verified_at", "TEXT").await? {
+        // Accounts that predate verification keep working as before.
+        sqlx::query("UPDATE customers SET email_verified_at = ?")
+            .bind(Utc::now())
+            .execute(pool)
+            .await?;
+    }
+    init_db::add_column_if_missing(pool, "customers", "erased_at", "TEXT").await?;
+    init_db::add_column_if_missing(pool, "customers", "artist_id", "TEXT REFERENCES artists(artist_id)").await?;
+    init_db::add_column_if_missing(pool, "customers", "tax_exempt", "BOOLEAN NOT NULL DEFAULT 0").await?;
+    init_db::add_column_if_missing(pool, "customers", "tax_exempt_reason", "TEXT").await?;
     println!("✅ customer table ready");
     
     Ok(())
 }
 
+/// Promotes the account whose email is in `GALLERY_ADMIN_EMAIL` to admin, so a
+/// fresh install has someone who can hand out the other roles.
+pub async fn promote_bootstrap_admin(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let Ok(email) = std::env::var("GALLERY_ADMIN_EMAIL") else {
+        return Ok(());
+    };
+
+    let result = sqlx::query("UPDATE customers SET role = ? WHERE lower(email) = lower(?)")
+        .bind(Role::Admin)
+        .bind(email.trim())
+        .execute(pool)
+        .await?;
+    if result.rows_affected() > 0 {
+        println!("👑 {} has the admin role", email);
+    } else {
+        println!("⚠️ GALLERY_ADMIN_EMAIL is set but no customer is registered with {}", email);
+    }
+
+    Ok(())
+}
+
+/// Minimal shape check: one '@', something before it, a dotted domain after it.
+pub fn is_valid_email(email: &str) -> bool {
+    let email = email.trim();
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && !email.chars().any(char::is_whitespace)
+                && domain.split('.').count() >= 2
+                && domain.split('.').all(|part| !part.is_empty())
+        }
+        None => false,
+    }
+}
+
+/// `Some(true)` if the customer exists and has verified their email address.
+pub async fn is_email_verified(pool: &SqlitePool, customer_id: &str) -> Result<Option<bool>, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>("SELECT email_verified_at IS NOT NULL FROM customers WHERE customer_id = ?")
+        .bind(customer_id)
+        .fetch_optional(pool)
+        .await
+}
+
 #[get("/")] // נתיב יחסי ל-scope של הלקוחות (ככל הנראה "/customers")
 pub async fn get_customers(pool: web::Data<SqlitePool>) -> impl Responder {
     match sqlx::query_as::<_, Customer>(
         r#"
-        SELECT customer_id, first_name, last_name, email, phone, address
+        SELECT customer_id, first_name, last_name, email, phone, address, role, email_verified_at, erased_at, artist_id, tax_exempt, tax_exempt_reason
         FROM customers
         ORDER BY last_name, first_name
         "#
@@ -58,16 +129,47 @@ pub async fn get_customers_in_jerusalem(pool: web::Data<SqlitePool>) -> impl Res
 }
 
 #[post("/")]
-pub async fn create_customer(pool: web::Data<SqlitePool>, customer: web::Json<Customer>) -> impl Responder {
+pub async fn create_customer(pool: web::Data<SqlitePool>, mailer: web::Data<dyn MailSender>, customer: web::Json<Customer>) -> impl Responder {
+    if !is_valid_email(&customer.email) {
+        return HttpResponse::BadRequest().body("Invalid email address");
+    }
+    let plain_password = match customer.password.as_deref() {
+        Some(p) if p.chars().count() >= password::MIN_PASSWORD_LENGTH => p,
+        _ => {
+            return HttpResponse::BadRequest().body(format!(
+                "Password must be at least {} characters long",
+                password::MIN_PASSWORD_LENGTH
+            ))
+        }
+    };
+
+    match sqlx::query("SELECT customer_id FROM customers WHERE lower(email) = lower(?)")
+        .bind(customer.email.trim())
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(_)) => return HttpResponse::Conflict().body("Email is already registered"),
+        Ok(None) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    let password_hash = match password::hash_password(plain_password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            eprintln!("Failed to hash password: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to store password");
+        }
+    };
 
     let id =Uuid::new_v4().to_string();
-    match sqlx::query("INSERT INTO CUSTOMERS (customer_id, first_name, last_name, email, phone, address) VALUES (?, ?, ?, ?, ?, ?)")
+    match sqlx::query("INSERT INTO CUSTOMERS (customer_id, first_name, last_name, email, phone, address, password_hash) VALUES (?, ?, ?, ?, ?, ?, ?)")
         .bind(&id)
         .bind(&customer.first_name)
         .bind(&customer.last_name)
-        .bind(&customer.email)
+        .bind(customer.email.trim())
         .bind(&customer.phone)
         .bind(&customer.address)
+        .bind(&password_hash)
         .execute(&**pool)
         .await
     {
@@ -76,56 +178,151 @@ pub async fn create_customer(pool: web::Data<SqlitePool>, customer: web::Json<Cu
                customer_id: id,
                first_name: customer.first_name.clone(),
                last_name: customer.last_name.clone(),
-               email: customer.email.clone(),
+               email: customer.email.trim().to_string(),
                phone: customer.phone.clone(),
                address: customer.address.clone(),
+               role: Role::Customer,
+               email_verified_at: None,
+               erased_at: None,
+               artist_id: None,
+               tax_exempt: false,
+               tax_exempt_reason: None,
+               password: None,
             };
+            // The account exists either way; a failed mail can be resent by an admin.
+            if let Err(e) = email_verification_controller::send_verification_email(&pool, mailer.get_ref(), &new_customer.customer_id, &new_customer.email).await {
+                eprintln!("Failed to send verification mail to {}: {}", new_customer.email, e);
+            }
             HttpResponse::Created().json(new_customer)
         }
         Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
     }
 }
-#[delete("/{customer_id}")]
-pub async fn delete_customer(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
-    let customer_id = path.into_inner();
-    
-    match sqlx::query("DELETE FROM customers WHERE customer_id = ?")
-        .bind(&customer_id)
-        .execute(&**pool)
-        .await
-    {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                HttpResponse::Ok().body("customer deleted successfully")
-            } else {
-                HttpResponse::NotFound().body("customer not found")
+
+#[post("/login")]
+pub async fn login_customer(req: HttpRequest, pool: web::Data<SqlitePool>, credentials: web::Json<CustomerLogin>) -> impl Responder {
+    // peer_addr rather than X-Forwarded-For: a spoofable header would let clients dodge the IP limit.
+    let client_ip = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_else(|| "unknown".to_string());
+    let account = login_throttle_controller::account_subject(&credentials.email);
+    let ip = login_throttle_controller::ip_subject(&client_ip);
+
+    for subject in [&account, &ip] {
+        match login_throttle_controller::locked_until(&pool, subject).await {
+            Ok(Some(until)) => {
+                let retry_after = (until - Utc::now()).num_seconds().max(1);
+                return HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                    .body(format!("Too many failed login attempts. Try again after {}", until.format("%Y-%m-%d %H:%M:%S UTC")));
             }
+            Ok(None) => {}
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
         }
+    }
+
+    let row = match sqlx::query(
+        r#"
+        SELECT customer_id, first_name, last_name, email, phone, address, role, email_verified_at, erased_at, artist_id, tax_exempt, tax_exempt_reason, password_hash
+        FROM customers
+        WHERE lower(email) = lower(?)
+        "#
+    )
+    .bind(credentials.email.trim())
+    .fetch_optional(&**pool)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    // Customers created before passwords existed have no hash and cannot log in.
+    let password_hash: Option<String> = row.as_ref().and_then(|row| row.get("password_hash"));
+    let authenticated = match password_hash {
+        Some(hash) => password::verify_password(&credentials.password, &hash),
+        None => {
+            password::verify_dummy_password(&credentials.password);
+            false
+        }
+    };
+    let row = match row {
+        Some(row) if authenticated => row,
+        _ => {
+            return match login_throttle_controller::register_failed_login(&pool, &account, &ip).await {
+                Ok(()) => HttpResponse::Unauthorized().body("Invalid credentials"),
+                Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+            };
+        }
+    };
+
+    if let Err(e) = login_throttle_controller::reset(&pool, &account).await {
+        return HttpResponse::InternalServerError().body(format!("Database error: {}", e));
+    }
+
+    let customer = match Customer::from_row(&row) {
+        Ok(customer) => customer,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    match session_controller::create_session(&pool, &customer.customer_id).await {
+        Ok((token, expires_at)) => HttpResponse::Ok().json(LoginResponse { token, expires_at, customer }),
         Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
     }
 }
 
+#[delete("/{customer_id}")]
+pub async fn delete_customer(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+    // Orders reference the customer, so deletion anonymises instead of removing the row.
+    privacy_controller::erasure_response(&pool, &path.into_inner()).await
+}
+
 #[put("/{customer_id}")]
 pub async fn update_customer(
     pool: web::Data<SqlitePool>,
+    mailer: web::Data<dyn MailSender>,
+    principal: Principal,
     path: web::Path<String>, // Change to String
     updated: web::Json<Customer>,
 ) -> impl Responder {
     let customer_id = path.into_inner();
+
+    // Someone else's profile looks exactly like a missing one.
+    if !principal.can_access_customer(&customer_id) {
+        return HttpResponse::NotFound().body("Customer with provided customer_id does not exist");
+    }
+    if !is_valid_email(&updated.email) {
+        return HttpResponse::BadRequest().body("Invalid email address");
+    }
     
     // Check if customer exists
-    match sqlx::query("SELECT customer_id FROM customers WHERE customer_id = ?") // Corrected query
+    match sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE customer_id = ?") // Corrected query
         .bind(&customer_id)
         .fetch_optional(&**pool)
         .await
     {
-        Ok(Some(_)) => {
-            match sqlx::query("UPDATE customers SET first_name = ?, last_name = ?, email = ?, phone = ?, address = ? WHERE customer_id = ?") // Corrected table name
+        Ok(Some(existing)) if existing.erased_at.is_some() => {
+            HttpResponse::Conflict().body("Customer data has been erased")
+        }
+        Ok(Some(existing)) => {
+            // A new address has to be verified again.
+            let email_changed = !existing.email.eq_ignore_ascii_case(updated.email.trim());
+            let email_verified_at = if email_changed { None } else { existing.email_verified_at };
+            if email_changed {
+                match sqlx::query("SELECT customer_id FROM customers WHERE lower(email) = lower(?) AND customer_id != ?")
+                    .bind(updated.email.trim())
+                    .bind(&customer_id)
+                    .fetch_optional(&**pool)
+                    .await
+                {
+                    Ok(Some(_)) => return HttpResponse::Conflict().body("Email is already registered"),
+                    Ok(None) => {}
+                    Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+                }
+            }
+            match sqlx::query("UPDATE customers SET first_name = ?, last_name = ?, email = ?, phone = ?, address = ?, email_verified_at = ? WHERE customer_id = ?") // Corrected table name
                 .bind(&updated.first_name)
                 .bind(&updated.last_name)
-                .bind(&updated.email)
+                .bind(updated.email.trim())
                 .bind(&updated.phone)
                 .bind(&updated.address)
+                .bind(email_verified_at)
                 .bind(&customer_id) // Bind the customer_id from the path
                 .execute(&**pool)
                 .await
@@ -136,10 +333,22 @@ pub async fn update_customer(
                             customer_id: customer_id.clone(), // Use the customer_id from the path
                             first_name: updated.first_name.clone(),
                             last_name: updated.last_name.clone(),
-                            email: updated.email.clone(),
+                            email: updated.email.trim().to_string(),
                             phone: updated.phone.clone(),
                             address: updated.address.clone(),
+                            role: existing.role,
+                            email_verified_at,
+                            erased_at: existing.erased_at,
+                            artist_id: existing.artist_id,
+                            tax_exempt: existing.tax_exempt,
+                            tax_exempt_reason: existing.tax_exempt_reason,
+                            password: None,
                         };
+                        if email_changed {
+                            if let Err(e) = email_verification_controller::send_verification_email(&pool, mailer.get_ref(), &customer_id, &updated_customer.email).await {
+                                eprintln!("Failed to send verification mail to {}: {}", updated_customer.email, e);
+                            }
+                        }
                         HttpResponse::Ok().json(updated_customer)
                     } else {
                         HttpResponse::NotFound().body("Customer not found")
@@ -151,4 +360,96 @@ pub async fn update_customer(
         Ok(None) => HttpResponse::NotFound().body("Customer with provided customer_id does not exist"), // Corrected response
         Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
     }
-}
\ No newline at end of file
+}
+
+#[put("/{customer_id}/role")]
+pub async fn update_customer_role(
+    pool: web::Data<SqlitePool>,
+    principal: Principal,
+    path: web::Path<String>,
+    update: web::Json<RoleUpdate>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+
+    if customer_id == principal.customer_id && update.role != Role::Admin {
+        return HttpResponse::Forbidden().body("Forbidden: admins cannot remove their own admin role");
+    }
+
+    // Only artist accounts are linked to an artist; any other role drops the link.
+    let artist_id = match (update.role, update.artist_id.as_deref()) {
+        (Role::Artist, None) => return HttpResponse::BadRequest().body("The artist role requires an artist_id"),
+        (Role::Artist, Some(artist_id)) => {
+            match sqlx::query_scalar::<_, String>(
+                r#"
+                SELECT a.artist_id FROM artists a
+                WHERE a.artist_id = ?
+                  AND NOT EXISTS (SELECT 1 FROM customers c WHERE c.artist_id = a.artist_id AND c.customer_id != ?)
+                "#
+            )
+                .bind(artist_id)
+                .bind(&customer_id)
+                .fetch_optional(&**pool)
+                .await
+            {
+                Ok(Some(artist_id)) => Some(artist_id),
+                Ok(None) => return HttpResponse::BadRequest().body("Artist not found or already linked to another account"),
+                Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+            }
+        }
+        (_, _) => None,
+    };
+
+    match sqlx::query("UPDATE customers SET role = ?, artist_id = ? WHERE customer_id = ?")
+        .bind(update.role)
+        .bind(&artist_id)
+        .bind(&customer_id)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                println!("🔑 {} set role of customer {} to {}", principal.customer_id, customer_id, update.role);
+                HttpResponse::Ok().body(format!("Customer {} now has the {} role", customer_id, update.role))
+            } else {
+                HttpResponse::NotFound().body("Customer not found")
+            }
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[put("/{customer_id}/tax-exemption")]
+pub async fn update_customer_tax_exemption(
+    pool: web::Data<SqlitePool>,
+    principal: Principal,
+    path: web::Path<String>,
+    update: web::Json<TaxExemptionUpdate>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+
+    // The reason is printed on invoices, so an exemption without one is refused.
+    let reason = update.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    if update.tax_exempt && reason.is_none() {
+        return HttpResponse::BadRequest().body("A tax exemption needs a reason");
+    }
+    let reason = if update.tax_exempt { reason } else { None };
+
+    // Only orders placed from now on are affected; existing orders keep the tax they were charged.
+    match sqlx::query("UPDATE customers SET tax_exempt = ?, tax_exempt_reason = ? WHERE customer_id = ?")
+        .bind(update.tax_exempt)
+        .bind(reason)
+        .bind(&customer_id)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                println!("🧾 {} set tax exemption of customer {} to {}", principal.customer_id, customer_id, update.tax_exempt);
+                HttpResponse::Ok().body(format!("Customer {} is now {}", customer_id, if update.tax_exempt { "tax exempt" } else { "charged tax" }))
+            } else {
+                HttpResponse::NotFound().body("Customer not found")
+            }
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
@@ -0,0 +1,287 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use crate::controllers::tax_controller;
+use crate::models::discount::{DiscountCode, DiscountKind};
+use crate::models::money::Money;
+
+pub async fn init_discount_codes_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating discount_codes table if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS discount_codes (
+            id_discount TEXT PRIMARY KEY NOT NULL,
+            code TEXT NOT NULL UNIQUE,
+            kind TEXT NOT NULL,
+            percent_off INTEGER,
+            amount_off_minor INTEGER,
+            min_order_minor INTEGER,
+            currency TEXT,
+            valid_from TEXT,
+            valid_until TEXT,
+            max_uses INTEGER,
+            times_used INTEGER NOT NULL DEFAULT 0,
+            id_artist TEXT,
+            art_type TEXT,
+            is_active BOOLEAN NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (id_artist) REFERENCES artists(artist_id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ discount_codes table ready");
+
+    Ok(())
+}
+
+/// Codes are matched without regard to case or surrounding spaces.
+fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// Applies a discount code to an order whose lines are already written, spreading
+/// the discount over the lines it covers. Runs inside the checkout transaction;
+/// the inner `Err` explains to the customer why the code was refused.
+pub async fn apply_discount(conn: &mut SqliteConnection, id_order: &str, code: &str) -> Result<Result<Money, String>, sqlx::Error> {
+    let code = normalize_code(code);
+    let Some(discount) = sqlx::query_as::<_, DiscountCode>("SELECT * FROM discount_codes WHERE code = ?")
+        .bind(&code)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(Err(format!("Discount code {} does not exist", code)));
+    };
+    let now = Utc::now();
+    if !discount.is_active {
+        return Ok(Err(format!("Discount code {} is not active", code)));
+    }
+    if discount.valid_from.is_some_and(|from| now < from) {
+        return Ok(Err(format!("Discount code {} is not valid yet", code)));
+    }
+    if discount.valid_until.is_some_and(|until| now >= until) {
+        return Ok(Err(format!("Discount code {} has expired", code)));
+    }
+
+    let lines = sqlx::query_as::<_, (String, i64, String, i32, String, Option<String>)>(
+        r#"
+        SELECT aio.id_artwork_in_order, aio.price_minor, aio.currency, aio.amount, aio.id_artist, a.art_type
+        FROM artworks_in_order aio
+        LEFT JOIN ARTWORKS a ON a.id_artwork = aio.id_artwork
+        WHERE aio.id_order = ?
+        ORDER BY aio.id_artwork_in_order
+        "#
+    )
+    .bind(id_order)
+    .fetch_all(&mut *conn)
+    .await?;
+    let Some(currency) = lines.first().map(|line| line.2.clone()) else {
+        return Ok(Err("There is nothing in the order to discount".to_string()));
+    };
+    if discount.currency().is_some_and(|code_currency| code_currency != currency) {
+        return Ok(Err(format!("Discount code {} is only valid for orders in {}", code, discount.currency().unwrap_or_default())));
+    }
+    // (line, line total, whether the code covers it)
+    let lines: Vec<(String, i64, bool)> = lines
+        .into_iter()
+        .map(|(id_line, price_minor, _, amount, id_artist, art_type)| {
+            let covered = discount.id_artist.as_ref().is_none_or(|artist| artist == &id_artist)
+                && discount.art_type.as_ref().is_none_or(|wanted| {
+                    art_type.as_ref().is_some_and(|art_type| art_type.to_lowercase() == wanted.to_lowercase())
+                });
+            (id_line, price_minor * i64::from(amount), covered)
+        })
+        .collect();
+
+    let subtotal: i64 = lines.iter().map(|line| line.1).sum();
+    if let Some(min_order) = &discount.min_order {
+        if subtotal < min_order.minor {
+            return Ok(Err(format!("Discount code {} needs an order of at least {}", code, min_order)));
+        }
+    }
+    let covered: Vec<&(String, i64, bool)> = lines.iter().filter(|line| line.2).collect();
+    let covered_total: i64 = covered.iter().map(|line| line.1).sum();
+    if covered.is_empty() || covered_total == 0 {
+        return Ok(Err(format!("Discount code {} does not apply to any artwork in this order", code)));
+    }
+
+    let allocations: Vec<(&str, i64)> = match (discount.kind, discount.percent_off, &discount.amount_off) {
+        (DiscountKind::Percentage, Some(percent), _) => covered
+            .iter()
+            .map(|line| (line.0.as_str(), ((i128::from(line.1) * i128::from(percent) + 50) / 100) as i64))
+            .collect(),
+        (DiscountKind::Fixed, _, Some(amount_off)) => {
+            // Shared out in proportion to each line's price; every share is taken from
+            // what is left, so the shares add up to the discount exactly.
+            let mut remaining_discount = amount_off.minor.min(covered_total);
+            let mut remaining_total = covered_total;
+            covered
+                .iter()
+                .map(|line| {
+                    let share = (i128::from(remaining_discount) * i128::from(line.1) / i128::from(remaining_total)) as i64;
+                    remaining_discount -= share;
+                    remaining_total -= line.1;
+                    (line.0.as_str(), share)
+                })
+                .collect()
+        }
+        _ => return Ok(Err(format!("Discount code {} is misconfigured", code))),
+    };
+
+    let claimed = sqlx::query("UPDATE discount_codes SET times_used = times_used + 1 WHERE id_discount = ? AND (max_uses IS NULL OR times_used < max_uses)")
+        .bind(&discount.id_discount)
+        .execute(&mut *conn)
+        .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(Err(format!("Discount code {} has been used up", code)));
+    }
+    for (id_line, share) in &allocations {
+        sqlx::query("UPDATE artworks_in_order SET discount_minor = ? WHERE id_artwork_in_order = ?")
+            .bind(share)
+            .bind(id_line)
+            .execute(&mut *conn)
+            .await?;
+    }
+    sqlx::query("UPDATE ORDERS SET id_discount = ?, discount_code = ? WHERE id_order = ?")
+        .bind(&discount.id_discount)
+        .bind(&code)
+        .bind(id_order)
+        .execute(&mut *conn)
+        .await?;
+    // Tax is charged on what is left after the discount.
+    tax_controller::recompute_order_tax(conn, id_order).await?;
+
+    let total = Money::new(allocations.iter().map(|(_, share)| share).sum(), &currency).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    Ok(Ok(total))
+}
+
+#[get("/")]
+pub async fn get_discount_codes(pool: web::Data<SqlitePool>) -> impl Responder {
+    match sqlx::query_as::<_, DiscountCode>("SELECT * FROM discount_codes ORDER BY created_at DESC")
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(codes) => HttpResponse::Ok().json(codes),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/{id}")]
+pub async fn get_discount_code(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+    let id_discount = path.into_inner();
+    match sqlx::query_as::<_, DiscountCode>("SELECT * FROM discount_codes WHERE id_discount = ?")
+        .bind(&id_discount)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(code)) => HttpResponse::Ok().json(code),
+        Ok(None) => HttpResponse::NotFound().body(format!("Discount code with id {} not found", id_discount)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/")]
+pub async fn create_discount_code(pool: web::Data<SqlitePool>, discount: web::Json<DiscountCode>) -> impl Responder {
+    let mut discount = discount.into_inner();
+    if let Err(reason) = discount.validate() {
+        return HttpResponse::BadRequest().body(reason);
+    }
+    discount.id_discount = Uuid::new_v4().to_string();
+    discount.code = normalize_code(&discount.code);
+    discount.times_used = 0;
+
+    match sqlx::query(
+        r#"
+        INSERT INTO discount_codes (
+            id_discount, code, kind, percent_off, amount_off_minor, min_order_minor, currency,
+            valid_from, valid_until, max_uses, id_artist, art_type, is_active, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&discount.id_discount)
+    .bind(&discount.code)
+    .bind(discount.kind)
+    .bind(discount.percent_off)
+    .bind(discount.amount_off.as_ref().map(|amount| amount.minor))
+    .bind(discount.min_order.as_ref().map(|amount| amount.minor))
+    .bind(discount.currency())
+    .bind(discount.valid_from)
+    .bind(discount.valid_until)
+    .bind(discount.max_uses)
+    .bind(&discount.id_artist)
+    .bind(&discount.art_type)
+    .bind(discount.is_active)
+    .bind(Utc::now())
+    .execute(&**pool)
+    .await
+    {
+        Ok(_) => {
+            println!("🏷️ Discount code {} created", discount.code);
+            HttpResponse::Created().json(discount)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().body(format!("Discount code {} already exists", discount.code))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[put("/{id}")]
+pub async fn update_discount_code(pool: web::Data<SqlitePool>, path: web::Path<String>, discount: web::Json<DiscountCode>) -> impl Responder {
+    let id_discount = path.into_inner();
+    let mut discount = discount.into_inner();
+    if let Err(reason) = discount.validate() {
+        return HttpResponse::BadRequest().body(reason);
+    }
+    discount.code = normalize_code(&discount.code);
+
+    match sqlx::query_as::<_, DiscountCode>(
+        r#"
+        UPDATE discount_codes SET
+            code = ?, kind = ?, percent_off = ?, amount_off_minor = ?, min_order_minor = ?, currency = ?,
+            valid_from = ?, valid_until = ?, max_uses = ?, id_artist = ?, art_type = ?, is_active = ?
+        WHERE id_discount = ?
+        RETURNING *
+        "#
+    )
+    .bind(&discount.code)
+    .bind(discount.kind)
+    .bind(discount.percent_off)
+    .bind(discount.amount_off.as_ref().map(|amount| amount.minor))
+    .bind(discount.min_order.as_ref().map(|amount| amount.minor))
+    .bind(discount.currency())
+    .bind(discount.valid_from)
+    .bind(discount.valid_until)
+    .bind(discount.max_uses)
+    .bind(&discount.id_artist)
+    .bind(&discount.art_type)
+    .bind(discount.is_active)
+    .bind(&id_discount)
+    .fetch_optional(&**pool)
+    .await
+    {
+        Ok(Some(updated)) => HttpResponse::Ok().json(updated),
+        Ok(None) => HttpResponse::NotFound().body(format!("Discount code with id {} not found", id_discount)),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().body(format!("Discount code {} already exists", discount.code))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[delete("/{id}")]
+pub async fn delete_discount_code(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+    let id_discount = path.into_inner();
+    // Orders keep the code they were placed with, so deleting one leaves their history intact.
+    match sqlx::query("DELETE FROM discount_codes WHERE id_discount = ?")
+        .bind(&id_discount)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => HttpResponse::Ok().body(format!("Discount code with id {} deleted successfully", id_discount)),
+        Ok(_) => HttpResponse::NotFound().body(format!("Discount code with id {} not found", id_discount)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
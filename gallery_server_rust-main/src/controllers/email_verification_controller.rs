@@ -0,0 +1,92 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use crate::controllers::account_token_controller;
+use crate::mail::{self, MailSender, OutgoingMail};
+use crate::models::account_token::{EmailVerificationQuery, TokenPurpose};
+
+pub const VERIFICATION_TOKEN_TTL_HOURS: i64 = 48;
+
+/// Issues a fresh verification token for the customer and mails the link to `email`.
+pub async fn send_verification_email(pool: &SqlitePool, mailer: &dyn MailSender, customer_id: &str, email: &str) -> Result<(), String> {
+    let token = account_token_controller::issue_token(
+        pool,
+        customer_id,
+        TokenPurpose::EmailVerification,
+        Duration::hours(VERIFICATION_TOKEN_TTL_HOURS),
+    )
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let verification_mail = OutgoingMail {
+        to: email.to_string(),
+        subject: "Confirm your email address".to_string(),
+        body: format!(
+            "Welcome to the gallery!\n\nPlease confirm your email address within {} hours by opening this link:\n{}/verify-email?token={}\n\nYou can browse right away, but orders can only be placed once the address is confirmed.",
+            VERIFICATION_TOKEN_TTL_HOURS,
+            mail::app_url(),
+            token
+        ),
+    };
+    mailer.send(&verification_mail).await.map_err(|e| e.to_string())
+}
+
+#[get("/verify")]
+pub async fn verify_email(pool: web::Data<SqlitePool>, query: web::Query<EmailVerificationQuery>) -> impl Responder {
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let customer_id = match account_token_controller::consume_token(&mut tx, &query.token, TokenPurpose::EmailVerification).await {
+        Ok(Some(customer_id)) => customer_id,
+        Ok(None) => return HttpResponse::BadRequest().body("Invalid or expired verification token"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let result = async {
+        sqlx::query("UPDATE customers SET email_verified_at = ? WHERE customer_id = ?")
+            .bind(Utc::now())
+            .bind(&customer_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => HttpResponse::Ok().body("Email address verified"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/{customer_id}/verification-email")]
+pub async fn resend_verification_email(
+    pool: web::Data<SqlitePool>,
+    mailer: web::Data<dyn MailSender>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+
+    let (email, verified) = match sqlx::query_as::<_, (String, bool)>("SELECT email, email_verified_at IS NOT NULL FROM customers WHERE customer_id = ?")
+        .bind(&customer_id)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(customer)) => customer,
+        Ok(None) => return HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    if verified {
+        return HttpResponse::Conflict().body("Email address is already verified");
+    }
+
+    match send_verification_email(&pool, mailer.get_ref(), &customer_id, &email).await {
+        Ok(()) => HttpResponse::Ok().body(format!("Verification email sent to {}", email)),
+        Err(e) => {
+            eprintln!("Failed to resend verification mail to {}: {}", email, e);
+            HttpResponse::InternalServerError().body("Failed to send verification email")
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use actix_web::{http::StatusCode, rt, HttpResponse};
+use chrono::{Duration, Utc};
+use sqlx::{FromRow, SqlitePool};
+
+/// How long a key and its response are kept. After that the key is forgotten
+/// and may be used for a new request.
+pub const IDEMPOTENCY_RETENTION_HOURS: i64 = 24;
+const SWEEP_INTERVAL_SECONDS: u64 = 3600;
+
+pub async fn init_idempotency_keys_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating idempotency_keys table if not exists...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS idempotency_keys (
+            customer_id TEXT NOT NULL,
+            idempotency_key TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            status_code INTEGER,
+            content_type TEXT,
+            response_body BLOB,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (customer_id, idempotency_key)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    // A request cut off by a restart never stores its response; let its key be retried.
+    sqlx::query("DELETE FROM idempotency_keys WHERE status_code IS NULL")
+        .execute(pool)
+        .await?;
+    println!("✅ idempotency_keys table ready");
+
+    Ok(())
+}
+
+/// A request made earlier with the same key. `status_code` is empty while
+/// that request is still being handled.
+#[derive(Debug, FromRow)]
+pub struct StoredRequest {
+    pub fingerprint: String,
+    pub status_code: Option<i64>,
+    pub content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+impl StoredRequest {
+    /// The stored response, sent again in place of running the request twice.
+    pub fn replay(&self) -> Option<HttpResponse> {
+        let status = StatusCode::from_u16(u16::try_from(self.status_code?).ok()?).ok()?;
+        let mut response = HttpResponse::build(status);
+        response.insert_header(("Idempotency-Replayed", "true"));
+        if let Some(content_type) = &self.content_type {
+            response.content_type(content_type.as_str());
+        }
+        Some(response.body(self.response_body.clone().unwrap_or_default()))
+    }
+}
+
+pub enum Reservation {
+    /// The key is new (or had expired) and now belongs to this request.
+    Reserved,
+    Existing(StoredRequest),
+}
+
+/// Claims `key` for a request with the given fingerprint, or returns the
+/// request that already holds it.
+pub async fn reserve_key(pool: &SqlitePool, customer_id: &str, key: &str, fingerprint: &str) -> Result<Reservation, sqlx::Error> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM idempotency_keys WHERE customer_id = ? AND idempotency_key = ? AND created_at < ?")
+        .bind(customer_id)
+        .bind(key)
+        .bind(now - Duration::hours(IDEMPOTENCY_RETENTION_HOURS))
+        .execute(&mut *tx)
+        .await?;
+    let inserted = sqlx::query(
+        "INSERT OR IGNORE INTO idempotency_keys (customer_id, idempotency_key, fingerprint, created_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(customer_id)
+    .bind(key)
+    .bind(fingerprint)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    let reservation = if inserted.rows_affected() > 0 {
+        Reservation::Reserved
+    } else {
+        let stored = sqlx::query_as::<_, StoredRequest>(
+            "SELECT fingerprint, status_code, content_type, response_body FROM idempotency_keys WHERE customer_id = ? AND idempotency_key = ?",
+        )
+        .bind(customer_id)
+        .bind(key)
+        .fetch_one(&mut *tx)
+        .await?;
+        Reservation::Existing(stored)
+    };
+    tx.commit().await?;
+    Ok(reservation)
+}
+
+pub async fn store_response(
+    pool: &SqlitePool,
+    customer_id: &str,
+    key: &str,
+    status: StatusCode,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE idempotency_keys SET status_code = ?, content_type = ?, response_body = ? WHERE customer_id = ? AND idempotency_key = ?",
+    )
+    .bind(i64::from(status.as_u16()))
+    .bind(content_type)
+    .bind(body)
+    .bind(customer_id)
+    .bind(key)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Gives a key back when its request failed on our side, so a retry runs again.
+pub async fn release_key(pool: &SqlitePool, customer_id: &str, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE customer_id = ? AND idempotency_key = ?")
+        .bind(customer_id)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn purge_expired_keys(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let purged = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?")
+        .bind(Utc::now() - Duration::hours(IDEMPOTENCY_RETENTION_HOURS))
+        .execute(pool)
+        .await?;
+    Ok(purged.rows_affected())
+}
+
+/// Runs `purge_expired_keys` every hour for as long as the server is up.
+pub fn spawn_idempotency_sweeper(pool: SqlitePool) {
+    rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            match purge_expired_keys(&pool).await {
+                Ok(0) => {}
+                Ok(purged) => println!("🔑 Purged {} expired idempotency keys", purged),
+                Err(e) => eprintln!("Failed to purge expired idempotency keys: {}", e),
+            }
+        }
+    });
+}
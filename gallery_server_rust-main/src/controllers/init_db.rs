@@ -1,9 +1,23 @@
-use sqlx::{self, SqlitePool};
+use sqlx::{self, Row, SqlitePool};
 use crate::controllers::customer_controller;
 use crate::controllers::artist_controller;
 use crate::controllers::artwork_controller;
 use crate::controllers::order_controller;
 use crate::controllers::artwork_in_order_controller;
+use crate::controllers::session_controller;
+use crate::controllers::account_token_controller;
+use crate::controllers::login_throttle_controller;
+use crate::controllers::api_key_controller;
+use crate::controllers::cart_controller;
+use crate::controllers::payment_controller;
+use crate::controllers::return_controller;
+use crate::controllers::discount_controller;
+use crate::controllers::tax_controller;
+use crate::controllers::shipping_controller;
+use crate::controllers::invoice_controller;
+use crate::controllers::idempotency_controller;
+use crate::controllers::auction_controller;
+use crate::controllers::offer_controller;
 
 pub async fn init_db() -> Result<SqlitePool, sqlx::Error> {
     let database_url = "sqlite:src/mydb.db";
@@ -13,10 +27,59 @@ pub async fn init_db() -> Result<SqlitePool, sqlx::Error> {
 
     customer_controller::init_customers_table(&pool).await?;
     artist_controller::init_artists_table(&pool).await?;
+    tax_controller::init_tax_rates_table(&pool).await?;
     artwork_controller::init_artwork_table(&pool).await?;
     order_controller::init_orders_table(&pool).await?;
     artwork_in_order_controller::init_artworks_in_order_table(&pool).await?;
+    session_controller::init_sessions_table(&pool).await?;
+    account_token_controller::init_account_tokens_table(&pool).await?;
+    login_throttle_controller::init_login_throttle_table(&pool).await?;
+    api_key_controller::init_api_keys_table(&pool).await?;
+    cart_controller::init_carts_table(&pool).await?;
+    payment_controller::init_payments_table(&pool).await?;
+    return_controller::init_returns_table(&pool).await?;
+    discount_controller::init_discount_codes_table(&pool).await?;
+    shipping_controller::init_shipping_tables(&pool).await?;
+    invoice_controller::init_invoices_table(&pool).await?;
+    idempotency_controller::init_idempotency_keys_table(&pool).await?;
+    auction_controller::init_auction_tables(&pool).await?;
+    offer_controller::init_offers_table(&pool).await?;
+
+    customer_controller::promote_bootstrap_admin(&pool).await?;
 
     
     Ok(pool)
-}
\ No newline at end of file
+}
+
+/// Adds a column to an existing table when it is missing, so databases created
+/// by older versions of the server pick up new fields on startup. Returns
+/// whether the column was added, for callers that need to backfill it.
+pub async fn add_column_if_missing(pool: &SqlitePool, table: &str, column: &str, definition: &str) -> Result<bool, sqlx::Error> {
+    if !has_column(pool, table, column).await? {
+        println!("🔧 Adding column {}.{}", table, column);
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+pub async fn has_column(pool: &SqlitePool, table: &str, column: &str) -> Result<bool, sqlx::Error> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await?;
+    Ok(columns.iter().any(|c| c.get::<String, _>("name") == column))
+}
+
+/// Drops a column that a migration has replaced, once its data has been copied over.
+pub async fn drop_column_if_exists(pool: &SqlitePool, table: &str, column: &str) -> Result<(), sqlx::Error> {
+    if has_column(pool, table, column).await? {
+        println!("🔧 Dropping column {}.{}", table, column);
+        sqlx::query(&format!("ALTER TABLE {} DROP COLUMN {}", table, column))
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use crate::auth::principal::Principal;
+use crate::controllers::{init_db, order_controller};
+use crate::invoices::{render_invoice, GalleryDetails, InvoiceDocument, InvoiceOrder};
+use crate::models::order::OrderStatus;
+use crate::models::payment::Payment;
+
+pub async fn init_invoices_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating invoices table if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS invoices (
+            invoice_number INTEGER PRIMARY KEY NOT NULL,
+            id_order TEXT NOT NULL UNIQUE,
+            issued_at TEXT NOT NULL,
+            document TEXT,
+            FOREIGN KEY (id_order) REFERENCES ORDERS(id_order)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    // Invoices issued before their content was stored get it on their next download.
+    init_db::add_column_if_missing(pool, "invoices", "document", "TEXT").await?;
+    println!("✅ invoices table ready");
+
+    Ok(())
+}
+
+/// Returns the order's invoice number and issue date, issuing the next number
+/// the first time an invoice is asked for. Numbers run 1, 2, 3… without gaps
+/// and an order keeps its number however often the invoice is downloaded.
+async fn issue_invoice(pool: &SqlitePool, id_order: &str) -> Result<(i64, DateTime<Utc>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let issued = sqlx::query(
+        r#"
+        INSERT INTO invoices (invoice_number, id_order, issued_at)
+        SELECT (SELECT COALESCE(MAX(invoice_number), 0) + 1 FROM invoices), ?, ?
+        WHERE NOT EXISTS (SELECT 1 FROM invoices WHERE id_order = ?)
+        "#
+    )
+    .bind(id_order)
+    .bind(Utc::now())
+    .bind(id_order)
+    .execute(&mut *tx)
+    .await?;
+    let invoice = sqlx::query_as::<_, (i64, DateTime<Utc>)>("SELECT invoice_number, issued_at FROM invoices WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    if issued.rows_affected() > 0 {
+        println!("🧾 Invoice {} issued for order {}", invoice.0, id_order);
+    }
+    Ok(invoice)
+}
+
+fn db_error(e: sqlx::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+async fn stored_invoice(pool: &SqlitePool, id_order: &str) -> Result<Option<InvoiceDocument>, String> {
+    let document = sqlx::query_scalar::<_, Option<String>>("SELECT document FROM invoices WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .flatten();
+    document.map(|document| serde_json::from_str(&document).map_err(|e| format!("Invoice error: {}", e))).transpose()
+}
+
+/// The order's invoice as it was issued. The first request issues it from the
+/// order, customer and payment as they are now and stores the result; every
+/// later download serves that stored copy, whatever has changed since.
+async fn load_invoice(pool: &SqlitePool, id_order: &str) -> Result<Option<InvoiceDocument>, String> {
+    if let Some(invoice) = stored_invoice(pool, id_order).await? {
+        return Ok(Some(invoice));
+    }
+    let Some(invoice) = build_invoice(pool, id_order).await? else {
+        return Ok(None);
+    };
+    let document = serde_json::to_string(&invoice).map_err(|e| format!("Invoice error: {}", e))?;
+    // Two first downloads at once both build a copy; the one stored first wins.
+    sqlx::query("UPDATE invoices SET document = ? WHERE id_order = ? AND document IS NULL")
+        .bind(&document)
+        .bind(id_order)
+        .execute(pool)
+        .await
+        .map_err(db_error)?;
+    stored_invoice(pool, id_order).await
+}
+
+async fn build_invoice(pool: &SqlitePool, id_order: &str) -> Result<Option<InvoiceDocument>, String> {
+    let orders = order_controller::fetch_detailed_orders(pool, None, Some(id_order)).await.map_err(db_error)?;
+    let Some(order) = orders.into_iter().next() else {
+        return Ok(None);
+    };
+    let order: InvoiceOrder = serde_json::from_value(order).map_err(|e| format!("Invoice error: {}", e))?;
+
+    let (invoice_number, issued_at) = issue_invoice(pool, id_order).await.map_err(db_error)?;
+    let tax_exempt_reason = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT c.tax_exempt_reason FROM ORDERS o JOIN customers c ON c.customer_id = o.id_customer WHERE o.id_order = ?",
+    )
+    .bind(id_order)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?
+    .flatten();
+    let payment = sqlx::query_as::<_, Payment>(
+        "SELECT * FROM payments WHERE id_order = ? AND status IN ('captured', 'refunded') ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(id_order)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Some(InvoiceDocument {
+        invoice_number,
+        issued_at,
+        gallery: GalleryDetails::from_env(),
+        order,
+        tax_exempt_reason,
+        payment,
+    }))
+}
+
+#[get("/{id_order}/invoice.pdf")]
+pub async fn get_order_invoice(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_order = path.into_inner();
+    let order = sqlx::query_as::<_, (String, OrderStatus)>("SELECT id_customer, status FROM ORDERS WHERE id_order = ?")
+        .bind(&id_order)
+        .fetch_optional(&**pool)
+        .await;
+    match order {
+        Ok(Some((id_customer, _))) if !principal.can_access_customer(&id_customer) => {
+            return HttpResponse::NotFound().body("Order not found")
+        }
+        Ok(Some((_, OrderStatus::Pending | OrderStatus::Cancelled))) => {
+            return HttpResponse::Conflict().body("Invoices are issued once an order is paid")
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    let invoice = match load_invoice(&pool, &id_order).await {
+        Ok(Some(invoice)) => invoice,
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+    let filename = format!("invoice-{}.pdf", invoice.number());
+
+    // Laying out the PDF is CPU work, so it runs off the async workers.
+    match web::block(move || render_invoice(&invoice)).await {
+        Ok(Ok(pdf)) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((header::CONTENT_DISPOSITION, format!("inline; filename=\"{}\"", filename)))
+            .body(pdf),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(format!("Invoice error: {}", e)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Invoice error: {}", e)),
+    }
+}
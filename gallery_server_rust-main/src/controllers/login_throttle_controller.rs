@@ -0,0 +1,164 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, SqlitePool};
+use crate::auth::principal::Principal;
+
+/// Consecutive failures on one account before it is locked.
+pub const ACCOUNT_FAILURE_THRESHOLD: i64 = 5;
+/// Failures from one client IP (across any accounts) before it is blocked.
+pub const IP_FAILURE_THRESHOLD: i64 = 20;
+/// First lockout length; every further lockout of the same subject doubles it.
+pub const BASE_LOCKOUT_MINUTES: i64 = 15;
+pub const MAX_LOCKOUT_MINUTES: i64 = 24 * 60;
+/// Failures older than this are forgotten.
+pub const FAILURE_WINDOW_HOURS: i64 = 24;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct LoginThrottle {
+    pub subject: String,
+    pub failures: i64,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+pub async fn init_login_throttle_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating login_throttle table if not exists...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS login_throttle (
+            subject TEXT PRIMARY KEY NOT NULL,
+            failures INTEGER NOT NULL,
+            last_failure_at TEXT NOT NULL,
+            locked_until TEXT
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ login_throttle table ready");
+
+    Ok(())
+}
+
+pub fn account_subject(email: &str) -> String {
+    format!("account:{}", email.trim().to_lowercase())
+}
+
+pub fn ip_subject(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+/// Returns when the subject's current lock ends, if it is locked.
+pub async fn locked_until(pool: &SqlitePool, subject: &str) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let locked_until = sqlx::query_scalar::<_, Option<DateTime<Utc>>>("SELECT locked_until FROM login_throttle WHERE subject = ?")
+        .bind(subject)
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+
+    Ok(locked_until.filter(|until| *until > Utc::now()))
+}
+
+/// Counts a failed login for the subject and locks it when it crosses the
+/// threshold. Returns the new lock expiry when this failure caused a lockout.
+pub async fn record_failure(pool: &SqlitePool, subject: &str, threshold: i64) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let now = Utc::now();
+
+    sqlx::query("DELETE FROM login_throttle WHERE subject = ? AND last_failure_at < ? AND (locked_until IS NULL OR locked_until < ?)")
+        .bind(subject)
+        .bind(now - Duration::hours(FAILURE_WINDOW_HOURS))
+        .bind(now)
+        .execute(pool)
+        .await?;
+
+    let failures = sqlx::query_scalar::<_, i64>(
+        r#"
+        INSERT INTO login_throttle (subject, failures, last_failure_at) VALUES (?, 1, ?)
+        ON CONFLICT(subject) DO UPDATE SET failures = failures + 1, last_failure_at = excluded.last_failure_at
+        RETURNING failures
+        "#
+    )
+    .bind(subject)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+
+    if failures < threshold || failures % threshold != 0 {
+        return Ok(None);
+    }
+
+    let lockouts = (failures / threshold - 1).min(10) as u32;
+    let minutes = (BASE_LOCKOUT_MINUTES * 2i64.pow(lockouts)).min(MAX_LOCKOUT_MINUTES);
+    let until = now + Duration::minutes(minutes);
+    sqlx::query("UPDATE login_throttle SET locked_until = ? WHERE subject = ?")
+        .bind(until)
+        .bind(subject)
+        .execute(pool)
+        .await?;
+
+    Ok(Some(until))
+}
+
+/// Records a failed login against both the account and the client IP, logging
+/// any lockout it triggers.
+pub async fn register_failed_login(pool: &SqlitePool, account: &str, ip: &str) -> Result<(), sqlx::Error> {
+    if let Some(until) = record_failure(pool, account, ACCOUNT_FAILURE_THRESHOLD).await? {
+        eprintln!("🔒 Login lockout: {} locked until {} (last attempt from {})", account, until, ip);
+    }
+    if let Some(until) = record_failure(pool, ip, IP_FAILURE_THRESHOLD).await? {
+        eprintln!("🔒 Login lockout: {} blocked until {} (last attempt on {})", ip, until, account);
+    }
+    Ok(())
+}
+
+pub async fn reset(pool: &SqlitePool, subject: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM login_throttle WHERE subject = ?")
+        .bind(subject)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[get("/login-lockouts")]
+pub async fn get_login_lockouts(pool: web::Data<SqlitePool>) -> impl Responder {
+    match sqlx::query_as::<_, LoginThrottle>(
+        "SELECT subject, failures, last_failure_at, locked_until FROM login_throttle WHERE locked_until IS NOT NULL ORDER BY last_failure_at DESC"
+    )
+    .fetch_all(&**pool)
+    .await
+    {
+        Ok(throttles) => {
+            let now = Utc::now();
+            let active: Vec<LoginThrottle> = throttles
+                .into_iter()
+                .filter(|t| t.locked_until.is_some_and(|until| until > now))
+                .collect();
+            HttpResponse::Ok().json(active)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/{customer_id}/unlock")]
+pub async fn unlock_customer(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let customer_id = path.into_inner();
+
+    let email = match sqlx::query_scalar::<_, String>("SELECT email FROM customers WHERE customer_id = ?")
+        .bind(&customer_id)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(email)) => email,
+        Ok(None) => return HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    match reset(&pool, &account_subject(&email)).await {
+        Ok(()) => {
+            println!("🔓 {} unlocked login for customer {} ({})", principal.customer_id, customer_id, email);
+            HttpResponse::Ok().body("Customer login unlocked")
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
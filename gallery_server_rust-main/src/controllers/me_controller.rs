@@ -0,0 +1,48 @@
+use actix_web::{delete, get, web, HttpResponse, Responder};
+use sqlx::SqlitePool;
+use crate::auth::principal::Principal;
+use crate::controllers::{order_controller, privacy_controller};
+use crate::models::customer::Customer;
+
+#[get("")]
+pub async fn get_me(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    match sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE customer_id = ?")
+        .bind(&principal.customer_id)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(customer)) => HttpResponse::Ok().json(customer),
+        Ok(None) => HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/orders")]
+pub async fn get_my_orders(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    match order_controller::fetch_detailed_orders(&pool, Some(&principal.customer_id), None).await {
+        Ok(orders) => HttpResponse::Ok().json(orders),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/orders/{id_order}")]
+pub async fn get_my_order(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_order = path.into_inner();
+    match order_controller::fetch_detailed_orders(&pool, Some(&principal.customer_id), Some(&id_order)).await {
+        Ok(mut orders) => match orders.pop() {
+            Some(order) => HttpResponse::Ok().json(order),
+            None => HttpResponse::NotFound().body("Order not found"),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/export")]
+pub async fn export_my_data(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    privacy_controller::export_response(&pool, &principal.customer_id).await
+}
+
+#[delete("")]
+pub async fn erase_me(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    privacy_controller::erasure_response(&pool, &principal.customer_id).await
+}
@@ -3,4 +3,24 @@ pub mod customer_controller;
 pub mod artist_controller;
 pub mod init_db;
 pub mod artwork_controller;
-pub mod order_controller;
\ No newline at end of file
+pub mod order_controller;
+pub mod session_controller;
+pub mod account_token_controller;
+pub mod password_reset_controller;
+pub mod email_verification_controller;
+pub mod login_throttle_controller;
+pub mod api_key_controller;
+pub mod me_controller;
+pub mod privacy_controller;
+pub mod artist_portal_controller;
+pub mod checkout_controller;
+pub mod cart_controller;
+pub mod payment_controller;
+pub mod return_controller;
+pub mod discount_controller;
+pub mod tax_controller;
+pub mod shipping_controller;
+pub mod invoice_controller;
+pub mod idempotency_controller;
+pub mod auction_controller;
+pub mod offer_controller;
\ No newline at end of file
@@ -0,0 +1,458 @@
+use actix_web::{get, post, rt, web, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::controllers::{artwork_controller, customer_controller};
+use crate::controllers::artwork_controller::Transition;
+use crate::models::artwork::ArtworkStatus;
+use crate::models::money::Money;
+use crate::models::offer::{CounterOffer, NewOffer, Offer, OfferListQuery, OfferResponse, OfferStatus};
+
+/// How long the other side has to answer an offer or a counter-offer.
+pub const OFFER_RESPONSE_HOURS: i64 = 72;
+/// How long an accepted offer holds the piece for the customer to check out.
+pub const OFFER_CHECKOUT_HOURS: i64 = 48;
+const SWEEP_INTERVAL_SECONDS: u64 = 60;
+
+pub async fn init_offers_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating offers table if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS offers (
+            id_offer TEXT PRIMARY KEY NOT NULL,
+            id_artwork TEXT NOT NULL,
+            customer_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            amount_minor INTEGER NOT NULL,
+            counter_minor INTEGER,
+            agreed_minor INTEGER,
+            currency TEXT NOT NULL,
+            message TEXT,
+            note TEXT,
+            expires_at TEXT NOT NULL,
+            checkout_until TEXT,
+            id_order TEXT REFERENCES ORDERS(id_order) ON DELETE SET NULL,
+            created_at TEXT NOT NULL,
+            decided_by TEXT,
+            decided_at TEXT,
+            FOREIGN KEY (id_artwork) REFERENCES ARTWORKS(id_artwork) ON DELETE CASCADE,
+            FOREIGN KEY (customer_id) REFERENCES customers(customer_id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    // A customer negotiates one offer per piece at a time.
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS offers_open_artwork ON offers (id_artwork, customer_id) WHERE status IN ('pending', 'countered')",
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ offers table ready");
+
+    Ok(())
+}
+
+async fn fetch_offer(conn: &mut SqliteConnection, id_offer: &str) -> Result<Option<Offer>, sqlx::Error> {
+    sqlx::query_as::<_, Offer>("SELECT * FROM offers WHERE id_offer = ?")
+        .bind(id_offer)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// The customer's accepted offer on a piece while its checkout window is open,
+/// as the offer id and the agreed price.
+pub async fn accepted_offer(conn: &mut SqliteConnection, id_artwork: &str, customer_id: &str) -> Result<Option<(String, Money)>, sqlx::Error> {
+    let offer = sqlx::query_as::<_, Offer>("SELECT * FROM offers WHERE id_artwork = ? AND customer_id = ? AND status = ? AND checkout_until > ?")
+        .bind(id_artwork)
+        .bind(customer_id)
+        .bind(OfferStatus::Accepted)
+        .bind(Utc::now())
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(offer.and_then(|offer| offer.agreed_price.map(|agreed| (offer.id_offer, agreed))))
+}
+
+/// Marks an accepted offer as checked out in `id_order`.
+pub async fn complete_offer(conn: &mut SqliteConnection, id_offer: &str, id_order: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE offers SET status = ?, id_order = ? WHERE id_offer = ? AND status = ?")
+        .bind(OfferStatus::Completed)
+        .bind(id_order)
+        .bind(id_offer)
+        .bind(OfferStatus::Accepted)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Puts a piece held for an accepted offer back on sale. Pieces staff have
+/// since sold, archived or let someone else put in their cart are left alone.
+async fn release_offer_hold(conn: &mut SqliteConnection, id_artwork: &str) -> Result<(), sqlx::Error> {
+    let held = sqlx::query_scalar::<_, bool>(
+        "SELECT status = ? AND NOT EXISTS (SELECT 1 FROM cart_items WHERE id_artwork = ?) FROM ARTWORKS WHERE id_artwork = ?",
+    )
+    .bind(ArtworkStatus::Reserved)
+    .bind(id_artwork)
+    .bind(id_artwork)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if held == Some(true) {
+        if let Transition::Refused(current) = artwork_controller::transition_artwork(conn, id_artwork, ArtworkStatus::Available).await? {
+            println!("⚠️ Artwork {} stays {} after its offer hold was released", id_artwork, current);
+        }
+    }
+    Ok(())
+}
+
+/// Expires offers nobody answered in time and accepted offers that were not
+/// checked out in time, releasing the pieces those held. Returns how many expired.
+pub async fn expire_offers(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+    let unanswered = sqlx::query("UPDATE offers SET status = ? WHERE status IN (?, ?) AND expires_at <= ?")
+        .bind(OfferStatus::Expired)
+        .bind(OfferStatus::Pending)
+        .bind(OfferStatus::Countered)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    let lapsed = sqlx::query_as::<_, (String, String)>("SELECT id_offer, id_artwork FROM offers WHERE status = ? AND checkout_until <= ?")
+        .bind(OfferStatus::Accepted)
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await?;
+    for (id_offer, id_artwork) in &lapsed {
+        sqlx::query("UPDATE offers SET status = ? WHERE id_offer = ?")
+            .bind(OfferStatus::Expired)
+            .bind(id_offer)
+            .execute(&mut *tx)
+            .await?;
+        release_offer_hold(&mut tx, id_artwork).await?;
+    }
+    tx.commit().await?;
+    Ok(unanswered.rows_affected() + lapsed.len() as u64)
+}
+
+/// Runs `expire_offers` every minute for as long as the server is up.
+pub fn spawn_offer_sweeper(pool: SqlitePool) {
+    rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            match expire_offers(&pool).await {
+                Ok(0) => {}
+                Ok(expired) => println!("🤝 Expired {} offers", expired),
+                Err(e) => eprintln!("Failed to expire offers: {}", e),
+            }
+        }
+    });
+}
+
+/// Loads an offer for someone allowed to see it; other customers' offers look missing.
+async fn offer_for(pool: &SqlitePool, principal: &Principal, id_offer: &str) -> Result<Option<Offer>, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    Ok(fetch_offer(&mut conn, id_offer).await?.filter(|offer| principal.can_access_customer(&offer.customer_id)))
+}
+
+fn refuse(offer: &Offer, next: OfferStatus) -> HttpResponse {
+    HttpResponse::Conflict().body(format!("Offer with id {} is {} and cannot be {}", offer.id_offer, offer.status, next))
+}
+
+#[post("/")]
+pub async fn create_offer(pool: web::Data<SqlitePool>, principal: Principal, new_offer: web::Json<NewOffer>) -> impl Responder {
+    match customer_controller::is_email_verified(&pool, &principal.customer_id).await {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => return HttpResponse::Forbidden().body("Forbidden: the customer's email address must be verified before making offers"),
+        Ok(None) => return HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    let id_artwork = &new_offer.id_artwork;
+    let listed = sqlx::query_as::<_, (ArtworkStatus, i64, String)>("SELECT status, price_minor, currency FROM ARTWORKS WHERE id_artwork = ? AND is_draft = 0")
+        .bind(id_artwork)
+        .fetch_optional(&**pool)
+        .await;
+    let list_price = match listed {
+        Ok(Some((ArtworkStatus::Available, price_minor, currency))) => match Money::new(price_minor, &currency) {
+            Ok(list_price) => list_price,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+        },
+        Ok(Some((status, _, _))) => return HttpResponse::Conflict().body(format!("Artwork with id {} is {} and not open to offers", id_artwork, status)),
+        Ok(None) => return HttpResponse::NotFound().body(format!("Artwork with id {} not found", id_artwork)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    if new_offer.amount.currency != list_price.currency {
+        return HttpResponse::BadRequest().body(format!("The artwork is priced in {}; make the offer in the same currency", list_price.currency));
+    }
+    if new_offer.amount.minor <= 0 || new_offer.amount.minor >= list_price.minor {
+        return HttpResponse::BadRequest().body(format!("An offer must be more than zero and below the list price of {}", list_price));
+    }
+
+    let now = Utc::now();
+    let id_offer = Uuid::new_v4().to_string();
+    match sqlx::query_as::<_, Offer>(
+        r#"
+        INSERT INTO offers (id_offer, id_artwork, customer_id, status, amount_minor, currency, message, expires_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#
+    )
+    .bind(&id_offer)
+    .bind(id_artwork)
+    .bind(&principal.customer_id)
+    .bind(OfferStatus::Pending)
+    .bind(new_offer.amount.minor)
+    .bind(&list_price.currency)
+    .bind(&new_offer.message)
+    .bind(now + Duration::hours(OFFER_RESPONSE_HOURS))
+    .bind(now)
+    .fetch_one(&**pool)
+    .await
+    {
+        Ok(offer) => {
+            println!("🤝 Customer {} offered {} for artwork {}", principal.customer_id, new_offer.amount, id_artwork);
+            HttpResponse::Created().json(offer)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().body(format!("You already have an open offer on artwork {}", id_artwork))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Staff see every offer; customers see their own.
+#[get("/")]
+pub async fn get_offers(pool: web::Data<SqlitePool>, principal: Principal, query: web::Query<OfferListQuery>) -> impl Responder {
+    let customer_filter = if principal.is_staff() { None } else { Some(principal.customer_id.as_str()) };
+    match sqlx::query_as::<_, Offer>(
+        r#"
+        SELECT * FROM offers
+        WHERE (? IS NULL OR customer_id = ?) AND (? IS NULL OR status = ?) AND (? IS NULL OR id_artwork = ?)
+        ORDER BY created_at DESC
+        "#
+    )
+    .bind(customer_filter)
+    .bind(customer_filter)
+    .bind(query.status)
+    .bind(query.status)
+    .bind(&query.id_artwork)
+    .bind(&query.id_artwork)
+    .fetch_all(&**pool)
+    .await
+    {
+        Ok(offers) => HttpResponse::Ok().json(offers),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/{id_offer}")]
+pub async fn get_offer(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_offer = path.into_inner();
+    match offer_for(&pool, &principal, &id_offer).await {
+        Ok(Some(offer)) => HttpResponse::Ok().json(offer),
+        Ok(None) => HttpResponse::NotFound().body(format!("Offer with id {} not found", id_offer)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Staff answer a pending offer with a price of their own, which the customer
+/// then has `OFFER_RESPONSE_HOURS` to accept or reject.
+#[post("/{id_offer}/counter")]
+pub async fn counter_offer(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>, counter: web::Json<CounterOffer>) -> impl Responder {
+    let id_offer = path.into_inner();
+    let offer = match offer_for(&pool, &principal, &id_offer).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Offer with id {} not found", id_offer)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    if offer.status != OfferStatus::Pending {
+        return refuse(&offer, OfferStatus::Countered);
+    }
+    if counter.amount.currency != offer.amount.currency {
+        return HttpResponse::BadRequest().body(format!("The counter-offer must be in {}", offer.amount.currency));
+    }
+    if counter.amount.minor <= offer.amount.minor {
+        return HttpResponse::BadRequest().body(format!("A counter-offer must be above the customer's offer of {}; accept it instead", offer.amount));
+    }
+
+    let now = Utc::now();
+    let result = async {
+        let mut conn = pool.acquire().await?;
+        let countered = sqlx::query(
+            r#"
+            UPDATE offers SET status = ?, counter_minor = ?, note = ?, expires_at = ?, decided_by = ?, decided_at = ?
+            WHERE id_offer = ? AND status = ? AND expires_at > ?
+            "#
+        )
+        .bind(OfferStatus::Countered)
+        .bind(counter.amount.minor)
+        .bind(&counter.note)
+        .bind(now + Duration::hours(OFFER_RESPONSE_HOURS))
+        .bind(&principal.customer_id)
+        .bind(now)
+        .bind(&id_offer)
+        .bind(OfferStatus::Pending)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+        let offer = fetch_offer(&mut conn, &id_offer).await?.ok_or(sqlx::Error::RowNotFound)?;
+        Ok::<_, sqlx::Error>((countered.rows_affected() > 0, offer))
+    }
+    .await;
+
+    match result {
+        Ok((true, offer)) => {
+            println!("🤝 Offer {} countered at {}", id_offer, counter.amount);
+            HttpResponse::Ok().json(offer)
+        }
+        Ok((false, _)) => HttpResponse::Conflict().body(format!("Offer with id {} has expired or changed in the meantime", id_offer)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Staff accept a pending offer; the customer accepts a counter-offer. Either
+/// way the piece is reserved for the customer for `OFFER_CHECKOUT_HOURS`, and
+/// checking it out in that time bills the agreed price instead of the list price.
+#[post("/{id_offer}/accept")]
+pub async fn accept_offer(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_offer = path.into_inner();
+    let offer = match offer_for(&pool, &principal, &id_offer).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Offer with id {} not found", id_offer)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    // Whoever the offer is waiting on answers it.
+    let agreed = match (offer.status, &offer.counter_amount) {
+        (OfferStatus::Pending, _) if principal.is_staff() => offer.amount.clone(),
+        (OfferStatus::Pending, _) => return HttpResponse::Conflict().body("The offer is waiting for the gallery's answer"),
+        (OfferStatus::Countered, Some(counter)) if principal.customer_id == offer.customer_id => counter.clone(),
+        (OfferStatus::Countered, _) => return HttpResponse::Conflict().body("The counter-offer is waiting for the customer's answer"),
+        _ => return refuse(&offer, OfferStatus::Accepted),
+    };
+
+    let now = Utc::now();
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let accepted = sqlx::query(
+            r#"
+            UPDATE offers SET status = ?, agreed_minor = ?, checkout_until = ?, decided_by = ?, decided_at = ?
+            WHERE id_offer = ? AND status = ? AND expires_at > ?
+            "#
+        )
+        .bind(OfferStatus::Accepted)
+        .bind(agreed.minor)
+        .bind(now + Duration::hours(OFFER_CHECKOUT_HOURS))
+        .bind(&principal.customer_id)
+        .bind(now)
+        .bind(&id_offer)
+        .bind(offer.status)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        if accepted.rows_affected() == 0 {
+            return Ok(Err(HttpResponse::Conflict().body(format!("Offer with id {} has expired or changed in the meantime", id_offer))));
+        }
+        match artwork_controller::transition_artwork(&mut tx, &offer.id_artwork, ArtworkStatus::Reserved).await? {
+            Transition::Moved(_) => {}
+            Transition::Refused(current) => {
+                return Ok(Err(HttpResponse::Conflict().body(format!("Artwork with id {} is {} and can no longer be sold on this offer", offer.id_artwork, current))))
+            }
+            Transition::NotFound => return Ok(Err(HttpResponse::NotFound().body(format!("Artwork with id {} not found", offer.id_artwork)))),
+        }
+        let offer = fetch_offer(&mut tx, &id_offer).await?.ok_or(sqlx::Error::RowNotFound)?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Ok(offer))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(offer)) => {
+            println!("🤝 Offer {} accepted at {}; artwork {} reserved for checkout", id_offer, agreed, offer.id_artwork);
+            HttpResponse::Ok().json(offer)
+        }
+        Ok(Err(refused)) => refused,
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Staff turn down a pending offer or take back their counter-offer; the
+/// customer turns down a counter-offer.
+#[post("/{id_offer}/reject")]
+pub async fn reject_offer(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>, response: Option<web::Json<OfferResponse>>) -> impl Responder {
+    let id_offer = path.into_inner();
+    let offer = match offer_for(&pool, &principal, &id_offer).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Offer with id {} not found", id_offer)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    match offer.status {
+        OfferStatus::Pending if !principal.is_staff() => return HttpResponse::Conflict().body("The offer is waiting for the gallery's answer; withdraw it instead"),
+        OfferStatus::Pending | OfferStatus::Countered => {}
+        _ => return refuse(&offer, OfferStatus::Rejected),
+    }
+    let note = response.and_then(|response| response.into_inner().note);
+
+    let now = Utc::now();
+    match sqlx::query("UPDATE offers SET status = ?, note = COALESCE(?, note), decided_by = ?, decided_at = ? WHERE id_offer = ? AND status = ?")
+        .bind(OfferStatus::Rejected)
+        .bind(&note)
+        .bind(&principal.customer_id)
+        .bind(now)
+        .bind(&id_offer)
+        .bind(offer.status)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            println!("🤝 Offer {} rejected", id_offer);
+            HttpResponse::Ok().body(format!("Offer with id {} is now {}", id_offer, OfferStatus::Rejected))
+        }
+        Ok(_) => HttpResponse::Conflict().body(format!("Offer with id {} changed in the meantime", id_offer)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// The customer backs out of an open or accepted offer. Backing out of an
+/// accepted one puts the piece back on sale.
+#[post("/{id_offer}/withdraw")]
+pub async fn withdraw_offer(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_offer = path.into_inner();
+    let offer = match offer_for(&pool, &principal, &id_offer).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Offer with id {} not found", id_offer)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    if !offer.status.can_transition_to(OfferStatus::Withdrawn) {
+        return refuse(&offer, OfferStatus::Withdrawn);
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let withdrawn = sqlx::query("UPDATE offers SET status = ?, decided_by = ?, decided_at = ? WHERE id_offer = ? AND status = ?")
+            .bind(OfferStatus::Withdrawn)
+            .bind(&principal.customer_id)
+            .bind(Utc::now())
+            .bind(&id_offer)
+            .bind(offer.status)
+            .execute(&mut *tx)
+            .await?;
+        if withdrawn.rows_affected() == 0 {
+            return Ok(false);
+        }
+        if offer.status == OfferStatus::Accepted {
+            release_offer_hold(&mut tx, &offer.id_artwork).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {
+            println!("🤝 Offer {} withdrawn", id_offer);
+            HttpResponse::Ok().body(format!("Offer with id {} is now {}", id_offer, OfferStatus::Withdrawn))
+        }
+        Ok(false) => HttpResponse::Conflict().body(format!("Offer with id {} changed in the meantime", id_offer)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
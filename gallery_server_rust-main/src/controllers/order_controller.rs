@@ -1,8 +1,18 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sqlx::{SqlitePool, Row};
-use crate::models::order::{Order};
+use actix_web::{get, post, put, delete, rt, web, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::{sqlite::SqliteConnection, SqlitePool, Row};
+use crate::auth::principal::Principal;
+use crate::controllers::{artwork_controller, customer_controller, init_db, payment_controller};
+use crate::models::order::{Order, OrderStatus, OrderStatusChange, OrderStatusUpdate};
+use crate::models::order_return::ReturnStatus;
+use crate::models::payment::PaymentStatus;
+use crate::models::shipping::ShippingKind;
 use uuid::Uuid;
 use serde_json::json;
+use crate::models::money::{Money, MoneyError};
+
+pub const DEFAULT_PAYMENT_HOURS: i64 = 48;
+const SWEEP_INTERVAL_SECONDS: u64 = 300;
 
 pub async fn init_orders_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     sqlx::query(
@@ -11,18 +21,235 @@ pub async fn init_orders_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             id_order TEXT PRIMARY KEY NOT NULL,
             id_customer TEXT NOT NULL,
             order_date DATE NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            id_discount TEXT,
+            discount_code TEXT,
+            tax_exempt BOOLEAN NOT NULL DEFAULT 0,
             FOREIGN KEY (id_customer) REFERENCES customers(customer_id)
         )
         "#,
     )
     .execute(pool)
     .await?;
+    init_db::add_column_if_missing(pool, "ORDERS", "status", "TEXT NOT NULL DEFAULT 'pending'").await?;
+    init_db::add_column_if_missing(pool, "ORDERS", "id_discount", "TEXT").await?;
+    init_db::add_column_if_missing(pool, "ORDERS", "discount_code", "TEXT").await?;
+    init_db::add_column_if_missing(pool, "ORDERS", "tax_exempt", "BOOLEAN NOT NULL DEFAULT 0").await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_status_history (
+            id_status_change TEXT PRIMARY KEY NOT NULL,
+            id_order TEXT NOT NULL,
+            from_status TEXT,
+            to_status TEXT NOT NULL,
+            changed_at TEXT NOT NULL,
+            changed_by TEXT,
+            note TEXT,
+            FOREIGN KEY (id_order) REFERENCES ORDERS(id_order) ON DELETE CASCADE
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Outcome of asking an order to move to another status.
+pub enum OrderTransition {
+    Moved(OrderStatus),
+    Refused(OrderStatus),
+    NotFound,
+}
+
+/// Appends an entry to the order's status history.
+pub async fn record_status_change(
+    conn: &mut SqliteConnection,
+    id_order: &str,
+    from: Option<OrderStatus>,
+    to: OrderStatus,
+    changed_by: Option<&str>,
+    note: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO order_status_history (id_status_change, id_order, from_status, to_status, changed_at, changed_by, note) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(id_order)
+    .bind(from)
+    .bind(to)
+    .bind(Utc::now())
+    .bind(changed_by)
+    .bind(note)
+    .execute(&mut *conn)
+    .await?;
     Ok(())
 }
 
+/// Moves an order to `next` when its lifecycle allows it and records the move.
+/// Cancelling or refunding puts the order's pieces back on sale. Run it inside
+/// the caller's transaction so the status, history and artworks change together.
+pub async fn transition_order(
+    conn: &mut SqliteConnection,
+    id_order: &str,
+    next: OrderStatus,
+    changed_by: Option<&str>,
+    note: Option<&str>,
+) -> Result<OrderTransition, sqlx::Error> {
+    let Some(current) = sqlx::query_scalar::<_, OrderStatus>("SELECT status FROM ORDERS WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(OrderTransition::NotFound);
+    };
+    if !current.can_transition_to(next) {
+        return Ok(OrderTransition::Refused(current));
+    }
+
+    // Guarded by the status we read, so a concurrent change cannot be overwritten.
+    let result = sqlx::query("UPDATE ORDERS SET status = ? WHERE id_order = ? AND status = ?")
+        .bind(next)
+        .bind(id_order)
+        .bind(current)
+        .execute(&mut *conn)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(OrderTransition::Refused(current));
+    }
+    record_status_change(conn, id_order, Some(current), next, changed_by, note).await?;
+
+    if matches!(next, OrderStatus::Cancelled | OrderStatus::Refunded) {
+        // Lines refunded through a return had their piece released then.
+        let artwork_ids = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT aio.id_artwork FROM artworks_in_order aio
+            WHERE aio.id_order = ?
+              AND NOT EXISTS (SELECT 1 FROM order_returns r WHERE r.id_artwork_in_order = aio.id_artwork_in_order AND r.status = ?)
+            "#
+        )
+        .bind(id_order)
+        .bind(ReturnStatus::Refunded)
+        .fetch_all(&mut *conn)
+        .await?;
+        for id_artwork in &artwork_ids {
+            artwork_controller::release_artwork(conn, id_artwork).await?;
+        }
+    }
+    Ok(OrderTransition::Moved(current))
+}
+
+/// How long a pending order waits for payment before it is cancelled (`ORDER_PAYMENT_HOURS`).
+pub fn payment_deadline() -> Duration {
+    let hours = std::env::var("ORDER_PAYMENT_HOURS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|hours| *hours > 0)
+        .unwrap_or(DEFAULT_PAYMENT_HOURS);
+    Duration::hours(hours)
+}
+
+/// Cancels pending orders placed longer than `payment_deadline()` ago, which
+/// puts their pieces back on sale. Orders with a payment the provider is still
+/// settling are left for it to finish. Returns how many were cancelled.
+pub async fn cancel_unpaid_orders(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let deadline = payment_deadline();
+    // Orders from before the status history count from their order date.
+    let stale = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT o.id_order FROM ORDERS o
+        WHERE o.status = ?
+          AND COALESCE((SELECT MIN(h.changed_at) FROM order_status_history h WHERE h.id_order = o.id_order), o.order_date) <= ?
+          AND NOT EXISTS (SELECT 1 FROM payments p WHERE p.id_order = o.id_order AND p.status IN (?, ?))
+        "#
+    )
+    .bind(OrderStatus::Pending)
+    .bind(Utc::now() - deadline)
+    .bind(PaymentStatus::Pending)
+    .bind(PaymentStatus::Authorized)
+    .fetch_all(pool)
+    .await?;
+
+    let note = format!("Not paid within {} hours", deadline.num_hours());
+    let mut cancelled = 0;
+    for id_order in &stale {
+        let mut tx = pool.begin().await?;
+        if !matches!(transition_order(&mut tx, id_order, OrderStatus::Cancelled, None, Some(&note)).await?, OrderTransition::Moved(_)) {
+            continue;
+        }
+        // A payment started since the orders were listed; leave the order to it.
+        if payment_controller::payment_in_progress(&mut tx, id_order).await? {
+            continue;
+        }
+        tx.commit().await?;
+        cancelled += 1;
+    }
+    Ok(cancelled)
+}
+
+/// Runs `cancel_unpaid_orders` every few minutes for as long as the server is up.
+pub fn spawn_unpaid_order_sweeper(pool: SqlitePool) {
+    rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            match cancel_unpaid_orders(&pool).await {
+                Ok(0) => {}
+                Ok(cancelled) => println!("📦 Cancelled {} unpaid orders", cancelled),
+                Err(e) => eprintln!("Failed to cancel unpaid orders: {}", e),
+            }
+        }
+    });
+}
+
+/// Staff see every customer's orders; everyone else only their own.
+fn customer_filter(principal: &Principal) -> Option<&str> {
+    if principal.is_staff() {
+        None
+    } else {
+        Some(principal.customer_id.as_str())
+    }
+}
+
+/// Inserts an order; binds are id, customer, date, status and the customer again.
+/// Whether the customer is tax exempt is copied onto the order as it is placed.
+pub const INSERT_ORDER: &str = r#"
+    INSERT INTO ORDERS (id_order, id_customer, order_date, status, tax_exempt)
+    VALUES (?, ?, ?, ?, COALESCE((SELECT tax_exempt FROM customers WHERE customer_id = ?), 0))
+"#;
+
+/// What the customer owes for an order: the line totals, which already take
+/// discounts and tax into account, plus shipping. `None` when the order has no lines yet.
+pub async fn order_total(conn: &mut SqliteConnection, id_order: &str) -> Result<Option<Money>, sqlx::Error> {
+    let lines = sqlx::query_as::<_, (i64, String)>("SELECT total_minor, currency FROM artworks_in_order WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_all(&mut *conn)
+        .await?;
+    let money_error = |e: MoneyError| sqlx::Error::Decode(Box::new(e));
+    let mut total: Option<Money> = None;
+    for (total_minor, currency) in lines {
+        let line_total = Money::new(total_minor, &currency).map_err(money_error)?;
+        total = Some(match total {
+            Some(total) => total.checked_add(&line_total).map_err(money_error)?,
+            None => line_total,
+        });
+    }
+    if let Some(total) = total.as_mut() {
+        let shipping = sqlx::query_as::<_, (i64, String)>("SELECT shipping_minor, currency FROM order_shipments WHERE id_order = ?")
+            .bind(id_order)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if let Some((shipping_minor, currency)) = shipping {
+            *total = total.checked_add(&Money::new(shipping_minor, &currency).map_err(money_error)?).map_err(money_error)?;
+        }
+    }
+    Ok(total)
+}
+
 #[get("/")]
-pub async fn get_orders(pool: web::Data<SqlitePool>) -> impl Responder {
-    match sqlx::query_as::<_, Order>("SELECT id_order, id_customer, order_date FROM ORDERS")
+pub async fn get_orders(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    match sqlx::query_as::<_, Order>("SELECT id_order, id_customer, order_date, status, discount_code FROM ORDERS WHERE (? IS NULL OR id_customer = ?)")
+        .bind(customer_filter(&principal))
+        .bind(customer_filter(&principal))
         .fetch_all(&**pool)
         .await
     {
@@ -32,20 +259,39 @@ pub async fn get_orders(pool: web::Data<SqlitePool>) -> impl Responder {
 }
 
 #[post("/")]
-pub async fn create_order(pool: web::Data<SqlitePool>, order: web::Json<Order>) -> impl Responder {
+pub async fn create_order(pool: web::Data<SqlitePool>, principal: Principal, order: web::Json<Order>) -> impl Responder {
+    if !principal.can_access_customer(&order.id_customer) {
+        return HttpResponse::Forbidden().body("Forbidden: customers may only place orders for themselves");
+    }
+    match customer_controller::is_email_verified(&pool, &order.id_customer).await {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => return HttpResponse::Forbidden().body("Forbidden: the customer's email address must be verified before placing orders"),
+        Ok(None) => return HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
     let id =Uuid::new_v4().to_string();
-    match sqlx::query("INSERT INTO ORDERS (id_order, id_customer, order_date) VALUES (?, ?, ?) RETURNING id_order, id_customer, order_date")
-        .bind(&id)
-        .bind(&order.id_customer)
-        .bind(&order.order_date)
-        .execute(&**pool)
-        .await
-    {
-        Ok(_result) => {
+    let result = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query(INSERT_ORDER)
+            .bind(&id)
+            .bind(&order.id_customer)
+            .bind(order.order_date)
+            .bind(OrderStatus::Pending)
+            .bind(&order.id_customer)
+            .execute(&mut *tx)
+            .await?;
+        record_status_change(&mut tx, &id, None, OrderStatus::Pending, Some(&principal.customer_id), None).await?;
+        tx.commit().await
+    }
+    .await;
+    match result {
+        Ok(()) => {
             let new_order = Order {
                 id_order: id,
                 id_customer: order.id_customer.clone(),
-                order_date: order.order_date.clone(),
+                order_date: order.order_date,
+                status: OrderStatus::Pending,
+                discount_code: None,
             };
             HttpResponse::Created().json(new_order)         
         }
@@ -53,13 +299,15 @@ pub async fn create_order(pool: web::Data<SqlitePool>, order: web::Json<Order>)
     }
 }
 #[get("/{id_order}")]
-pub async fn get_order_by_id(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+pub async fn get_order_by_id(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
     let id_order = path.into_inner();
-    match sqlx::query_as::<_, Order>("SELECT id_order, id_customer, order_date FROM ORDERS WHERE id_order = ?")
+    match sqlx::query_as::<_, Order>("SELECT id_order, id_customer, order_date, status, discount_code FROM ORDERS WHERE id_order = ?")
         .bind(&id_order)
         .fetch_one(pool.get_ref())
         .await
     {
+        // Someone else's order looks exactly like a missing one.
+        Ok(order) if !principal.can_access_customer(&order.id_customer) => HttpResponse::NotFound().into(),
         Ok(order) => HttpResponse::Ok().json(order),
         Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().into(),
         Err(_) => HttpResponse::InternalServerError().into(),
@@ -69,27 +317,17 @@ pub async fn get_order_by_id(pool: web::Data<SqlitePool>, path: web::Path<String
 #[put("/{id_order}")]
 pub async fn update_order(pool: web::Data<SqlitePool>, path: web::Path<String>, updated_order: web::Json<Order>) -> impl Responder {
     let id_order = path.into_inner();
-    match sqlx::query(
-        "UPDATE ORDERS SET id_customer = ?, order_date = ? WHERE id_order = ?"
+    match sqlx::query_as::<_, Order>(
+        "UPDATE ORDERS SET id_customer = ?, order_date = ? WHERE id_order = ? RETURNING id_order, id_customer, order_date, status, discount_code"
     )
         .bind(&updated_order.id_customer)
-        .bind(&updated_order.order_date)
+        .bind(updated_order.order_date)
         .bind(&id_order)
-        .execute(pool.get_ref())
+        .fetch_optional(pool.get_ref())
         .await
     {
-        Ok(result) => {
-            if result.rows_affected() > 0 {
-                let updated_order_response = Order {
-                    id_order: id_order.clone(),
-                    id_customer: updated_order.id_customer.clone(),
-                    order_date: updated_order.order_date.clone(),
-                };
-                HttpResponse::Ok().json(updated_order_response)
-            } else {
-                HttpResponse::NotFound().body(format!("Order with id {} not found", id_order))
-            }
-        }
+        Ok(Some(updated_order_response)) => HttpResponse::Ok().json(updated_order_response),
+        Ok(None) => HttpResponse::NotFound().body(format!("Order with id {} not found", id_order)),
         Err(e) => {
             eprintln!("Failed to update order with id {}: {}", id_order, e);
             HttpResponse::InternalServerError().body(format!("Database error: {}", e))
@@ -97,42 +335,104 @@ pub async fn update_order(pool: web::Data<SqlitePool>, path: web::Path<String>,
     }
 }
 
+/// Orders are never erased: their lines, payments and status history are the
+/// sales record. DELETE no longer removes the order; it is kept for older
+/// clients as a shorthand for `PUT /orders/{id}/status` with `cancelled`, which
+/// new clients should use. Only pending orders can be cancelled this way, which
+/// puts their pieces back on sale; any other order answers 409, and paid ones
+/// are refunded through `POST /payments/{id}/refunds` instead.
 #[delete("/{id_order}")]
-pub async fn delete_order(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+pub async fn delete_order(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_order = path.into_inner();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        // Money may still arrive for a payment the provider has not settled.
+        if payment_controller::payment_in_progress(&mut tx, &id_order).await? {
+            return Ok(None);
+        }
+        let transition = transition_order(&mut tx, &id_order, OrderStatus::Cancelled, Some(&principal.customer_id), Some("Order deleted")).await?;
+        if let OrderTransition::Moved(_) = transition {
+            tx.commit().await?;
+        }
+        Ok::<_, sqlx::Error>(Some(transition))
+    }
+    .await;
+
+    match result {
+        Ok(Some(OrderTransition::Moved(_))) => {
+            println!("📦 Order {} cancelled on delete by {}", id_order, principal.customer_id);
+            HttpResponse::Ok().body(format!(
+                "Order with id {} was cancelled, not deleted; PUT /orders/{}/status is the way to cancel orders",
+                id_order, id_order
+            ))
+        }
+        Ok(Some(OrderTransition::Refused(current))) => HttpResponse::Conflict().body(format!(
+            "Order with id {} is {}; only pending orders can be cancelled, paid ones are refunded through POST /payments/{{id}}/refunds",
+            id_order, current
+        )),
+        Ok(Some(OrderTransition::NotFound)) => HttpResponse::NotFound().body(format!("Order with id {} not found", id_order)),
+        Ok(None) => HttpResponse::Conflict().body(format!("Order {} has a payment in progress; wait for it to settle", id_order)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[put("/{id_order}/status")]
+pub async fn update_order_status(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>, update: web::Json<OrderStatusUpdate>) -> impl Responder {
     let id_order = path.into_inner();
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let transition = transition_order(&mut tx, &id_order, update.status, Some(&principal.customer_id), update.note.as_deref()).await?;
+        if let OrderTransition::Moved(_) = transition {
+            tx.commit().await?;
+        }
+        Ok::<_, sqlx::Error>(transition)
+    }
+    .await;
+    match result {
+        Ok(OrderTransition::Moved(previous)) => {
+            println!("📦 Order {} moved from {} to {} by {}", id_order, previous, update.status, principal.customer_id);
+            HttpResponse::Ok().body(format!("Order with id {} is now {}", id_order, update.status))
+        }
+        Ok(OrderTransition::Refused(current)) => HttpResponse::Conflict().body(format!(
+            "Order with id {} cannot move from {} to {}",
+            id_order, current, update.status
+        )),
+        Ok(OrderTransition::NotFound) => HttpResponse::NotFound().body(format!("Order with id {} not found", id_order)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
 
-    // Delete related artworks in order first
-    match sqlx::query("DELETE FROM artworks_in_order WHERE id_order = ?")
+#[get("/{id_order}/history")]
+pub async fn get_order_history(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_order = path.into_inner();
+    // Someone else's order looks exactly like a missing one.
+    match sqlx::query_scalar::<_, String>("SELECT id_customer FROM ORDERS WHERE id_order = ?")
         .bind(&id_order)
-        .execute(pool.get_ref())
+        .fetch_optional(&**pool)
         .await
     {
-        Ok(_) => {
-            // Now delete the order
-            match sqlx::query("DELETE FROM ORDERS WHERE id_order = ?")
-                .bind(&id_order)
-                .execute(pool.get_ref())
-                .await
-            {
-                Ok(result) => {
-                    if result.rows_affected() > 0 {
-                        HttpResponse::Ok().body("order deleted successfully")
-                    } else {
-                        HttpResponse::NotFound().body("order not found")
-                    }
-                }
-                Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
-            }
-        }
-        Err(e) => HttpResponse::InternalServerError().body(format!("Database error while deleting artworks in order: {}", e)),
+        Ok(Some(id_customer)) if principal.can_access_customer(&id_customer) => {}
+        Ok(_) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    match sqlx::query_as::<_, OrderStatusChange>("SELECT * FROM order_status_history WHERE id_order = ? ORDER BY changed_at")
+        .bind(&id_order)
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(history) => HttpResponse::Ok().json(history),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
     }
 }
 
 #[get("/after/2025-01-01")]
-pub async fn get_orders_after_2025_01_01(pool: web::Data<SqlitePool>) -> impl Responder {
+pub async fn get_orders_after_2025_01_01(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
     let date_str = "2025-01-01".to_string();
-    match sqlx::query_as::<_, Order>("SELECT id_order, id_customer, order_date FROM ORDERS WHERE order_date > ?")
+    match sqlx::query_as::<_, Order>("SELECT id_order, id_customer, order_date, status, discount_code FROM ORDERS WHERE order_date > ? AND (? IS NULL OR id_customer = ?)")
         .bind(date_str)
+        .bind(customer_filter(&principal))
+        .bind(customer_filter(&principal))
         .fetch_all(&**pool)
         .await
     {
@@ -144,12 +444,28 @@ pub async fn get_orders_after_2025_01_01(pool: web::Data<SqlitePool>) -> impl Re
     }
 }
 
-#[get("/detailed")]
-pub async fn get_detailed_orders(pool: web::Data<SqlitePool>) -> impl Responder {
+/// Loads orders with their customer and artwork lines, optionally narrowed to
+/// one customer and/or one order. Orders come back in `id_order` order.
+pub async fn fetch_detailed_orders(pool: &SqlitePool, id_customer: Option<&str>, id_order: Option<&str>) -> Result<Vec<serde_json::Value>, sqlx::Error> {
     let query = r#"
         SELECT
             o.id_order,
             o.order_date,
+            o.status,
+            o.discount_code,
+            o.tax_exempt,
+            s.method_name AS shipping_method,
+            s.kind AS shipping_kind,
+            s.recipient AS shipping_recipient,
+            s.street AS shipping_street,
+            s.city AS shipping_city,
+            s.postal_code AS shipping_postal_code,
+            s.country AS shipping_country,
+            s.phone AS shipping_phone,
+            s.shipping_minor,
+            s.currency AS shipping_currency,
+            s.tracking_number,
+            s.carrier,
             c.customer_id,
             c.first_name,
             c.last_name,
@@ -159,73 +475,180 @@ pub async fn get_detailed_orders(pool: web::Data<SqlitePool>) -> impl Responder
             aio.id_artwork_in_order,
             aio.id_artwork,
             aio.amount,
-            a.title AS artwork_title,
+            aio.title AS artwork_title,
+            aio.price_minor,
+            aio.currency,
+            aio.id_artist AS artwork_id_artist,
+            aio.artist_name,
+            aio.discount_minor,
+            aio.tax_rate_basis_points,
+            aio.price_includes_tax,
+            aio.tax_minor,
+            aio.total_minor,
+            r.status AS return_status,
+            CASE WHEN r.status = 'refunded' THEN r.refund_minor ELSE 0 END AS refunded_minor,
             a.description AS artwork_description,
             a.year_created AS artwork_year_created,
-            a.price AS artwork_price,
-            a.id_artist AS artwork_id_artist,
             a.art_type AS artwork_art_type
         FROM ORDERS o
         JOIN CUSTOMERS c ON o.id_customer = c.customer_id
+        LEFT JOIN order_shipments s ON s.id_order = o.id_order
         LEFT JOIN artworks_in_order aio ON o.id_order = aio.id_order
         LEFT JOIN ARTWORKS a ON aio.id_artwork = a.id_artwork
+        LEFT JOIN order_returns r ON r.id_artwork_in_order = aio.id_artwork_in_order AND r.status <> 'rejected'
+        WHERE (? IS NULL OR o.id_customer = ?)
+          AND (? IS NULL OR o.id_order = ?)
         ORDER BY o.id_order, aio.id_artwork_in_order
     "#;
 
-    match sqlx::query(query)
-        .fetch_all(&**pool)
-        .await
-    {
-        Ok(rows) => {
-            let mut orders_map: std::collections::HashMap<String, serde_json::Value> = std::collections::HashMap::new();
-
-            for row in rows {
-                let order_id: String = row.get("id_order");
-                
-                let order_entry = orders_map.entry(order_id.clone()).or_insert_with(|| {
-                    json!({
-                        "id_order": order_id,
-                        "order_date": row.get::<String, _>("order_date"),
-                        "customer": {
-                            "customer_id": row.get::<String, _>("customer_id"),
-                            "first_name": row.get::<String, _>("first_name"),
-                            "last_name": row.get::<String, _>("last_name"),
-                            "email": row.get::<String, _>("email"),
-                            "phone": row.get::<String, _>("phone"),
-                            "address": row.get::<String, _>("address"),
-                        },
-                        "artworks": [],
-                        "total_amount": 0.0f64,
-                    })
-                });
-
-                if let Some(artwork_in_order_id) = row.try_get::<String, _>("id_artwork_in_order").ok() {
-                    let artwork_amount: i32 = row.get("amount");
-                    let artwork_price: f64 = row.get("artwork_price");
-
-                    let artwork_total = artwork_amount as f64 * artwork_price;
-
-                    if let Some(artworks_array) = order_entry["artworks"].as_array_mut() {
-                        artworks_array.push(json!({
-                            "id_artwork_in_order": artwork_in_order_id,
-                            "id_artwork": row.get::<String, _>("id_artwork"),
-                            "amount": artwork_amount,
-                            "artwork_title": row.get::<String, _>("artwork_title"),
-                            "artwork_description": row.get::<String, _>("artwork_description"),
-                            "artwork_year_created": row.get::<i32, _>("artwork_year_created"),
-                            "artwork_price": artwork_price,
-                            "artwork_id_artist": row.get::<String, _>("artwork_id_artist"),
-                            "artwork_art_type": row.get::<String, _>("artwork_art_type"),
-                            "total_price_for_artwork": artwork_total,
-                        }));
-                    }
-                    
-                    let current_total = order_entry["total_amount"].as_f64().unwrap_or(0.0);
-                    order_entry["total_amount"] = json!(current_total + artwork_total);
-                }
+    let rows = sqlx::query(query)
+        .bind(id_customer)
+        .bind(id_customer)
+        .bind(id_order)
+        .bind(id_order)
+        .fetch_all(pool)
+        .await?;
+
+    // Running sums per order, in the same positions as `orders`.
+    #[derive(Default)]
+    struct Sums {
+        subtotal: Option<Money>,
+        discount: Option<Money>,
+        tax: Option<Money>,
+        total: Option<Money>,
+        refunded: Option<Money>,
+        shipping: Option<Money>,
+    }
+    fn add(sum: &mut Option<Money>, amount: &Money) -> Result<(), MoneyError> {
+        *sum = Some(match sum.take() {
+            Some(sum) => sum.checked_add(amount)?,
+            None => amount.clone(),
+        });
+        Ok(())
+    }
+
+    let mut orders: Vec<serde_json::Value> = Vec::new();
+    let mut sums: Vec<Sums> = Vec::new();
+    let mut positions: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let money_error = |e: MoneyError| sqlx::Error::Decode(Box::new(e));
+
+    for row in rows {
+        let order_id: String = row.get("id_order");
+
+        let shipping = match row.get::<Option<String>, _>("shipping_currency") {
+            Some(currency) => Some(Money::new(row.get("shipping_minor"), &currency).map_err(money_error)?),
+            None => None,
+        };
+
+        let position = *positions.entry(order_id.clone()).or_insert_with(|| {
+            // Pickups have no address; orders from before shipping existed have no shipment at all.
+            let shipment = shipping.as_ref().map(|cost| json!({
+                "method": row.get::<String, _>("shipping_method"),
+                "kind": row.get::<ShippingKind, _>("shipping_kind"),
+                "address": row.get::<Option<String>, _>("shipping_country").map(|country| json!({
+                    "recipient": row.get::<Option<String>, _>("shipping_recipient"),
+                    "street": row.get::<Option<String>, _>("shipping_street"),
+                    "city": row.get::<Option<String>, _>("shipping_city"),
+                    "postal_code": row.get::<Option<String>, _>("shipping_postal_code"),
+                    "country": country,
+                    "phone": row.get::<Option<String>, _>("shipping_phone"),
+                })),
+                "cost": cost,
+                "tracking_number": row.get::<Option<String>, _>("tracking_number"),
+                "carrier": row.get::<Option<String>, _>("carrier"),
+            }));
+            orders.push(json!({
+                "id_order": order_id,
+                "order_date": row.get::<String, _>("order_date"),
+                "status": row.get::<OrderStatus, _>("status"),
+                "discount_code": row.get::<Option<String>, _>("discount_code"),
+                "tax_exempt": row.get::<bool, _>("tax_exempt"),
+                "shipping": shipment,
+                "customer": {
+                    "customer_id": row.get::<String, _>("customer_id"),
+                    "first_name": row.get::<String, _>("first_name"),
+                    "last_name": row.get::<String, _>("last_name"),
+                    "email": row.get::<String, _>("email"),
+                    "phone": row.get::<String, _>("phone"),
+                    "address": row.get::<String, _>("address"),
+                },
+                "artworks": [],
+            }));
+            sums.push(Sums { shipping: shipping.clone(), ..Sums::default() });
+            orders.len() - 1
+        });
+        let order_entry = &mut orders[position];
+        let order_sums = &mut sums[position];
+
+        // Orders without lines come back with NULLs from the LEFT JOIN.
+        if let Ok(Some(artwork_in_order_id)) = row.try_get::<Option<String>, _>("id_artwork_in_order") {
+            let artwork_amount: i32 = row.get("amount");
+            let artwork_price = Money::new(row.get("price_minor"), row.get("currency")).map_err(money_error)?;
+            let currency = artwork_price.currency.clone();
+
+            let artwork_total = artwork_price.checked_mul(artwork_amount.into()).map_err(money_error)?;
+            let line_discount = Money::new(row.get("discount_minor"), &currency).map_err(money_error)?;
+            let line_tax = Money::new(row.get("tax_minor"), &currency).map_err(money_error)?;
+            let line_total = Money::new(row.get("total_minor"), &currency).map_err(money_error)?;
+            let line_refunded = Money::new(row.get("refunded_minor"), &currency).map_err(money_error)?;
+            add(&mut order_sums.subtotal, &artwork_total).map_err(money_error)?;
+            add(&mut order_sums.discount, &line_discount).map_err(money_error)?;
+            add(&mut order_sums.tax, &line_tax).map_err(money_error)?;
+            add(&mut order_sums.total, &line_total).map_err(money_error)?;
+            add(&mut order_sums.refunded, &line_refunded).map_err(money_error)?;
+
+            // Price, title and artist come from the line's snapshot; the rest is
+            // descriptive and read from the catalog, if the artwork still exists.
+            if let Some(artworks_array) = order_entry["artworks"].as_array_mut() {
+                artworks_array.push(json!({
+                    "id_artwork_in_order": artwork_in_order_id,
+                    "id_artwork": row.get::<String, _>("id_artwork"),
+                    "amount": artwork_amount,
+                    "artwork_title": row.get::<String, _>("artwork_title"),
+                    "artwork_description": row.get::<Option<String>, _>("artwork_description"),
+                    "artwork_year_created": row.get::<Option<i32>, _>("artwork_year_created"),
+                    "artwork_price": artwork_price,
+                    "artwork_id_artist": row.get::<String, _>("artwork_id_artist"),
+                    "artist_name": row.get::<String, _>("artist_name"),
+                    "artwork_art_type": row.get::<Option<String>, _>("artwork_art_type"),
+                    "total_price_for_artwork": artwork_total,
+                    "discount_amount": line_discount,
+                    "tax_rate_basis_points": row.get::<i64, _>("tax_rate_basis_points"),
+                    "price_includes_tax": row.get::<bool, _>("price_includes_tax"),
+                    "tax_amount": line_tax,
+                    "line_total": line_total,
+                    "return_status": row.get::<Option<ReturnStatus>, _>("return_status"),
+                    "refunded_amount": line_refunded,
+                }));
             }
-            HttpResponse::Ok().json(orders_map.values().collect::<Vec<&serde_json::Value>>())  
         }
+    }
+
+    // `subtotal_amount` is the lines at list price and `total_amount` what was
+    // charged after discounts and tax, with shipping; `tax_amount` is the tax on
+    // the lines. `net_amount` is what the gallery keeps after returns.
+    for (order_entry, order_sums) in orders.iter_mut().zip(sums) {
+        let subtotal = order_sums.subtotal.unwrap_or_default();
+        let zero = Money::zero(&subtotal.currency);
+        let shipping = order_sums.shipping.unwrap_or_else(|| zero.clone());
+        let total = order_sums.total.unwrap_or_else(|| zero.clone()).checked_add(&shipping).map_err(money_error)?;
+        let refunded = order_sums.refunded.unwrap_or_else(|| zero.clone());
+        order_entry["net_amount"] = json!(total.checked_sub(&refunded).map_err(money_error)?);
+        order_entry["subtotal_amount"] = json!(subtotal);
+        order_entry["discount_amount"] = json!(order_sums.discount.unwrap_or_else(|| zero.clone()));
+        order_entry["tax_amount"] = json!(order_sums.tax.unwrap_or(zero));
+        order_entry["shipping_amount"] = json!(shipping);
+        order_entry["total_amount"] = json!(total);
+        order_entry["refunded_amount"] = json!(refunded);
+    }
+
+    Ok(orders)
+}
+
+#[get("/detailed")]
+pub async fn get_detailed_orders(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    match fetch_detailed_orders(&pool, customer_filter(&principal), None).await {
+        Ok(orders) => HttpResponse::Ok().json(orders),
         Err(e) => {
             eprintln!("Failed to fetch detailed orders: {}", e);
             HttpResponse::InternalServerError().body(format!("Database error: {}", e))
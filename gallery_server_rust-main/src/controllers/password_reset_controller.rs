@@ -0,0 +1,107 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use chrono::Duration;
+use sqlx::SqlitePool;
+use crate::auth::password;
+use crate::controllers::account_token_controller;
+use crate::mail::{self, MailSender, OutgoingMail};
+use crate::models::account_token::{PasswordResetConfirm, PasswordResetRequest, TokenPurpose};
+
+pub const RESET_TOKEN_TTL_MINUTES: i64 = 30;
+
+#[post("/password-reset/request")]
+pub async fn request_password_reset(
+    pool: web::Data<SqlitePool>,
+    mailer: web::Data<dyn MailSender>,
+    request: web::Json<PasswordResetRequest>,
+) -> impl Responder {
+    // Same answer whether or not the email exists, so the endpoint can't be used to probe accounts.
+    let accepted = HttpResponse::Ok().body("If the email is registered, a password reset link has been sent");
+
+    let (customer_id, email) = match sqlx::query_as::<_, (String, String)>("SELECT customer_id, email FROM customers WHERE lower(email) = lower(?)")
+        .bind(request.email.trim())
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(customer)) => customer,
+        Ok(None) => return accepted,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let token = match account_token_controller::issue_token(
+        &pool,
+        &customer_id,
+        TokenPurpose::PasswordReset,
+        Duration::minutes(RESET_TOKEN_TTL_MINUTES),
+    )
+    .await
+    {
+        Ok(token) => token,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let reset_mail = OutgoingMail {
+        to: email,
+        subject: "Reset your gallery password".to_string(),
+        body: format!(
+            "Someone asked to reset the password for this account.\n\nOpen this link within {} minutes to choose a new password:\n{}/reset-password?token={}\n\nIf it wasn't you, ignore this email.",
+            RESET_TOKEN_TTL_MINUTES,
+            mail::app_url(),
+            token
+        ),
+    };
+    // A failed send is only logged: answering differently would tell the caller the email is registered.
+    if let Err(e) = mailer.send(&reset_mail).await {
+        eprintln!("Failed to send password reset mail: {}", e);
+    }
+
+    accepted
+}
+
+#[post("/password-reset/confirm")]
+pub async fn confirm_password_reset(pool: web::Data<SqlitePool>, confirm: web::Json<PasswordResetConfirm>) -> impl Responder {
+    if confirm.new_password.chars().count() < password::MIN_PASSWORD_LENGTH {
+        return HttpResponse::BadRequest().body(format!(
+            "Password must be at least {} characters long",
+            password::MIN_PASSWORD_LENGTH
+        ));
+    }
+
+    let password_hash = match password::hash_password(&confirm.new_password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            eprintln!("Failed to hash password: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to store password");
+        }
+    };
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let customer_id = match account_token_controller::consume_token(&mut tx, &confirm.token, TokenPurpose::PasswordReset).await {
+        Ok(Some(customer_id)) => customer_id,
+        Ok(None) => return HttpResponse::BadRequest().body("Invalid or expired reset token"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let result = async {
+        sqlx::query("UPDATE customers SET password_hash = ? WHERE customer_id = ?")
+            .bind(&password_hash)
+            .bind(&customer_id)
+            .execute(&mut *tx)
+            .await?;
+        // A reset signs the account out everywhere.
+        sqlx::query("DELETE FROM sessions WHERE customer_id = ?")
+            .bind(&customer_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => HttpResponse::Ok().body("Password has been reset"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
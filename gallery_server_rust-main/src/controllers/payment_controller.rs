@@ -0,0 +1,449 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::controllers::order_controller::{self, OrderTransition};
+use crate::models::money::Money;
+use crate::models::order::OrderStatus;
+use crate::models::payment::{Payment, PaymentRequest, PaymentStatus, RefundRequest};
+use crate::payments::{PaymentError, PaymentIntent, PaymentProvider};
+
+pub enum PaymentFailure {
+    Provider(PaymentError),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for PaymentFailure {
+    fn from(e: sqlx::Error) -> Self {
+        PaymentFailure::Database(e)
+    }
+}
+
+impl From<PaymentError> for PaymentFailure {
+    fn from(e: PaymentError) -> Self {
+        PaymentFailure::Provider(e)
+    }
+}
+
+impl PaymentFailure {
+    pub fn response(&self) -> HttpResponse {
+        match self {
+            PaymentFailure::Provider(e) => HttpResponse::BadGateway().body(e.to_string()),
+            PaymentFailure::Database(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+        }
+    }
+}
+
+pub async fn init_payments_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating payments tables if not exist...");
+    // No cascade from ORDERS: payment records are kept for accounting.
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS payments (
+            id_payment TEXT PRIMARY KEY NOT NULL,
+            id_order TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            provider_reference TEXT,
+            status TEXT NOT NULL,
+            amount_minor INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            refunded_minor INTEGER NOT NULL DEFAULT 0,
+            failure_reason TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (id_order) REFERENCES ORDERS(id_order)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS payments_provider_reference ON payments (provider, provider_reference)")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS payment_refunds (
+            id_refund TEXT PRIMARY KEY NOT NULL,
+            id_payment TEXT NOT NULL,
+            provider_reference TEXT NOT NULL,
+            amount_minor INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            reason TEXT,
+            created_at TEXT NOT NULL,
+            created_by TEXT,
+            FOREIGN KEY (id_payment) REFERENCES payments(id_payment)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ payments tables ready");
+
+    Ok(())
+}
+
+pub async fn fetch_payment(conn: &mut SqliteConnection, id_payment: &str) -> Result<Option<Payment>, sqlx::Error> {
+    sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id_payment = ?")
+        .bind(id_payment)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+async fn set_payment_status(
+    conn: &mut SqliteConnection,
+    id_payment: &str,
+    status: PaymentStatus,
+    reference: Option<&str>,
+    failure_reason: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE payments SET status = ?, provider_reference = COALESCE(?, provider_reference), failure_reason = ?, updated_at = ? WHERE id_payment = ?"
+    )
+    .bind(status)
+    .bind(reference)
+    .bind(failure_reason)
+    .bind(Utc::now())
+    .bind(id_payment)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Whether the order has a payment the provider has not settled yet. Its amount
+/// was fixed when it started, so the order's lines must stay as they are.
+pub async fn payment_in_progress(conn: &mut SqliteConnection, id_order: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM payments WHERE id_order = ? AND status IN (?, ?))")
+        .bind(id_order)
+        .bind(PaymentStatus::Pending)
+        .bind(PaymentStatus::Authorized)
+        .fetch_one(&mut *conn)
+        .await
+}
+
+/// Captures an authorized payment and marks its order paid. If the order was
+/// cancelled or its total changed while the payment waited, the money is left
+/// uncaptured instead.
+async fn settle_payment(pool: &SqlitePool, provider: &dyn PaymentProvider, id_payment: &str, changed_by: Option<&str>) -> Result<Payment, PaymentFailure> {
+    let mut conn = pool.acquire().await?;
+    let payment = fetch_payment(&mut conn, id_payment).await?.ok_or(sqlx::Error::RowNotFound)?;
+    let order_status = sqlx::query_scalar::<_, OrderStatus>("SELECT status FROM ORDERS WHERE id_order = ?")
+        .bind(&payment.id_order)
+        .fetch_one(&mut *conn)
+        .await?;
+    let refusal = if order_status != OrderStatus::Pending {
+        Some(format!("Order is {}; payment not captured", order_status))
+    } else if order_controller::order_total(&mut conn, &payment.id_order).await?.as_ref() != Some(&payment.amount) {
+        Some("Order total changed since the payment started; payment not captured".to_string())
+    } else {
+        None
+    };
+    if let Some(reason) = refusal {
+        set_payment_status(&mut conn, id_payment, PaymentStatus::Cancelled, None, Some(&reason)).await?;
+        return Ok(fetch_payment(&mut conn, id_payment).await?.ok_or(sqlx::Error::RowNotFound)?);
+    }
+
+    provider.capture(payment.provider_reference.as_deref().unwrap_or_default(), &payment.amount).await?;
+
+    let mut tx = pool.begin().await?;
+    set_payment_status(&mut tx, id_payment, PaymentStatus::Captured, None, None).await?;
+    let note = format!("Payment {} captured", id_payment);
+    if let OrderTransition::Refused(current) = order_controller::transition_order(&mut tx, &payment.id_order, OrderStatus::Paid, changed_by, Some(&note)).await? {
+        eprintln!("⚠️ Payment {} was captured but order {} is {}", id_payment, payment.id_order, current);
+    }
+    tx.commit().await?;
+    println!("💰 Payment {} captured {} for order {}", id_payment, payment.amount, payment.id_order);
+
+    Ok(fetch_payment(&mut conn, id_payment).await?.ok_or(sqlx::Error::RowNotFound)?)
+}
+
+/// The order's captured payment, the one refunds are issued against.
+pub async fn captured_payment(conn: &mut SqliteConnection, id_order: &str) -> Result<Option<Payment>, sqlx::Error> {
+    sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id_order = ? AND status = 'captured'")
+        .bind(id_order)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// Records a refund the provider has already made. Run it in the caller's
+/// transaction, next to whatever else the refund changes. Returns the refund id
+/// and whether the payment is now refunded in full.
+pub async fn record_refund(
+    conn: &mut SqliteConnection,
+    payment: &Payment,
+    amount: &Money,
+    provider_reference: &str,
+    reason: Option<&str>,
+    issued_by: &str,
+) -> Result<(String, bool), sqlx::Error> {
+    let id_refund = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO payment_refunds (id_refund, id_payment, provider_reference, amount_minor, currency, reason, created_at, created_by) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&id_refund)
+    .bind(&payment.id_payment)
+    .bind(provider_reference)
+    .bind(amount.minor)
+    .bind(&amount.currency)
+    .bind(reason)
+    .bind(Utc::now())
+    .bind(issued_by)
+    .execute(&mut *conn)
+    .await?;
+    let status = sqlx::query_scalar::<_, PaymentStatus>(
+        r#"
+        UPDATE payments
+        SET refunded_minor = refunded_minor + ?,
+            status = CASE WHEN refunded_minor + ? >= amount_minor THEN 'refunded' ELSE status END,
+            updated_at = ?
+        WHERE id_payment = ?
+        RETURNING status
+        "#
+    )
+    .bind(amount.minor)
+    .bind(amount.minor)
+    .bind(Utc::now())
+    .bind(&payment.id_payment)
+    .fetch_one(&mut *conn)
+    .await?;
+    println!("↩️ Refunded {} of payment {} for order {}", amount, payment.id_payment, payment.id_order);
+    Ok((id_refund, status == PaymentStatus::Refunded))
+}
+
+/// Moves an order to refunded or partially refunded after money went back to
+/// the customer. Each further partial refund adds its own entry to the history.
+pub async fn mark_order_refunded(conn: &mut SqliteConnection, id_order: &str, in_full: bool, changed_by: &str, note: Option<&str>) -> Result<(), sqlx::Error> {
+    let next = if in_full { OrderStatus::Refunded } else { OrderStatus::PartiallyRefunded };
+    match order_controller::transition_order(conn, id_order, next, Some(changed_by), note).await? {
+        OrderTransition::Refused(current) if current != next => {
+            println!("⚠️ Order {} stays {} after a refund", id_order, current);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn payment_response(payment: Payment) -> HttpResponse {
+    match payment.status {
+        PaymentStatus::Captured => HttpResponse::Created().json(payment),
+        PaymentStatus::Pending | PaymentStatus::Authorized => HttpResponse::Accepted().json(payment),
+        PaymentStatus::Declined => HttpResponse::PaymentRequired().json(payment),
+        _ => HttpResponse::Conflict().json(payment),
+    }
+}
+
+#[post("/{id_order}/payments")]
+pub async fn create_payment(
+    pool: web::Data<SqlitePool>,
+    provider: web::Data<dyn PaymentProvider>,
+    principal: Principal,
+    path: web::Path<String>,
+    request: web::Json<PaymentRequest>,
+) -> impl Responder {
+    let id_order = path.into_inner();
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    match sqlx::query_as::<_, (String, OrderStatus)>("SELECT id_customer, status FROM ORDERS WHERE id_order = ?")
+        .bind(&id_order)
+        .fetch_optional(&mut *conn)
+        .await
+    {
+        // Someone else's order looks exactly like a missing one.
+        Ok(Some((id_customer, _))) if !principal.can_access_customer(&id_customer) => return HttpResponse::NotFound().body("Order not found"),
+        Ok(Some((_, OrderStatus::Pending))) => {}
+        Ok(Some((_, status))) => return HttpResponse::Conflict().body(format!("Order {} is {} and does not take payments", id_order, status)),
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    let amount = match order_controller::order_total(&mut conn, &id_order).await {
+        Ok(Some(amount)) => amount,
+        Ok(None) => return HttpResponse::BadRequest().body(format!("Order {} has no lines to pay for", id_order)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let id_payment = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    // One guarded insert, so two concurrent attempts cannot both start paying the same order.
+    let started = sqlx::query(
+        r#"
+        INSERT INTO payments (id_payment, id_order, provider, status, amount_minor, currency, created_at, updated_at)
+        SELECT ?, ?, ?, ?, ?, ?, ?, ?
+        WHERE NOT EXISTS (SELECT 1 FROM payments WHERE id_order = ? AND status IN ('pending', 'authorized', 'captured'))
+        "#
+    )
+    .bind(&id_payment)
+    .bind(&id_order)
+    .bind(provider.name())
+    .bind(PaymentStatus::Pending)
+    .bind(amount.minor)
+    .bind(&amount.currency)
+    .bind(now)
+    .bind(now)
+    .bind(&id_order)
+    .execute(&mut *conn)
+    .await;
+    match started {
+        Ok(result) if result.rows_affected() == 0 => {
+            return HttpResponse::Conflict().body(format!("Order {} already has a payment in progress", id_order))
+        }
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    let intent = PaymentIntent {
+        id_payment: id_payment.clone(),
+        id_order: id_order.clone(),
+        amount,
+        payment_method: request.payment_method.clone(),
+    };
+    let created = match provider.create_intent(&intent).await {
+        Ok(created) => created,
+        Err(e) => {
+            if let Err(db) = set_payment_status(&mut conn, &id_payment, PaymentStatus::Declined, None, Some(&e.to_string())).await {
+                eprintln!("Failed to record failed payment {}: {}", id_payment, db);
+            }
+            return PaymentFailure::Provider(e).response();
+        }
+    };
+    if let Err(e) = set_payment_status(&mut conn, &id_payment, created.status, Some(&created.reference), created.failure_reason.as_deref()).await {
+        return HttpResponse::InternalServerError().body(format!("Database error: {}", e));
+    }
+    drop(conn);
+
+    let payment = if created.status == PaymentStatus::Authorized {
+        settle_payment(&pool, provider.get_ref(), &id_payment, Some(&principal.customer_id)).await
+    } else {
+        let mut conn = match pool.acquire().await {
+            Ok(conn) => conn,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+        };
+        fetch_payment(&mut conn, &id_payment).await.map_err(PaymentFailure::from).and_then(|p| p.ok_or(sqlx::Error::RowNotFound.into()))
+    };
+    match payment {
+        Ok(payment) => payment_response(payment),
+        Err(failure) => failure.response(),
+    }
+}
+
+#[get("/{id_order}/payments")]
+pub async fn get_order_payments(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_order = path.into_inner();
+    match sqlx::query_scalar::<_, String>("SELECT id_customer FROM ORDERS WHERE id_order = ?")
+        .bind(&id_order)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(id_customer)) if principal.can_access_customer(&id_customer) => {}
+        Ok(_) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    match sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id_order = ? ORDER BY created_at")
+        .bind(&id_order)
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(payments) => HttpResponse::Ok().json(payments),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/webhook")]
+pub async fn payment_webhook(pool: web::Data<SqlitePool>, provider: web::Data<dyn PaymentProvider>, req: HttpRequest, body: web::Bytes) -> impl Responder {
+    let signature = req.headers().get("X-Payment-Signature").and_then(|value| value.to_str().ok());
+    let event = match provider.handle_webhook(signature, &body).await {
+        Ok(event) => event,
+        Err(e) => {
+            eprintln!("⚠️ Rejected payment webhook: {}", e);
+            return HttpResponse::BadRequest().body(e.to_string());
+        }
+    };
+
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    let payment = match sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE provider = ? AND provider_reference = ?")
+        .bind(provider.name())
+        .bind(&event.reference)
+        .fetch_optional(&mut *conn)
+        .await
+    {
+        Ok(Some(payment)) => payment,
+        Ok(None) => return HttpResponse::NotFound().body("Payment not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    // Providers redeliver webhooks; only a pending payment still has anything to learn.
+    if payment.status != PaymentStatus::Pending {
+        return HttpResponse::Ok().body(format!("Payment {} is already {}", payment.id_payment, payment.status));
+    }
+
+    if let Err(e) = set_payment_status(&mut conn, &payment.id_payment, event.status, None, event.failure_reason.as_deref()).await {
+        return HttpResponse::InternalServerError().body(format!("Database error: {}", e));
+    }
+    drop(conn);
+    let status = if event.status == PaymentStatus::Authorized {
+        match settle_payment(&pool, provider.get_ref(), &payment.id_payment, None).await {
+            Ok(settled) => settled.status,
+            Err(failure) => return failure.response(),
+        }
+    } else {
+        event.status
+    };
+    HttpResponse::Ok().body(format!("Payment {} is now {}", payment.id_payment, status))
+}
+
+#[post("/{id_payment}/refunds")]
+pub async fn refund_payment(
+    pool: web::Data<SqlitePool>,
+    provider: web::Data<dyn PaymentProvider>,
+    principal: Principal,
+    path: web::Path<String>,
+    request: web::Json<RefundRequest>,
+) -> impl Responder {
+    let id_payment = path.into_inner();
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    let payment = match fetch_payment(&mut conn, &id_payment).await {
+        Ok(Some(payment)) => payment,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Payment with id {} not found", id_payment)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    drop(conn);
+    if payment.status != PaymentStatus::Captured {
+        return HttpResponse::Conflict().body(format!("Payment {} is {} and cannot be refunded", id_payment, payment.status));
+    }
+
+    let remaining = match payment.amount.checked_sub(&payment.refunded) {
+        Ok(remaining) => remaining,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let amount = request.amount.clone().unwrap_or_else(|| remaining.clone());
+    if amount.currency != payment.amount.currency {
+        return HttpResponse::BadRequest().body(format!("Refunds of this payment must be in {}", payment.amount.currency));
+    }
+    if amount.minor <= 0 || amount.minor > remaining.minor {
+        return HttpResponse::BadRequest().body(format!("A refund must be more than zero and at most {}", remaining));
+    }
+
+    let refund_reference = match provider.refund(payment.provider_reference.as_deref().unwrap_or_default(), &amount).await {
+        Ok(reference) => reference,
+        Err(e) => return PaymentFailure::Provider(e).response(),
+    };
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let (_, in_full) = record_refund(&mut tx, &payment, &amount, &refund_reference, request.reason.as_deref(), &principal.customer_id).await?;
+        mark_order_refunded(&mut tx, &payment.id_order, in_full, &principal.customer_id, request.reason.as_deref()).await?;
+        let refunded = fetch_payment(&mut tx, &id_payment).await?.ok_or(sqlx::Error::RowNotFound)?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(refunded)
+    }
+    .await;
+    match result {
+        Ok(payment) => HttpResponse::Created().json(payment),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
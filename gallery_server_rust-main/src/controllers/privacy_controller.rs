@@ -0,0 +1,219 @@
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use crate::controllers::{cart_controller, login_throttle_controller, order_controller};
+use crate::models::customer::Customer;
+use crate::models::cart::CartItem;
+use crate::models::customer_export::{AccountTokenRecord, BidRecord, CustomerExport, SessionRecord};
+use crate::models::offer::Offer;
+use crate::models::order_return::OrderReturn;
+use crate::models::payment::Payment;
+use crate::models::shipping::Shipment;
+use crate::models::role::Role;
+
+pub enum Erasure {
+    Erased,
+    AlreadyErased,
+    NotFound,
+}
+
+/// Collects the profile, orders with everything attached to them, offers, bids,
+/// cart and account activity of one customer.
+pub async fn export_customer_data(pool: &SqlitePool, customer_id: &str) -> Result<Option<CustomerExport>, sqlx::Error> {
+    let Some(customer) = sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE customer_id = ?")
+        .bind(customer_id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let orders = order_controller::fetch_detailed_orders(pool, Some(customer_id), None).await?;
+    let payments = sqlx::query_as::<_, Payment>(
+        "SELECT * FROM payments WHERE id_order IN (SELECT id_order FROM ORDERS WHERE id_customer = ?) ORDER BY created_at",
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+    let returns = sqlx::query_as::<_, OrderReturn>(
+        "SELECT * FROM order_returns WHERE id_order IN (SELECT id_order FROM ORDERS WHERE id_customer = ?) ORDER BY requested_at",
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+    let shipments = sqlx::query_as::<_, Shipment>(
+        "SELECT * FROM order_shipments WHERE id_order IN (SELECT id_order FROM ORDERS WHERE id_customer = ?) ORDER BY updated_at",
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+    // Only invoices already issued; exporting does not issue new ones.
+    let invoices = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT i.document FROM invoices i
+        JOIN ORDERS o ON o.id_order = i.id_order
+        WHERE o.id_customer = ? AND i.document IS NOT NULL
+        ORDER BY i.invoice_number
+        "#,
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|document| serde_json::from_str(document).map_err(|e| sqlx::Error::Decode(Box::new(e))))
+    .collect::<Result<Vec<_>, _>>()?;
+    let offers = sqlx::query_as::<_, Offer>("SELECT * FROM offers WHERE customer_id = ? ORDER BY created_at")
+        .bind(customer_id)
+        .fetch_all(pool)
+        .await?;
+    let bids = sqlx::query_as::<_, BidRecord>(
+        r#"
+        SELECT b.id_bid, b.id_auction, a.id_artwork, b.amount_minor, a.currency, b.placed_at
+        FROM bids b
+        JOIN auctions a ON a.id_auction = b.id_auction
+        WHERE b.customer_id = ?
+        ORDER BY b.placed_at
+        "#,
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+    let cart_items = sqlx::query_as::<_, CartItem>(
+        r#"
+        SELECT a.id_artwork, a.title, a.price_minor, a.currency, a.id_artist, a.art_type, ci.added_at, ci.reserved_until
+        FROM cart_items ci
+        JOIN carts c ON c.id_cart = ci.id_cart
+        JOIN ARTWORKS a ON a.id_artwork = ci.id_artwork
+        WHERE c.customer_id = ?
+        ORDER BY ci.added_at
+        "#,
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+    let sessions = sqlx::query_as::<_, SessionRecord>(
+        "SELECT created_at, expires_at FROM sessions WHERE customer_id = ? ORDER BY created_at",
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+    let account_tokens = sqlx::query_as::<_, AccountTokenRecord>(
+        "SELECT purpose, created_at, expires_at, used_at FROM account_tokens WHERE customer_id = ? ORDER BY created_at",
+    )
+    .bind(customer_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(CustomerExport {
+        exported_at: Utc::now(),
+        customer,
+        orders,
+        payments,
+        returns,
+        shipments,
+        invoices,
+        offers,
+        bids,
+        cart_items,
+        sessions,
+        account_tokens,
+    }))
+}
+
+/// Anonymises the customer's personal fields and drops their credentials. The row itself
+/// stays so that orders and their lines keep pointing at it for the accounts.
+///
+/// Issued invoices are left as they are, name and address included: they are
+/// accounting records the gallery must keep unchanged for the statutory
+/// retention period, which overrides the right to erasure.
+pub async fn erase_customer_data(pool: &SqlitePool, customer_id: &str) -> Result<Erasure, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let existing = sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE customer_id = ?")
+        .bind(customer_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let existing = match existing {
+        Some(customer) if customer.erased_at.is_some() => return Ok(Erasure::AlreadyErased),
+        Some(customer) => customer,
+        None => return Ok(Erasure::NotFound),
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE customers
+        SET first_name = 'Erased', last_name = 'Customer', email = ?, phone = '', address = '',
+            password_hash = NULL, role = ?, email_verified_at = NULL, erased_at = ?, artist_id = NULL,
+            tax_exempt = 0, tax_exempt_reason = NULL
+        WHERE customer_id = ?
+        "#,
+    )
+    // Unique per customer and undeliverable by design (RFC 2606).
+    .bind(format!("erased-{}@invalid", customer_id))
+    .bind(Role::Customer)
+    .bind(Utc::now())
+    .bind(customer_id)
+    .execute(&mut *tx)
+    .await?;
+    // Shipments keep their method, cost and tracking; who they went to and where is erased.
+    sqlx::query(
+        r#"
+        UPDATE order_shipments
+        SET recipient = NULL, street = NULL, city = NULL, postal_code = NULL, phone = NULL
+        WHERE id_order IN (SELECT id_order FROM ORDERS WHERE id_customer = ?)
+        "#,
+    )
+    .bind(customer_id)
+    .execute(&mut *tx)
+    .await?;
+    cart_controller::release_customer_holds(&mut tx, customer_id).await?;
+    sqlx::query("DELETE FROM sessions WHERE customer_id = ?")
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM account_tokens WHERE customer_id = ?")
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .await?;
+    // Stored responses can repeat the profile, addresses and orders back.
+    sqlx::query("DELETE FROM idempotency_keys WHERE customer_id = ?")
+        .bind(customer_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM login_throttle WHERE subject = ?")
+        .bind(login_throttle_controller::account_subject(&existing.email))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    println!("🧹 Customer {} erased", customer_id);
+    Ok(Erasure::Erased)
+}
+
+/// Serves an export as a downloadable JSON file.
+pub async fn export_response(pool: &SqlitePool, customer_id: &str) -> HttpResponse {
+    match export_customer_data(pool, customer_id).await {
+        Ok(Some(export)) => HttpResponse::Ok()
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"customer-{}-export.json\"", customer_id),
+            ))
+            .json(export),
+        Ok(None) => HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+pub async fn erasure_response(pool: &SqlitePool, customer_id: &str) -> HttpResponse {
+    match erase_customer_data(pool, customer_id).await {
+        Ok(Erasure::Erased) => HttpResponse::Ok().body("Customer data erased; order history and issued invoices were kept"),
+        Ok(Erasure::AlreadyErased) => HttpResponse::Conflict().body("Customer data has already been erased"),
+        Ok(Erasure::NotFound) => HttpResponse::NotFound().body("Customer not found"),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/{customer_id}/export")]
+pub async fn export_customer(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+    export_response(&pool, &path.into_inner()).await
+}
@@ -0,0 +1,293 @@
+use actix_web::{get, post, put, web, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::controllers::{artwork_controller, payment_controller};
+use crate::controllers::payment_controller::PaymentFailure;
+use crate::models::money::Money;
+use crate::models::order::OrderStatus;
+use crate::models::order_return::{OrderReturn, ReturnDecision, ReturnListQuery, ReturnRequest, ReturnStatus};
+use crate::payments::PaymentProvider;
+
+pub async fn init_returns_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating order_returns table if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_returns (
+            id_return TEXT PRIMARY KEY NOT NULL,
+            id_order TEXT NOT NULL,
+            id_artwork_in_order TEXT NOT NULL,
+            status TEXT NOT NULL,
+            reason TEXT,
+            refund_minor INTEGER,
+            currency TEXT NOT NULL,
+            requested_by TEXT NOT NULL,
+            requested_at TEXT NOT NULL,
+            decided_by TEXT,
+            decided_at TEXT,
+            note TEXT,
+            id_refund TEXT,
+            FOREIGN KEY (id_order) REFERENCES ORDERS(id_order) ON DELETE CASCADE,
+            FOREIGN KEY (id_artwork_in_order) REFERENCES artworks_in_order(id_artwork_in_order) ON DELETE CASCADE,
+            FOREIGN KEY (id_refund) REFERENCES payment_refunds(id_refund)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    // A line can be asked back again after a rejection, but never has two live returns.
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS order_returns_open_line ON order_returns (id_artwork_in_order) WHERE status <> 'rejected'")
+        .execute(pool)
+        .await?;
+    println!("✅ order_returns table ready");
+
+    Ok(())
+}
+
+async fn fetch_return(conn: &mut SqliteConnection, id_return: &str) -> Result<Option<OrderReturn>, sqlx::Error> {
+    sqlx::query_as::<_, OrderReturn>("SELECT * FROM order_returns WHERE id_return = ?")
+        .bind(id_return)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// The artwork and what the customer paid for a line, after its discount and tax.
+async fn line_paid(conn: &mut SqliteConnection, id_artwork_in_order: &str) -> Result<(String, Money), sqlx::Error> {
+    let (id_artwork, total_minor, currency) = sqlx::query_as::<_, (String, i64, String)>(
+        "SELECT id_artwork, total_minor, currency FROM artworks_in_order WHERE id_artwork_in_order = ?",
+    )
+    .bind(id_artwork_in_order)
+    .fetch_one(&mut *conn)
+    .await?;
+    let paid = Money::new(total_minor, &currency).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+    Ok((id_artwork, paid))
+}
+
+#[post("/{id_order}/returns")]
+pub async fn create_return(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>, request: web::Json<ReturnRequest>) -> impl Responder {
+    let id_order = path.into_inner();
+    match sqlx::query_as::<_, (String, OrderStatus)>("SELECT id_customer, status FROM ORDERS WHERE id_order = ?")
+        .bind(&id_order)
+        .fetch_optional(&**pool)
+        .await
+    {
+        // Someone else's order looks exactly like a missing one.
+        Ok(Some((id_customer, _))) if !principal.can_access_customer(&id_customer) => return HttpResponse::NotFound().body("Order not found"),
+        Ok(Some((_, OrderStatus::Paid | OrderStatus::Shipped | OrderStatus::Delivered | OrderStatus::PartiallyRefunded))) => {}
+        Ok(Some((_, status))) => return HttpResponse::Conflict().body(format!("Order {} is {} and cannot take returns", id_order, status)),
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    let currency = match sqlx::query_scalar::<_, String>("SELECT currency FROM artworks_in_order WHERE id_artwork_in_order = ? AND id_order = ?")
+        .bind(&request.id_artwork_in_order)
+        .bind(&id_order)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(currency)) => currency,
+        Ok(None) => return HttpResponse::NotFound().body("Order line not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let id_return = Uuid::new_v4().to_string();
+    match sqlx::query_as::<_, OrderReturn>(
+        r#"
+        INSERT INTO order_returns (id_return, id_order, id_artwork_in_order, status, reason, currency, requested_by, requested_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#
+    )
+    .bind(&id_return)
+    .bind(&id_order)
+    .bind(&request.id_artwork_in_order)
+    .bind(ReturnStatus::Requested)
+    .bind(&request.reason)
+    .bind(&currency)
+    .bind(&principal.customer_id)
+    .bind(Utc::now())
+    .fetch_one(&**pool)
+    .await
+    {
+        Ok(created) => {
+            println!("📮 Return {} requested for line {} of order {}", id_return, request.id_artwork_in_order, id_order);
+            HttpResponse::Created().json(created)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().body(format!("Order line {} already has a return in progress", request.id_artwork_in_order))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/{id_order}/returns")]
+pub async fn get_order_returns(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_order = path.into_inner();
+    match sqlx::query_scalar::<_, String>("SELECT id_customer FROM ORDERS WHERE id_order = ?")
+        .bind(&id_order)
+        .fetch_optional(&**pool)
+        .await
+    {
+        Ok(Some(id_customer)) if principal.can_access_customer(&id_customer) => {}
+        Ok(_) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    match sqlx::query_as::<_, OrderReturn>("SELECT * FROM order_returns WHERE id_order = ? ORDER BY requested_at")
+        .bind(&id_order)
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(returns) => HttpResponse::Ok().json(returns),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/")]
+pub async fn get_returns(pool: web::Data<SqlitePool>, query: web::Query<ReturnListQuery>) -> impl Responder {
+    match sqlx::query_as::<_, OrderReturn>("SELECT * FROM order_returns WHERE (? IS NULL OR status = ?) ORDER BY requested_at DESC")
+        .bind(query.status)
+        .bind(query.status)
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(returns) => HttpResponse::Ok().json(returns),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Sends the approved amount back through the order's payment, puts the piece
+/// back on sale and moves the order to (partially) refunded, all in one commit.
+/// Orders paid outside the payment provider have the refund recorded only.
+async fn refund_return(pool: &SqlitePool, provider: &dyn PaymentProvider, order_return: &OrderReturn, staff_id: &str, note: Option<&str>) -> Result<bool, PaymentFailure> {
+    let mut conn = pool.acquire().await?;
+    let amount = order_return.refund_amount.clone().ok_or(sqlx::Error::RowNotFound)?;
+    let payment = payment_controller::captured_payment(&mut conn, &order_return.id_order).await?;
+    drop(conn);
+    let provider_reference = match &payment {
+        Some(payment) => Some(provider.refund(payment.provider_reference.as_deref().unwrap_or_default(), &amount).await?),
+        None => {
+            println!("⚠️ Order {} has no captured payment; return {} is recorded without a provider refund", order_return.id_order, order_return.id_return);
+            None
+        }
+    };
+
+    let mut tx = pool.begin().await?;
+    let id_refund = match (&payment, &provider_reference) {
+        (Some(payment), Some(reference)) => {
+            Some(payment_controller::record_refund(&mut tx, payment, &amount, reference, note.or(order_return.reason.as_deref()), staff_id).await?.0)
+        }
+        _ => None,
+    };
+    let updated = sqlx::query("UPDATE order_returns SET status = ?, id_refund = ?, decided_by = ?, decided_at = ?, note = COALESCE(?, note) WHERE id_return = ? AND status = ?")
+        .bind(ReturnStatus::Refunded)
+        .bind(&id_refund)
+        .bind(staff_id)
+        .bind(Utc::now())
+        .bind(note)
+        .bind(&order_return.id_return)
+        .bind(ReturnStatus::Approved)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if updated == 0 {
+        if let Some(reference) = provider_reference {
+            eprintln!("⚠️ Return {} changed while refunding; provider refund {} needs to be reconciled", order_return.id_return, reference);
+        }
+        return Ok(false);
+    }
+
+    let (id_artwork, _) = line_paid(&mut tx, &order_return.id_artwork_in_order).await?;
+    artwork_controller::release_artwork(&mut tx, &id_artwork).await?;
+
+    let open_lines = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM artworks_in_order aio
+        WHERE aio.id_order = ?
+          AND NOT EXISTS (SELECT 1 FROM order_returns r WHERE r.id_artwork_in_order = aio.id_artwork_in_order AND r.status = 'refunded')
+        "#
+    )
+    .bind(&order_return.id_order)
+    .fetch_one(&mut *tx)
+    .await?;
+    let note = format!("Return {} refunded {}", order_return.id_return, amount);
+    payment_controller::mark_order_refunded(&mut tx, &order_return.id_order, open_lines == 0, staff_id, Some(&note)).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+#[put("/{id_return}/status")]
+pub async fn update_return_status(
+    pool: web::Data<SqlitePool>,
+    provider: web::Data<dyn PaymentProvider>,
+    principal: Principal,
+    path: web::Path<String>,
+    decision: web::Json<ReturnDecision>,
+) -> impl Responder {
+    let id_return = path.into_inner();
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    let order_return = match fetch_return(&mut conn, &id_return).await {
+        Ok(Some(order_return)) => order_return,
+        Ok(None) => return HttpResponse::NotFound().body(format!("Return with id {} not found", id_return)),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+    if !order_return.status.can_transition_to(decision.status) {
+        return HttpResponse::Conflict().body(format!("Return with id {} cannot move from {} to {}", id_return, order_return.status, decision.status));
+    }
+
+    let moved = match decision.status {
+        ReturnStatus::Approved => {
+            let paid = match line_paid(&mut conn, &order_return.id_artwork_in_order).await {
+                Ok((_, paid)) => paid,
+                Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+            };
+            let refund = decision.refund_amount.clone().unwrap_or_else(|| paid.clone());
+            if refund.currency != paid.currency {
+                return HttpResponse::BadRequest().body(format!("The refund for this line must be in {}", paid.currency));
+            }
+            if refund.minor <= 0 || refund.minor > paid.minor {
+                return HttpResponse::BadRequest().body(format!("The refund must be more than zero and at most {}", paid));
+            }
+            sqlx::query("UPDATE order_returns SET status = ?, refund_minor = ?, decided_by = ?, decided_at = ?, note = ? WHERE id_return = ? AND status = ?")
+                .bind(ReturnStatus::Approved)
+                .bind(refund.minor)
+                .bind(&principal.customer_id)
+                .bind(Utc::now())
+                .bind(&decision.note)
+                .bind(&id_return)
+                .bind(order_return.status)
+                .execute(&mut *conn)
+                .await
+                .map(|result| result.rows_affected() > 0)
+        }
+        ReturnStatus::Rejected => sqlx::query("UPDATE order_returns SET status = ?, decided_by = ?, decided_at = ?, note = ? WHERE id_return = ? AND status = ?")
+            .bind(ReturnStatus::Rejected)
+            .bind(&principal.customer_id)
+            .bind(Utc::now())
+            .bind(&decision.note)
+            .bind(&id_return)
+            .bind(order_return.status)
+            .execute(&mut *conn)
+            .await
+            .map(|result| result.rows_affected() > 0),
+        ReturnStatus::Refunded => {
+            drop(conn);
+            match refund_return(&pool, provider.get_ref(), &order_return, &principal.customer_id, decision.note.as_deref()).await {
+                Ok(moved) => Ok(moved),
+                Err(failure) => return failure.response(),
+            }
+        }
+        ReturnStatus::Requested => Ok(false),
+    };
+
+    match moved {
+        Ok(true) => {
+            println!("📮 Return {} moved from {} to {}", id_return, order_return.status, decision.status);
+            HttpResponse::Ok().body(format!("Return with id {} is now {}", id_return, decision.status))
+        }
+        Ok(false) => HttpResponse::Conflict().body(format!("Return with id {} changed in the meantime", id_return)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
@@ -0,0 +1,98 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Row, SqlitePool};
+use crate::auth::principal::{Credential, Principal};
+use crate::auth::token;
+
+pub const SESSION_TTL_HOURS: i64 = 24;
+
+pub async fn init_sessions_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating sessions table if not exists...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS sessions (
+            token_hash TEXT PRIMARY KEY NOT NULL,
+            customer_id TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            FOREIGN KEY (customer_id) REFERENCES customers(customer_id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ sessions table ready");
+
+    Ok(())
+}
+
+/// Opens a session for the customer and returns the plain token (shown once) and its expiry.
+pub async fn create_session(pool: &SqlitePool, customer_id: &str) -> Result<(String, DateTime<Utc>), sqlx::Error> {
+    let plain_token = token::generate_token();
+    let now = Utc::now();
+    let expires_at = now + Duration::hours(SESSION_TTL_HOURS);
+
+    sqlx::query("INSERT INTO sessions (token_hash, customer_id, created_at, expires_at) VALUES (?, ?, ?, ?)")
+        .bind(token::hash_token(&plain_token))
+        .bind(customer_id)
+        .bind(now)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+
+    Ok((plain_token, expires_at))
+}
+
+/// Looks up a live session by its plain token. Expired sessions are removed on sight.
+pub async fn find_session(pool: &SqlitePool, plain_token: &str) -> Result<Option<Principal>, sqlx::Error> {
+    let token_hash = token::hash_token(plain_token);
+    let row = sqlx::query(
+        r#"
+        SELECT s.customer_id, s.expires_at, c.role, c.artist_id
+        FROM sessions s
+        JOIN customers c ON c.customer_id = s.customer_id
+        WHERE s.token_hash = ?
+        "#
+    )
+        .bind(&token_hash)
+        .fetch_optional(pool)
+        .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let expires_at: DateTime<Utc> = row.get("expires_at");
+    if expires_at <= Utc::now() {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
+            .bind(&token_hash)
+            .execute(pool)
+            .await?;
+        return Ok(None);
+    }
+
+    Ok(Some(Principal {
+        customer_id: row.get("customer_id"),
+        role: row.get("role"),
+        artist_id: row.get("artist_id"),
+        credential: Credential::Session { token_hash },
+    }))
+}
+
+#[post("/logout")]
+pub async fn logout(pool: web::Data<SqlitePool>, principal: Principal) -> impl Responder {
+    let Credential::Session { token_hash } = &principal.credential else {
+        return HttpResponse::BadRequest().body("Only session tokens can be logged out; revoke API keys instead");
+    };
+    match sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
+        .bind(token_hash)
+        .execute(&**pool)
+        .await
+    {
+        Ok(_) => {
+            println!("👋 Customer {} logged out", principal.customer_id);
+            HttpResponse::Ok().body("Logged out successfully")
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
@@ -0,0 +1,537 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use crate::auth::principal::Principal;
+use crate::models::money::Money;
+use crate::models::order::OrderStatus;
+use crate::models::shipping::{
+    Shipment, ShipmentEvent, ShipmentRequest, ShippingKind, ShippingMethod, ShippingOption, ShippingOptionsQuery, TrackingUpdate,
+    VOLUMETRIC_DIVISOR,
+};
+
+pub async fn init_shipping_tables(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating shipping tables if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS shipping_methods (
+            id_shipping_method TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            destination_country TEXT,
+            base_minor INTEGER NOT NULL,
+            per_kg_minor INTEGER NOT NULL DEFAULT 0,
+            currency TEXT NOT NULL,
+            max_weight_kg REAL,
+            max_side_cm REAL,
+            is_active BOOLEAN NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_shipments (
+            id_order TEXT PRIMARY KEY NOT NULL,
+            id_shipping_method TEXT,
+            method_name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            recipient TEXT,
+            street TEXT,
+            city TEXT,
+            postal_code TEXT,
+            country TEXT,
+            phone TEXT,
+            shipping_minor INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            tracking_number TEXT,
+            carrier TEXT,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (id_order) REFERENCES ORDERS(id_order) ON DELETE CASCADE,
+            FOREIGN KEY (id_shipping_method) REFERENCES shipping_methods(id_shipping_method) ON DELETE SET NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS shipment_events (
+            id_shipment_event TEXT PRIMARY KEY NOT NULL,
+            id_order TEXT NOT NULL,
+            status TEXT NOT NULL,
+            location TEXT,
+            tracking_number TEXT,
+            occurred_at TEXT NOT NULL,
+            recorded_by TEXT,
+            FOREIGN KEY (id_order) REFERENCES order_shipments(id_order) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    println!("✅ shipping tables ready");
+
+    Ok(())
+}
+
+/// One order line as the shipping rates see it. Measurements are read from the
+/// catalog and are missing when the artwork has none recorded.
+struct Piece {
+    title: String,
+    amount: i32,
+    currency: String,
+    weight_kg: Option<f64>,
+    sides_cm: Option<[f64; 3]>,
+}
+
+async fn order_pieces(conn: &mut SqliteConnection, id_order: &str) -> Result<Vec<Piece>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, i32, String, Option<f64>, Option<f64>, Option<f64>, Option<f64>)>(
+        r#"
+        SELECT aio.title, aio.amount, aio.currency, a.weight_kg, a.width_cm, a.height_cm, a.depth_cm
+        FROM artworks_in_order aio
+        LEFT JOIN ARTWORKS a ON a.id_artwork = aio.id_artwork
+        WHERE aio.id_order = ?
+        "#
+    )
+    .bind(id_order)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(title, amount, currency, weight_kg, width, height, depth)| Piece {
+            title,
+            amount,
+            currency,
+            weight_kg,
+            sides_cm: match (width, height, depth) {
+                (Some(width), Some(height), Some(depth)) => Some([width, height, depth]),
+                _ => None,
+            },
+        })
+        .collect())
+}
+
+/// Prices a method for the pieces of one order, or explains why it cannot carry them.
+fn quote(method: &ShippingMethod, pieces: &[Piece]) -> Result<Money, String> {
+    let Some(currency) = pieces.first().map(|piece| piece.currency.as_str()) else {
+        return Err("There is nothing in the order to ship".to_string());
+    };
+    if method.base_rate.currency != currency {
+        return Err(format!("{} is priced in {}, the order in {}", method.name, method.base_rate.currency, currency));
+    }
+    if method.kind == ShippingKind::Pickup || !method.needs_measurements() {
+        return Ok(method.base_rate.clone());
+    }
+
+    let mut billable_kg = 0.0;
+    for piece in pieces {
+        if piece.weight_kg.is_none() && piece.sides_cm.is_none() {
+            return Err(format!("{} has no weight or dimensions recorded", piece.title));
+        }
+        if let (Some(max), Some(weight)) = (method.max_weight_kg, piece.weight_kg) {
+            if weight > max {
+                return Err(format!("{} is heavier than {} kg", piece.title, max));
+            }
+        }
+        if let (Some(max), Some(sides)) = (method.max_side_cm, piece.sides_cm) {
+            if sides.iter().any(|side| *side > max) {
+                return Err(format!("{} has a side longer than {} cm", piece.title, max));
+            }
+        }
+        let volumetric_kg = piece.sides_cm.map_or(0.0, |[width, height, depth]| width * height * depth / VOLUMETRIC_DIVISOR);
+        billable_kg += piece.weight_kg.unwrap_or(0.0).max(volumetric_kg) * f64::from(piece.amount);
+    }
+
+    // Carriers bill every started kilogram.
+    method
+        .per_kg_rate
+        .checked_mul(billable_kg.ceil() as i64)
+        .and_then(|weight_charge| method.base_rate.checked_add(&weight_charge))
+        .map_err(|e| e.to_string())
+}
+
+pub async fn fetch_shipment(conn: &mut SqliteConnection, id_order: &str) -> Result<Option<Shipment>, sqlx::Error> {
+    sqlx::query_as::<_, Shipment>("SELECT * FROM order_shipments WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+/// Chooses how an order is delivered and fixes its shipping cost. The inner
+/// `Err` explains to the customer why the choice was refused.
+pub async fn set_shipment(conn: &mut SqliteConnection, id_order: &str, request: &ShipmentRequest) -> Result<Result<Shipment, String>, sqlx::Error> {
+    let Some(method) = sqlx::query_as::<_, ShippingMethod>("SELECT * FROM shipping_methods WHERE id_shipping_method = ? AND is_active = 1")
+        .bind(&request.id_shipping_method)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(Err(format!("Shipping method {} not found", request.id_shipping_method)));
+    };
+
+    // Pickups happen at the gallery, so any address sent along is not kept.
+    let address = match (method.kind, &request.address) {
+        (ShippingKind::Pickup, _) => None,
+        (_, None) => return Ok(Err(format!("{} needs a shipping address", method.name))),
+        (_, Some(address)) => {
+            let mut address = address.clone();
+            address.country = address.country.trim().to_uppercase();
+            if let Err(reason) = address.validate() {
+                return Ok(Err(reason));
+            }
+            if method.destination_country.as_ref().is_some_and(|country| country != &address.country) {
+                return Ok(Err(format!("{} does not deliver to {}", method.name, address.country)));
+            }
+            Some(address)
+        }
+    };
+    let cost = match quote(&method, &order_pieces(conn, id_order).await?) {
+        Ok(cost) => cost,
+        Err(reason) => return Ok(Err(reason)),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO order_shipments (
+            id_order, id_shipping_method, method_name, kind, recipient, street, city, postal_code, country, phone,
+            shipping_minor, currency, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (id_order) DO UPDATE SET
+            id_shipping_method = excluded.id_shipping_method, method_name = excluded.method_name, kind = excluded.kind,
+            recipient = excluded.recipient, street = excluded.street, city = excluded.city, postal_code = excluded.postal_code,
+            country = excluded.country, phone = excluded.phone, shipping_minor = excluded.shipping_minor,
+            currency = excluded.currency, updated_at = excluded.updated_at
+        "#
+    )
+    .bind(id_order)
+    .bind(&method.id_shipping_method)
+    .bind(&method.name)
+    .bind(method.kind)
+    .bind(address.as_ref().map(|a| &a.recipient))
+    .bind(address.as_ref().map(|a| &a.street))
+    .bind(address.as_ref().map(|a| &a.city))
+    .bind(address.as_ref().and_then(|a| a.postal_code.as_ref()))
+    .bind(address.as_ref().map(|a| &a.country))
+    .bind(address.as_ref().and_then(|a| a.phone.as_ref()))
+    .bind(cost.minor)
+    .bind(&cost.currency)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    match fetch_shipment(conn, id_order).await? {
+        Some(shipment) => Ok(Ok(shipment)),
+        None => Err(sqlx::Error::RowNotFound),
+    }
+}
+
+/// The order's owner and status, or `None` when the caller may not see it.
+async fn visible_order(pool: &SqlitePool, principal: &Principal, id_order: &str) -> Result<Option<OrderStatus>, sqlx::Error> {
+    let order = sqlx::query_as::<_, (String, OrderStatus)>("SELECT id_customer, status FROM ORDERS WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_optional(pool)
+        .await?;
+    Ok(order.filter(|(id_customer, _)| principal.can_access_customer(id_customer)).map(|(_, status)| status))
+}
+
+#[get("/{id_order}/shipping-options")]
+pub async fn get_shipping_options(
+    pool: web::Data<SqlitePool>,
+    principal: Principal,
+    path: web::Path<String>,
+    query: web::Query<ShippingOptionsQuery>,
+) -> impl Responder {
+    let id_order = path.into_inner();
+    match visible_order(&pool, &principal, &id_order).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+    let country = query.country.trim().to_uppercase();
+
+    let loaded = async {
+        let mut conn = pool.acquire().await?;
+        let pieces = order_pieces(&mut conn, &id_order).await?;
+        let methods = sqlx::query_as::<_, ShippingMethod>(
+            "SELECT * FROM shipping_methods WHERE is_active = 1 AND (kind = 'pickup' OR destination_country IS NULL OR destination_country = ?) ORDER BY base_minor",
+        )
+        .bind(&country)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok::<_, sqlx::Error>((pieces, methods))
+    }
+    .await;
+    let (pieces, methods) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    };
+
+    let options: Vec<ShippingOption> = methods
+        .into_iter()
+        .map(|method| {
+            let quoted = quote(&method, &pieces);
+            ShippingOption {
+                id_shipping_method: method.id_shipping_method,
+                name: method.name,
+                kind: method.kind,
+                cost: quoted.as_ref().ok().cloned(),
+                unavailable_reason: quoted.err(),
+            }
+        })
+        .collect();
+    HttpResponse::Ok().json(options)
+}
+
+#[put("/{id_order}/shipment")]
+pub async fn put_order_shipment(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>, request: web::Json<ShipmentRequest>) -> impl Responder {
+    let id_order = path.into_inner();
+    match visible_order(&pool, &principal, &id_order).await {
+        Ok(Some(OrderStatus::Pending)) => {}
+        Ok(Some(status)) => return HttpResponse::Conflict().body(format!("Shipping can only be changed on pending orders; this one is {}", status)),
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+        // The amount being charged includes shipping, so it is fixed once a payment is under way.
+        let paying = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM payments WHERE id_order = ? AND status IN ('pending', 'authorized', 'captured'))",
+        )
+        .bind(&id_order)
+        .fetch_one(&mut *tx)
+        .await?;
+        if paying {
+            return Ok(Err("A payment for this order is already under way".to_string()));
+        }
+        let shipment = set_shipment(&mut tx, &id_order, &request).await?;
+        if shipment.is_ok() {
+            tx.commit().await?;
+        }
+        Ok::<_, sqlx::Error>(shipment)
+    }
+    .await;
+
+    match result {
+        Ok(Ok(shipment)) => HttpResponse::Ok().json(shipment),
+        Ok(Err(reason)) => HttpResponse::BadRequest().body(reason),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/{id_order}/shipment")]
+pub async fn get_order_shipment(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>) -> impl Responder {
+    let id_order = path.into_inner();
+    match visible_order(&pool, &principal, &id_order).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    let loaded = async {
+        let mut conn = pool.acquire().await?;
+        let Some(shipment) = fetch_shipment(&mut conn, &id_order).await? else {
+            return Ok(None);
+        };
+        let events = sqlx::query_as::<_, ShipmentEvent>("SELECT * FROM shipment_events WHERE id_order = ? ORDER BY occurred_at")
+            .bind(&id_order)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok::<_, sqlx::Error>(Some((shipment, events)))
+    }
+    .await;
+
+    match loaded {
+        Ok(Some((shipment, events))) => {
+            let mut body = json!(shipment);
+            body["events"] = json!(events);
+            HttpResponse::Ok().json(body)
+        }
+        Ok(None) => HttpResponse::NotFound().body(format!("Order {} has no shipping chosen", id_order)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[post("/{id_order}/shipment/events")]
+pub async fn add_tracking_event(pool: web::Data<SqlitePool>, principal: Principal, path: web::Path<String>, update: web::Json<TrackingUpdate>) -> impl Responder {
+    let id_order = path.into_inner();
+    if update.status.trim().is_empty() {
+        return HttpResponse::BadRequest().body("A tracking update needs a status");
+    }
+    match visible_order(&pool, &principal, &id_order).await {
+        Ok(Some(OrderStatus::Pending | OrderStatus::Cancelled)) => {
+            return HttpResponse::Conflict().body("Only paid orders are shipped")
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Order not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+
+    let event = ShipmentEvent {
+        id_shipment_event: Uuid::new_v4().to_string(),
+        id_order: id_order.clone(),
+        status: update.status.trim().to_string(),
+        location: update.location.clone(),
+        tracking_number: None,
+        occurred_at: Utc::now(),
+        recorded_by: Some(principal.customer_id.clone()),
+    };
+    let result = async {
+        let mut tx = pool.begin().await?;
+        let tracking_number = sqlx::query_scalar::<_, Option<String>>(
+            r#"
+            UPDATE order_shipments
+            SET tracking_number = COALESCE(?, tracking_number), carrier = COALESCE(?, carrier), updated_at = ?
+            WHERE id_order = ?
+            RETURNING tracking_number
+            "#
+        )
+        .bind(&update.tracking_number)
+        .bind(&update.carrier)
+        .bind(event.occurred_at)
+        .bind(&id_order)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(tracking_number) = tracking_number else {
+            return Ok(None);
+        };
+        let event = ShipmentEvent { tracking_number, ..event };
+        sqlx::query(
+            "INSERT INTO shipment_events (id_shipment_event, id_order, status, location, tracking_number, occurred_at, recorded_by) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&event.id_shipment_event)
+        .bind(&event.id_order)
+        .bind(&event.status)
+        .bind(&event.location)
+        .bind(&event.tracking_number)
+        .bind(event.occurred_at)
+        .bind(&event.recorded_by)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Some(event))
+    }
+    .await;
+
+    match result {
+        Ok(Some(event)) => {
+            println!("🚚 Order {} shipment: {}", event.id_order, event.status);
+            HttpResponse::Created().json(event)
+        }
+        Ok(None) => HttpResponse::NotFound().body(format!("Order {} has no shipping chosen", id_order)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[get("/")]
+pub async fn get_shipping_methods(pool: web::Data<SqlitePool>) -> impl Responder {
+    match sqlx::query_as::<_, ShippingMethod>("SELECT * FROM shipping_methods ORDER BY kind, name")
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(methods) => HttpResponse::Ok().json(methods),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Rejects a method that does not make sense, after normalizing its country code.
+fn checked_method(method: web::Json<ShippingMethod>) -> Result<ShippingMethod, HttpResponse> {
+    let mut method = method.into_inner();
+    method.destination_country = method.destination_country.map(|country| country.trim().to_uppercase());
+    method.validate().map_err(|reason| HttpResponse::BadRequest().body(reason))?;
+    Ok(method)
+}
+
+#[post("/")]
+pub async fn create_shipping_method(pool: web::Data<SqlitePool>, method: web::Json<ShippingMethod>) -> impl Responder {
+    let mut method = match checked_method(method) {
+        Ok(method) => method,
+        Err(refused) => return refused,
+    };
+    method.id_shipping_method = Uuid::new_v4().to_string();
+
+    match sqlx::query(
+        r#"
+        INSERT INTO shipping_methods (
+            id_shipping_method, name, kind, destination_country, base_minor, per_kg_minor, currency,
+            max_weight_kg, max_side_cm, is_active, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&method.id_shipping_method)
+    .bind(&method.name)
+    .bind(method.kind)
+    .bind(&method.destination_country)
+    .bind(method.base_rate.minor)
+    .bind(method.per_kg_rate.minor)
+    .bind(&method.base_rate.currency)
+    .bind(method.max_weight_kg)
+    .bind(method.max_side_cm)
+    .bind(method.is_active)
+    .bind(Utc::now())
+    .execute(&**pool)
+    .await
+    {
+        Ok(_) => {
+            println!("🚚 Shipping method {} created", method.name);
+            HttpResponse::Created().json(method)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[put("/{id}")]
+pub async fn update_shipping_method(pool: web::Data<SqlitePool>, path: web::Path<String>, method: web::Json<ShippingMethod>) -> impl Responder {
+    let mut method = match checked_method(method) {
+        Ok(method) => method,
+        Err(refused) => return refused,
+    };
+    method.id_shipping_method = path.into_inner();
+
+    // Orders keep the cost they were quoted; only new choices see the change.
+    match sqlx::query(
+        r#"
+        UPDATE shipping_methods SET
+            name = ?, kind = ?, destination_country = ?, base_minor = ?, per_kg_minor = ?, currency = ?,
+            max_weight_kg = ?, max_side_cm = ?, is_active = ?
+        WHERE id_shipping_method = ?
+        "#
+    )
+    .bind(&method.name)
+    .bind(method.kind)
+    .bind(&method.destination_country)
+    .bind(method.base_rate.minor)
+    .bind(method.per_kg_rate.minor)
+    .bind(&method.base_rate.currency)
+    .bind(method.max_weight_kg)
+    .bind(method.max_side_cm)
+    .bind(method.is_active)
+    .bind(&method.id_shipping_method)
+    .execute(&**pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() > 0 => HttpResponse::Ok().json(method),
+        Ok(_) => HttpResponse::NotFound().body(format!("Shipping method with id {} not found", method.id_shipping_method)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[delete("/{id}")]
+pub async fn delete_shipping_method(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+    let id_shipping_method = path.into_inner();
+    // Shipments already chosen keep the method's name, kind and cost.
+    match sqlx::query("DELETE FROM shipping_methods WHERE id_shipping_method = ?")
+        .bind(&id_shipping_method)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            HttpResponse::Ok().body(format!("Shipping method with id {} deleted successfully", id_shipping_method))
+        }
+        Ok(_) => HttpResponse::NotFound().body(format!("Shipping method with id {} not found", id_shipping_method)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
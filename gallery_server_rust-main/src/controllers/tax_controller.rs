@@ -0,0 +1,206 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use sqlx::{sqlite::SqliteConnection, SqlitePool};
+use uuid::Uuid;
+use crate::models::tax::{LineTax, TaxRate};
+
+/// Israeli VAT, seeded as the default rate on a fresh database.
+const DEFAULT_VAT_BASIS_POINTS: i64 = 1800;
+
+pub async fn init_tax_rates_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📋 Creating tax_rates table if not exist...");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tax_rates (
+            id_tax_rate TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            rate_basis_points INTEGER NOT NULL,
+            is_default BOOLEAN NOT NULL DEFAULT 0
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_tax_rates_default ON tax_rates (is_default) WHERE is_default = 1")
+        .execute(pool)
+        .await?;
+    let seeded = sqlx::query(
+        "INSERT INTO tax_rates (id_tax_rate, name, rate_basis_points, is_default) SELECT ?, 'VAT', ?, 1 WHERE NOT EXISTS (SELECT 1 FROM tax_rates)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(DEFAULT_VAT_BASIS_POINTS)
+    .execute(pool)
+    .await?;
+    if seeded.rows_affected() > 0 {
+        println!("🧾 Seeded the default VAT rate");
+    }
+    println!("✅ tax_rates table ready");
+
+    Ok(())
+}
+
+/// Copies the artwork's tax rate (or the default rate) and whether its price
+/// includes tax onto an order line, then prices the line with them.
+pub async fn snapshot_line_tax(conn: &mut SqliteConnection, id_artwork_in_order: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE artworks_in_order SET
+            tax_rate_basis_points = COALESCE(
+                (SELECT t.rate_basis_points FROM ARTWORKS a JOIN tax_rates t ON t.id_tax_rate = a.id_tax_rate WHERE a.id_artwork = artworks_in_order.id_artwork),
+                (SELECT rate_basis_points FROM tax_rates WHERE is_default = 1),
+                0
+            ),
+            price_includes_tax = COALESCE((SELECT a.price_includes_tax FROM ARTWORKS a WHERE a.id_artwork = artworks_in_order.id_artwork), 1)
+        WHERE id_artwork_in_order = ?
+        "#
+    )
+    .bind(id_artwork_in_order)
+    .execute(&mut *conn)
+    .await?;
+    recompute_line_tax(conn, id_artwork_in_order).await
+}
+
+/// Recomputes the tax and total of one order line from its snapshot, its
+/// discount and whether the order was placed tax exempt.
+pub async fn recompute_line_tax(conn: &mut SqliteConnection, id_artwork_in_order: &str) -> Result<(), sqlx::Error> {
+    let (price_minor, amount, discount_minor, rate_basis_points, price_includes_tax, tax_exempt) =
+        sqlx::query_as::<_, (i64, i32, i64, i64, bool, bool)>(
+            r#"
+            SELECT aio.price_minor, aio.amount, aio.discount_minor, aio.tax_rate_basis_points, aio.price_includes_tax, o.tax_exempt
+            FROM artworks_in_order aio
+            JOIN ORDERS o ON o.id_order = aio.id_order
+            WHERE aio.id_artwork_in_order = ?
+            "#
+        )
+        .bind(id_artwork_in_order)
+        .fetch_one(&mut *conn)
+        .await?;
+    // A discount never takes a line below nothing.
+    let base_minor = (price_minor * i64::from(amount) - discount_minor).max(0);
+    let line = LineTax::compute(base_minor, rate_basis_points, price_includes_tax, tax_exempt);
+    sqlx::query("UPDATE artworks_in_order SET tax_minor = ?, total_minor = ? WHERE id_artwork_in_order = ?")
+        .bind(line.tax_minor)
+        .bind(line.total_minor)
+        .bind(id_artwork_in_order)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn recompute_order_tax(conn: &mut SqliteConnection, id_order: &str) -> Result<(), sqlx::Error> {
+    let lines = sqlx::query_scalar::<_, String>("SELECT id_artwork_in_order FROM artworks_in_order WHERE id_order = ?")
+        .bind(id_order)
+        .fetch_all(&mut *conn)
+        .await?;
+    for id_artwork_in_order in lines {
+        recompute_line_tax(conn, &id_artwork_in_order).await?;
+    }
+    Ok(())
+}
+
+#[get("/")]
+pub async fn get_tax_rates(pool: web::Data<SqlitePool>) -> impl Responder {
+    match sqlx::query_as::<_, TaxRate>("SELECT * FROM tax_rates ORDER BY is_default DESC, name")
+        .fetch_all(&**pool)
+        .await
+    {
+        Ok(rates) => HttpResponse::Ok().json(rates),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+/// Writes a rate, first taking the default flag off the others when it becomes the default.
+async fn save_tax_rate(pool: &SqlitePool, rate: &TaxRate, insert: bool) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    if rate.is_default {
+        sqlx::query("UPDATE tax_rates SET is_default = 0 WHERE id_tax_rate <> ?")
+            .bind(&rate.id_tax_rate)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let query = if insert {
+        "INSERT INTO tax_rates (name, rate_basis_points, is_default, id_tax_rate) VALUES (?, ?, ?, ?)"
+    } else {
+        "UPDATE tax_rates SET name = ?, rate_basis_points = ?, is_default = ? WHERE id_tax_rate = ?"
+    };
+    let result = sqlx::query(query)
+        .bind(&rate.name)
+        .bind(rate.rate_basis_points)
+        .bind(rate.is_default)
+        .bind(&rate.id_tax_rate)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+#[post("/")]
+pub async fn create_tax_rate(pool: web::Data<SqlitePool>, rate: web::Json<TaxRate>) -> impl Responder {
+    let mut rate = rate.into_inner();
+    if let Err(reason) = rate.validate() {
+        return HttpResponse::BadRequest().body(reason);
+    }
+    rate.id_tax_rate = Uuid::new_v4().to_string();
+
+    match save_tax_rate(&pool, &rate, true).await {
+        Ok(_) => {
+            println!("🧾 Tax rate {} ({} bp) created", rate.name, rate.rate_basis_points);
+            HttpResponse::Created().json(rate)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[put("/{id}")]
+pub async fn update_tax_rate(pool: web::Data<SqlitePool>, path: web::Path<String>, rate: web::Json<TaxRate>) -> impl Responder {
+    let mut rate = rate.into_inner();
+    if let Err(reason) = rate.validate() {
+        return HttpResponse::BadRequest().body(reason);
+    }
+    rate.id_tax_rate = path.into_inner();
+
+    // Unsetting the default would leave untagged artworks without a rate; pick another default instead.
+    if !rate.is_default {
+        match sqlx::query_scalar::<_, bool>("SELECT is_default FROM tax_rates WHERE id_tax_rate = ?")
+            .bind(&rate.id_tax_rate)
+            .fetch_optional(&**pool)
+            .await
+        {
+            Ok(Some(true)) => return HttpResponse::Conflict().body("Make another rate the default instead"),
+            Ok(_) => {}
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+        }
+    }
+
+    // Orders keep the rate they were placed with; only future order lines see the change.
+    match save_tax_rate(&pool, &rate, false).await {
+        Ok(true) => HttpResponse::Ok().json(rate),
+        Ok(false) => HttpResponse::NotFound().body(format!("Tax rate with id {} not found", rate.id_tax_rate)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
+
+#[delete("/{id}")]
+pub async fn delete_tax_rate(pool: web::Data<SqlitePool>, path: web::Path<String>) -> impl Responder {
+    let id_tax_rate = path.into_inner();
+    // Artworks on a deleted rate fall back to the default one.
+    match sqlx::query("DELETE FROM tax_rates WHERE id_tax_rate = ? AND is_default = 0")
+        .bind(&id_tax_rate)
+        .execute(&**pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => HttpResponse::Ok().body(format!("Tax rate with id {} deleted successfully", id_tax_rate)),
+        Ok(_) => match sqlx::query_scalar::<_, bool>("SELECT is_default FROM tax_rates WHERE id_tax_rate = ?")
+            .bind(&id_tax_rate)
+            .fetch_optional(&**pool)
+            .await
+        {
+            Ok(Some(_)) => HttpResponse::Conflict().body("The default tax rate cannot be deleted"),
+            Ok(None) => HttpResponse::NotFound().body(format!("Tax rate with id {} not found", id_tax_rate)),
+            Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+    }
+}
@@ -0,0 +1,119 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    middleware::Next,
+    web, Error, HttpMessage, HttpResponse,
+};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use crate::auth::principal::Principal;
+use crate::controllers::idempotency_controller::{self, Reservation};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Hashes what makes two requests "the same": method, path with query, and body.
+fn fingerprint(req: &ServiceRequest, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(b"\n");
+    hasher.update(req.uri().path_and_query().map(|p| p.as_str()).unwrap_or_else(|| req.path()));
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+fn no_store(res: &HttpResponse<impl MessageBody>) -> bool {
+    res.headers()
+        .get(header::CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("no-store"))
+}
+
+/// Makes POST requests safe to retry. A signed-in caller may send an
+/// `Idempotency-Key` header; the first request with a key runs normally and
+/// its response is kept for `IDEMPOTENCY_RETENTION_HOURS`. Repeats with the same
+/// key and the same request get that response back without running again; a
+/// repeat with a different request is rejected. Keys belong to one customer.
+///
+/// Server errors are not kept, so the retry runs again, and neither are
+/// responses marked `Cache-Control: no-store`, such as a newly created API key.
+pub async fn idempotency_keys(mut req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) if req.method() == Method::POST => value.to_str().map(|key| key.trim().to_string()),
+        _ => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    };
+    // Anonymous requests (sign-up, login, provider webhooks) are not tracked.
+    let Some(principal) = req.extensions().get::<Principal>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+    let key = match key {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+        _ => {
+            let message = format!("{} must be 1 to {} visible characters", IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH);
+            return Ok(req.into_response(HttpResponse::BadRequest().body(message)));
+        }
+    };
+    let pool = match req.app_data::<web::Data<SqlitePool>>() {
+        Some(pool) => pool.clone(),
+        None => return Ok(req.into_response(HttpResponse::InternalServerError().body("Database pool not configured"))),
+    };
+
+    // Read the body to fingerprint it, then hand it back for the handler.
+    let request_body = req.extract::<web::Bytes>().await?;
+    let fingerprint = fingerprint(&req, &request_body);
+    req.set_payload(request_body.into());
+
+    match idempotency_controller::reserve_key(&pool, &principal.customer_id, &key, &fingerprint).await {
+        Ok(Reservation::Reserved) => {}
+        Ok(Reservation::Existing(stored)) if stored.fingerprint != fingerprint => {
+            return Ok(req.into_response(
+                HttpResponse::UnprocessableEntity().body(format!("{} was already used for a different request", IDEMPOTENCY_KEY_HEADER)),
+            ))
+        }
+        Ok(Reservation::Existing(stored)) => {
+            let response = stored.replay().unwrap_or_else(|| {
+                HttpResponse::Conflict().body(format!("A request with this {} is still being processed", IDEMPOTENCY_KEY_HEADER))
+            });
+            return Ok(req.into_response(response));
+        }
+        Err(e) => return Ok(req.into_response(HttpResponse::InternalServerError().body(format!("Database error: {}", e)))),
+    }
+
+    let release = |pool: web::Data<SqlitePool>| {
+        let (customer_id, key) = (principal.customer_id.clone(), key.clone());
+        async move {
+            if let Err(e) = idempotency_controller::release_key(&pool, &customer_id, &key).await {
+                eprintln!("Failed to release idempotency key {}: {}", key, e);
+            }
+        }
+    };
+
+    let (req, res) = match next.call(req).await {
+        Ok(res) => res.into_parts(),
+        Err(e) => {
+            release(pool).await;
+            return Err(e);
+        }
+    };
+    if res.status().is_server_error() || no_store(&res) {
+        release(pool).await;
+        return Ok(ServiceResponse::new(req, res).map_into_boxed_body());
+    }
+
+    let status = res.status();
+    let content_type = res.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let (res, response_body) = res.into_parts();
+    let Ok(response_body) = body::to_bytes(response_body).await else {
+        release(pool).await;
+        return Ok(ServiceResponse::new(req, HttpResponse::InternalServerError().body("Failed to read the response")));
+    };
+    if let Err(e) =
+        idempotency_controller::store_response(&pool, &principal.customer_id, &key, status, content_type.as_deref(), &response_body).await
+    {
+        eprintln!("Failed to store the response for idempotency key {}: {}", key, e);
+        release(pool).await;
+    }
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(response_body))))
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::money::Money;
+use crate::models::payment::Payment;
+
+mod pdf;
+
+pub use pdf::render_invoice;
+
+/// The seller block printed on every invoice, configured through the environment.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GalleryDetails {
+    pub name: String,
+    pub address: Option<String>,
+    /// The gallery's VAT registration number (עוסק מורשה).
+    pub vat_number: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+impl GalleryDetails {
+    pub fn from_env() -> Self {
+        let optional = |key: &str| std::env::var(key).ok().filter(|value| !value.trim().is_empty());
+        GalleryDetails {
+            name: optional("GALLERY_NAME").unwrap_or_else(|| "Gallery".to_string()),
+            address: optional("GALLERY_ADDRESS"),
+            vat_number: optional("GALLERY_VAT_NUMBER"),
+            phone: optional("GALLERY_PHONE"),
+            email: optional("GALLERY_EMAIL"),
+        }
+    }
+}
+
+// The parts of an order from `fetch_detailed_orders` an invoice prints, so the
+// invoice agrees with what `/orders/detailed` showed when it was issued.
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceOrder {
+    pub id_order: String,
+    pub order_date: String,
+    pub discount_code: Option<String>,
+    pub tax_exempt: bool,
+    pub shipping: Option<InvoiceShipping>,
+    pub customer: InvoiceCustomer,
+    pub artworks: Vec<InvoiceLine>,
+    pub subtotal_amount: Money,
+    pub discount_amount: Money,
+    pub tax_amount: Money,
+    pub shipping_amount: Money,
+    pub total_amount: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceCustomer {
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub phone: String,
+    pub address: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceShipping {
+    pub method: String,
+    pub address: Option<InvoiceAddress>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceAddress {
+    pub recipient: Option<String>,
+    pub street: Option<String>,
+    pub city: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub artwork_title: String,
+    pub artist_name: String,
+    pub amount: i32,
+    pub artwork_price: Money,
+    pub discount_amount: Money,
+    pub tax_rate_basis_points: i64,
+    pub price_includes_tax: bool,
+    pub tax_amount: Money,
+    pub line_total: Money,
+}
+
+/// Everything an invoice prints, fixed when it is first issued and stored with
+/// its number, so a reissued copy always matches the original.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceDocument {
+    pub invoice_number: i64,
+    pub issued_at: DateTime<Utc>,
+    pub gallery: GalleryDetails,
+    pub order: InvoiceOrder,
+    pub tax_exempt_reason: Option<String>,
+    /// The payment that settled the order, printed as the receipt part.
+    pub payment: Option<Payment>,
+}
+
+impl InvoiceDocument {
+    /// Invoice numbers are printed zero-padded, e.g. 000042.
+    pub fn number(&self) -> String {
+        format!("{:06}", self.invoice_number)
+    }
+}
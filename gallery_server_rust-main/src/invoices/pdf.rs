@@ -0,0 +1,385 @@
+use printpdf::{Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point, Rgb};
+use ttf_parser::Face;
+use unicode_bidi::BidiInfo;
+use super::{InvoiceDocument, InvoiceLine};
+use crate::models::money::Money;
+
+// A4 portrait, in millimetres.
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 18.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const PT_TO_MM: f32 = 0.352_778;
+
+// DejaVu Sans covers Latin and Hebrew; see assets/fonts/LICENSE.
+static REGULAR_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+static BOLD_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// Line item columns as (heading, width); together they span the content width.
+const COLUMNS: [(&str, f32); 7] = [
+    ("Item", 58.0),
+    ("Qty", 10.0),
+    ("Unit price", 24.0),
+    ("Discount", 20.0),
+    ("VAT rate", 18.0),
+    ("VAT", 20.0),
+    ("Total", 24.0),
+];
+
+#[derive(Clone, Copy)]
+struct Style {
+    size: f32,
+    bold: bool,
+    muted: bool,
+}
+
+const TITLE: Style = Style { size: 16.0, bold: true, muted: false };
+const HEADING: Style = Style { size: 11.0, bold: true, muted: false };
+const LABEL: Style = Style { size: 9.0, bold: true, muted: false };
+const BODY: Style = Style { size: 9.0, bold: false, muted: false };
+const SMALL: Style = Style { size: 8.0, bold: false, muted: true };
+
+/// Where text sits in its box. `Start` is the left edge for left-to-right text
+/// and the right edge for Hebrew; `End` is the opposite edge.
+#[derive(Clone, Copy)]
+enum Align {
+    Start,
+    End,
+}
+
+struct Font {
+    face: Face<'static>,
+    pdf: IndirectFontRef,
+}
+
+impl Font {
+    fn load(doc: &PdfDocumentReference, data: &'static [u8]) -> Result<Font, printpdf::Error> {
+        let face = Face::parse(data, 0).expect("bundled fonts are valid TrueType");
+        Ok(Font { face, pdf: doc.add_external_font(data)? })
+    }
+
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units: u32 = text
+            .chars()
+            .filter_map(|c| self.face.glyph_index(c).and_then(|glyph| self.face.glyph_hor_advance(glyph)))
+            .map(u32::from)
+            .sum();
+        units as f32 / f32::from(self.face.units_per_em()) * size * PT_TO_MM
+    }
+}
+
+/// Puts text in the left-to-right order a PDF draws glyphs in: runs of Hebrew
+/// are reversed and their brackets mirrored. Also says whether the text as a
+/// whole reads right to left.
+fn visual_order(text: &str) -> (String, bool) {
+    let bidi = BidiInfo::new(text, None);
+    let Some(paragraph) = bidi.paragraphs.first() else {
+        return (String::new(), false);
+    };
+    let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+    let mut visual = String::with_capacity(text.len());
+    for run in runs {
+        let slice = &text[run.clone()];
+        if levels[run.start].is_rtl() {
+            visual.extend(slice.chars().rev().map(mirror));
+        } else {
+            visual.push_str(slice);
+        }
+    }
+    (visual, paragraph.level.is_rtl())
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+/// Collapses line breaks and runs of spaces, then cuts the text with an
+/// ellipsis if it is still wider than `width`.
+fn fit(font: &Font, text: &str, size: f32, width: f32) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if font.width(&text, size) <= width {
+        return text;
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while chars.pop().is_some() {
+        let shortened = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if font.width(&shortened, size) <= width {
+            return shortened;
+        }
+    }
+    String::new()
+}
+
+/// The page being drawn on, with a cursor that moves down as lines are written.
+struct Canvas {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: Font,
+    bold: Font,
+    footer: String,
+    pages: usize,
+    /// Baseline of the next line, in mm from the bottom of the page.
+    y: f32,
+}
+
+impl Canvas {
+    fn new(title: &str, footer: String) -> Result<Canvas, printpdf::Error> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Page 1");
+        let layer = doc.get_page(page).get_layer(layer);
+        let regular = Font::load(&doc, REGULAR_FONT)?;
+        let bold = Font::load(&doc, BOLD_FONT)?;
+        let canvas = Canvas { doc, layer, regular, bold, footer, pages: 1, y: PAGE_HEIGHT - MARGIN };
+        canvas.draw_footer();
+        Ok(canvas)
+    }
+
+    fn draw_footer(&self) {
+        let y = MARGIN / 2.0;
+        self.rule(y + 4.0);
+        self.text_at(&format!("{} — page {}", self.footer, self.pages), MARGIN, y, CONTENT_WIDTH, SMALL, Align::End);
+    }
+
+    /// Starts a new page when fewer than `height` mm are left above the footer.
+    /// Returns whether it did.
+    fn reserve(&mut self, height: f32) -> bool {
+        if self.y - height >= MARGIN {
+            return false;
+        }
+        self.pages += 1;
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), format!("Page {}", self.pages));
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+        self.draw_footer();
+        true
+    }
+
+    fn down(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn text(&self, text: &str, x: f32, width: f32, style: Style, align: Align) {
+        self.text_at(text, x, self.y, width, style, align);
+    }
+
+    fn text_at(&self, text: &str, x: f32, y: f32, width: f32, style: Style, align: Align) {
+        let font = if style.bold { &self.bold } else { &self.regular };
+        let (visual, rtl) = visual_order(&fit(font, text, style.size, width));
+        if visual.is_empty() {
+            return;
+        }
+        let flush_right = matches!((align, rtl), (Align::Start, true) | (Align::End, false));
+        let left = if flush_right { x + width - font.width(&visual, style.size) } else { x };
+        let shade = if style.muted { 0.4 } else { 0.0 };
+        self.layer.set_fill_color(Color::Rgb(Rgb::new(shade, shade, shade, None)));
+        self.layer.use_text(visual, style.size, Mm(left), Mm(y), &font.pdf);
+    }
+
+    /// A hairline across the content width at height `y`.
+    fn rule(&self, y: f32) {
+        self.layer.set_outline_color(Color::Rgb(Rgb::new(0.6, 0.6, 0.6, None)));
+        self.layer.set_outline_thickness(0.5);
+        self.layer.add_line(Line {
+            points: vec![(Point::new(Mm(MARGIN), Mm(y)), false), (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false)],
+            is_closed: false,
+        });
+    }
+
+    /// Writes lines one under the other in a column, skipping empty ones.
+    fn block(&mut self, x: f32, width: f32, heading: &str, lines: &[String]) {
+        self.text(heading, x, width, LABEL, Align::Start);
+        self.down(4.5);
+        for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+            self.text(line, x, width, BODY, Align::Start);
+            self.down(4.2);
+        }
+    }
+}
+
+fn rate(line: &InvoiceLine, tax_exempt: bool) -> String {
+    if tax_exempt {
+        return "exempt".to_string();
+    }
+    let percent = format!("{}%", line.tax_rate_basis_points as f64 / 100.0);
+    if line.price_includes_tax { format!("{} incl.", percent) } else { percent }
+}
+
+fn negated(amount: &Money) -> String {
+    if amount.minor == 0 { amount.amount() } else { format!("-{}", amount.amount()) }
+}
+
+fn table_header(canvas: &mut Canvas) {
+    let mut x = MARGIN;
+    for (index, (heading, width)) in COLUMNS.iter().enumerate() {
+        let align = if index == 0 { Align::Start } else { Align::End };
+        canvas.text(heading, x, *width, LABEL, align);
+        x += width;
+    }
+    canvas.rule(canvas.y - 2.0);
+    canvas.down(7.0);
+}
+
+fn table_row(canvas: &mut Canvas, line: &InvoiceLine, tax_exempt: bool) {
+    let cells = [
+        line.amount.to_string(),
+        line.artwork_price.amount(),
+        negated(&line.discount_amount),
+        rate(line, tax_exempt),
+        line.tax_amount.amount(),
+        line.line_total.amount(),
+    ];
+    let (_, item_width) = COLUMNS[0];
+    canvas.text(&line.artwork_title, MARGIN, item_width - 2.0, BODY, Align::Start);
+    let mut x = MARGIN + item_width;
+    for (cell, (_, width)) in cells.iter().zip(&COLUMNS[1..]) {
+        canvas.text(cell, x, *width, BODY, Align::End);
+        x += width;
+    }
+    canvas.down(4.0);
+    canvas.text(&line.artist_name, MARGIN, item_width - 2.0, SMALL, Align::Start);
+    canvas.down(6.0);
+}
+
+/// Lays out the invoice on as many A4 pages as its lines need and returns the PDF.
+pub fn render_invoice(invoice: &InvoiceDocument) -> Result<Vec<u8>, printpdf::Error> {
+    let number = invoice.number();
+    let order = &invoice.order;
+    let gallery = &invoice.gallery;
+    let mut canvas = Canvas::new(&format!("Invoice {}", number), format!("{} · Invoice {}", gallery.name, number))?;
+    let half = CONTENT_WIDTH / 2.0;
+    let right = MARGIN + half;
+
+    // Seller on the left, document title and numbers on the right.
+    let top = canvas.y;
+    canvas.text(&gallery.name, MARGIN, half, TITLE, Align::Start);
+    canvas.down(7.0);
+    let seller = [
+        gallery.address.clone().unwrap_or_default(),
+        gallery.vat_number.as_ref().map(|vat| format!("VAT no. {}", vat)).unwrap_or_default(),
+        gallery.phone.clone().unwrap_or_default(),
+        gallery.email.clone().unwrap_or_default(),
+    ];
+    for line in seller.iter().filter(|line| !line.is_empty()) {
+        canvas.text(line, MARGIN, half, BODY, Align::Start);
+        canvas.down(4.2);
+    }
+    let left_bottom = canvas.y;
+
+    canvas.y = top;
+    canvas.text("Tax invoice / Receipt", right, half, HEADING, Align::End);
+    canvas.down(5.5);
+    canvas.text("חשבונית מס / קבלה", right, half, HEADING, Align::End);
+    canvas.down(6.5);
+    let numbers = [
+        format!("Invoice no. {}", number),
+        format!("Issued {}", invoice.issued_at.format("%Y-%m-%d")),
+        format!("Order {}", order.id_order),
+        format!("Ordered {}", order.order_date),
+    ];
+    for line in &numbers {
+        canvas.text(line, right, half, BODY, Align::End);
+        canvas.down(4.2);
+    }
+    canvas.y = canvas.y.min(left_bottom) - 2.0;
+    canvas.rule(canvas.y);
+    canvas.down(7.0);
+
+    // Buyer and delivery side by side.
+    let top = canvas.y;
+    let customer = &order.customer;
+    canvas.block(MARGIN, half - 4.0, "Bill to", &[
+        format!("{} {}", customer.first_name, customer.last_name),
+        customer.address.clone(),
+        customer.email.clone(),
+        customer.phone.clone(),
+    ]);
+    let left_bottom = canvas.y;
+    canvas.y = top;
+    if let Some(shipping) = &order.shipping {
+        let delivery = match &shipping.address {
+            Some(address) => vec![
+                shipping.method.clone(),
+                address.recipient.clone().unwrap_or_default(),
+                address.street.clone().unwrap_or_default(),
+                [address.postal_code.as_deref(), address.city.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" "),
+                address.country.clone(),
+            ],
+            None => vec![shipping.method.clone(), "Collected at the gallery".to_string()],
+        };
+        canvas.block(right, half, "Deliver to", &delivery);
+    }
+    canvas.y = canvas.y.min(left_bottom) - 4.0;
+
+    // Line items, with the column headings repeated on every page.
+    let currency = &order.total_amount.currency;
+    canvas.text(&format!("Amounts in {}", currency), MARGIN, CONTENT_WIDTH, SMALL, Align::Start);
+    canvas.down(6.0);
+    table_header(&mut canvas);
+    for line in &order.artworks {
+        if canvas.reserve(10.0) {
+            table_header(&mut canvas);
+        }
+        table_row(&mut canvas, line, order.tax_exempt);
+    }
+    canvas.rule(canvas.y + 3.0);
+    canvas.down(3.0);
+
+    // Totals. Everything before VAT plus VAT plus shipping adds up to the total,
+    // whether line prices included VAT or had it added.
+    let goods = order.total_amount.checked_sub(&order.shipping_amount).unwrap_or_else(|_| order.total_amount.clone());
+    let before_vat = goods.checked_sub(&order.tax_amount).unwrap_or(goods);
+    let discount_label = match &order.discount_code {
+        Some(code) => format!("Discount ({})", code),
+        None => "Discount".to_string(),
+    };
+    let vat_label = if order.tax_exempt { "VAT (exempt)" } else { "VAT" };
+    let mut totals = vec![("Subtotal".to_string(), order.subtotal_amount.amount(), BODY)];
+    if order.discount_amount.minor != 0 {
+        totals.push((discount_label, negated(&order.discount_amount), BODY));
+    }
+    totals.push(("Total before VAT".to_string(), before_vat.amount(), BODY));
+    totals.push((vat_label.to_string(), order.tax_amount.amount(), BODY));
+    if order.shipping.is_some() {
+        totals.push(("Shipping".to_string(), order.shipping_amount.amount(), BODY));
+    }
+    totals.push((format!("Total ({})", currency), order.total_amount.amount(), HEADING));
+
+    canvas.reserve(totals.len() as f32 * 5.5 + 30.0);
+    let label_x = PAGE_WIDTH - MARGIN - 80.0;
+    for (label, amount, style) in &totals {
+        canvas.text(label, label_x, 50.0, *style, Align::Start);
+        canvas.text(amount, label_x + 50.0, 30.0, *style, Align::End);
+        canvas.down(5.5);
+    }
+    canvas.down(4.0);
+
+    if order.tax_exempt {
+        let reason = invoice.tax_exempt_reason.as_deref().unwrap_or("tax exempt customer");
+        canvas.text(&format!("No VAT charged: {}", reason), MARGIN, CONTENT_WIDTH, BODY, Align::Start);
+        canvas.down(5.0);
+    }
+    if let Some(payment) = &invoice.payment {
+        let reference = payment.provider_reference.as_ref().map(|reference| format!(", reference {}", reference)).unwrap_or_default();
+        canvas.text(
+            &format!("Paid {} via {} on {}{}", payment.amount, payment.provider, payment.created_at.format("%Y-%m-%d"), reference),
+            MARGIN,
+            CONTENT_WIDTH,
+            BODY,
+            Align::Start,
+        );
+        canvas.down(5.0);
+    }
+
+    canvas.doc.save_to_bytes()
+}
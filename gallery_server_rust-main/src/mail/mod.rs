@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct OutgoingMail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers outgoing email. Swap the implementation registered in `main.rs`
+/// to plug in a real transport (SMTP, an HTTP mail API, ...).
+#[async_trait]
+pub trait MailSender: Send + Sync {
+    async fn send(&self, mail: &OutgoingMail) -> std::io::Result<()>;
+}
+
+/// Default sender: writes every message to a file in a local outbox directory
+/// (`MAIL_OUTBOX_DIR`, default `outbox/`) instead of sending it.
+pub struct FileMailSender {
+    outbox: PathBuf,
+}
+
+impl FileMailSender {
+    pub fn from_env() -> Self {
+        let outbox = std::env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "outbox".to_string());
+        FileMailSender { outbox: PathBuf::from(outbox) }
+    }
+}
+
+#[async_trait]
+impl MailSender for FileMailSender {
+    async fn send(&self, mail: &OutgoingMail) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(&self.outbox).await?;
+        let path = self
+            .outbox
+            .join(format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S"), Uuid::new_v4()));
+        let contents = format!(
+            "To: {}\nSubject: {}\nDate: {}\n\n{}\n",
+            mail.to,
+            mail.subject,
+            Utc::now().to_rfc2822(),
+            mail.body
+        );
+        tokio::fs::write(&path, contents).await?;
+        println!("📧 Mail to {} ({}) written to {}", mail.to, mail.subject, path.display());
+        Ok(())
+    }
+}
+
+/// Base URL of the web client, used to build links in emails.
+pub fn app_url() -> String {
+    std::env::var("GALLERY_APP_URL").unwrap_or_else(|_| "http://localhost:3000".to_string())
+}
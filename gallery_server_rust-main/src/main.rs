@@ -1,20 +1,49 @@
-use actix_web::{web, App, HttpServer};
+use actix_web::{middleware::from_fn, web, App, HttpServer};
 use actix_cors::Cors;
-use crate::controllers::init_db;
+use std::sync::Arc;
+use crate::auth::middleware::authenticate;
+use crate::idempotency::idempotency_keys;
+use crate::controllers::{auction_controller, cart_controller, idempotency_controller, init_db, offer_controller, order_controller};
+use crate::mail::{FileMailSender, MailSender};
+use crate::payments::{MockPaymentProvider, PaymentProvider};
 use crate::routes::customers_routes::customer_routes;
 use crate::routes::artists_routes::artist_routes;
 use crate::routes::artworks_routes::artworks_routes; 
 use crate::routes::artworks_in_order_routes::artworks_in_order_routes;
 use crate::routes::orders_routes::orders_routes;
+use crate::routes::api_keys_routes::api_keys_routes;
+use crate::routes::me_routes::me_routes;
+use crate::routes::artist_portal_routes::artist_portal_routes;
+use crate::routes::checkout_routes::checkout_routes;
+use crate::routes::cart_routes::cart_routes;
+use crate::routes::payments_routes::payments_routes;
+use crate::routes::returns_routes::returns_routes;
+use crate::routes::discounts_routes::discounts_routes;
+use crate::routes::tax_rates_routes::tax_rates_routes;
+use crate::routes::shipping_routes::shipping_routes;
+use crate::routes::auctions_routes::auctions_routes;
+use crate::routes::offers_routes::offers_routes;
 
+mod auth;
 mod models;
 mod controllers;
+mod idempotency;
+mod invoices;
+mod mail;
+mod payments;
 mod routes;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Initialize database
     let pool = init_db::init_db().await.expect("Failed to initialize database");
+    let mailer: Arc<dyn MailSender> = Arc::new(FileMailSender::from_env());
+    let payment_provider: Arc<dyn PaymentProvider> = Arc::new(MockPaymentProvider::from_env());
+    cart_controller::spawn_hold_sweeper(pool.clone());
+    idempotency_controller::spawn_idempotency_sweeper(pool.clone());
+    auction_controller::spawn_auction_closer(pool.clone());
+    offer_controller::spawn_offer_sweeper(pool.clone());
+    order_controller::spawn_unpaid_order_sweeper(pool.clone());
     
     println!("🚀 Server running at http://127.0.0.1:3007");
     println!("📊 SQLite database initialized at src/mydb.db");
@@ -22,13 +51,30 @@ async fn main() -> std::io::Result<()> {
     HttpServer::new(move || {
         let cors = Cors::permissive(); // Allow all origins for development
         App::new()
+            // Runs after authentication, so keys are kept per customer
+            .wrap(from_fn(idempotency_keys))
+            .wrap(from_fn(authenticate))
             .wrap(cors)
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::from(mailer.clone()))
+            .app_data(web::Data::from(payment_provider.clone()))
             .service(customer_routes())
             .service(artist_routes())
             .service(artworks_routes())
             .service(orders_routes())
             .service(artworks_in_order_routes())
+            .service(api_keys_routes())
+            .service(me_routes())
+            .service(artist_portal_routes())
+            .service(checkout_routes())
+            .service(cart_routes())
+            .service(payments_routes())
+            .service(returns_routes())
+            .service(discounts_routes())
+            .service(tax_rates_routes())
+            .service(shipping_routes())
+            .service(auctions_routes())
+            .service(offers_routes())
     })
     .bind(("127.0.0.1", 3007))?
     .run()
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+/// Scopes an API key can be granted: `<resource>:<read|write>`, where the
+/// resource is the one named by each scope's `ScopeGuard` in `routes/*`.
+pub const API_SCOPES: &[&str] = &[
+    "catalog:read",
+    "catalog:write",
+    "orders:read",
+    "orders:write",
+    "customers:read",
+    "customers:write",
+];
+
+/// Space-separated in the database, a list in JSON.
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct ApiScopes(pub Vec<String>);
+
+impl From<String> for ApiScopes {
+    fn from(stored: String) -> Self {
+        ApiScopes(stored.split_whitespace().map(str::to_string).collect())
+    }
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ApiKey {
+    pub id_api_key: String,
+    pub name: String,
+    pub key_prefix: String,
+    #[sqlx(try_from = "String")]
+    pub scopes: ApiScopes,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewApiKey {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Returned once at creation; the plain key cannot be retrieved again.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    pub key: String,
+    pub api_key: ApiKey,
+}
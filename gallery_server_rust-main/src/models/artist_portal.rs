@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use sqlx::FromRow;
+use crate::models::money::Money;
+
+/// A new work submitted by an artist; it is always filed under their own artist id as a draft.
+#[derive(Debug, Deserialize)]
+pub struct ArtworkSubmission {
+    pub title: String,
+    pub description: String,
+    pub year_created: i32,
+    pub price: Money,
+    pub art_type: String,
+}
+
+/// Changes an artist makes to one of their works. Published works only take a new description.
+#[derive(Debug, Deserialize)]
+pub struct ArtworkEdit {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub year_created: Option<i32>,
+    pub price: Option<Money>,
+    pub art_type: Option<String>,
+}
+
+/// One sold line of an artist's work. Buyer details are left out on purpose.
+#[derive(Debug, Serialize, FromRow)]
+pub struct ArtistSale {
+    pub id_order: String,
+    pub order_date: NaiveDate,
+    pub id_artwork: String,
+    pub title: String,
+    pub amount: i32,
+    #[sqlx(flatten)]
+    pub price: Money,
+}
@@ -1,5 +1,60 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::fmt;
+use crate::models::money::Money;
+
+/// Where a piece stands in the shop. Every artwork is a single piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum ArtworkStatus {
+    #[default]
+    Available,
+    Reserved,
+    /// Up for auction; sold to the winning bidder rather than at `price`.
+    Auction,
+    Sold,
+    Archived,
+}
+
+impl ArtworkStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtworkStatus::Available => "available",
+            ArtworkStatus::Reserved => "reserved",
+            ArtworkStatus::Auction => "auction",
+            ArtworkStatus::Sold => "sold",
+            ArtworkStatus::Archived => "archived",
+        }
+    }
+
+    /// The allowed lifecycle moves. Sold pieces go back on sale when their order
+    /// is cancelled; reserved ones must be released or sold before archiving.
+    /// Pieces on auction are sold to the winner or return to sale when it closes.
+    pub fn can_transition_to(self, next: ArtworkStatus) -> bool {
+        use ArtworkStatus::*;
+        matches!(
+            (self, next),
+            (Available, Reserved)
+                | (Available, Sold)
+                | (Available, Archived)
+                | (Reserved, Available)
+                | (Reserved, Sold)
+                | (Available, Auction)
+                | (Auction, Available)
+                | (Auction, Sold)
+                | (Sold, Available)
+                | (Sold, Archived)
+                | (Archived, Available)
+        )
+    }
+}
+
+impl fmt::Display for ArtworkStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 #[derive(Debug, FromRow, Deserialize, Serialize, Clone)]
 pub struct Artwork {
@@ -7,7 +62,86 @@ pub struct Artwork {
     pub title: String,
     pub description: String,
     pub year_created: i32,
-    pub price: f64,
+    #[sqlx(flatten)]
+    pub price: Money,
     pub id_artist: String,
     pub art_type: String,
+    // Drafts are submitted by artists and stay out of the public catalog until staff publish them.
+    #[serde(default)]
+    pub is_draft: bool,
+    // Changed only through the status endpoint, checkout and order cancellation.
+    #[serde(default, skip_deserializing)]
+    pub status: ArtworkStatus,
+    // Left empty, the default tax rate applies.
+    #[serde(default)]
+    pub id_tax_rate: Option<String>,
+    // Whether `price` already contains the tax, as Israeli shelf prices do.
+    #[serde(default = "tax_included_by_default")]
+    pub price_includes_tax: bool,
+    // Measurements for shipping quotes; unknown ones rule out weight-based rates.
+    #[serde(default)]
+    pub width_cm: Option<f64>,
+    #[serde(default)]
+    pub height_cm: Option<f64>,
+    #[serde(default)]
+    pub depth_cm: Option<f64>,
+    #[serde(default)]
+    pub weight_kg: Option<f64>,
+}
+
+fn tax_included_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArtworkStatusUpdate {
+    pub status: ArtworkStatus,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArtworkListQuery {
+    // Honoured for staff only: also list sold and archived works.
+    #[serde(default)]
+    pub include_unavailable: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArtworkStatus::{self, *};
+
+    /// Whether every step along `path` is an allowed move.
+    fn walk(path: &[ArtworkStatus]) -> bool {
+        path.windows(2).all(|step| step[0].can_transition_to(step[1]))
+    }
+
+    #[test]
+    fn a_cancelled_sale_puts_the_piece_back_on_sale() {
+        assert!(walk(&[Available, Reserved, Sold, Available]));
+    }
+
+    #[test]
+    fn reserved_pieces_are_released_or_sold_before_archiving() {
+        assert!(!Reserved.can_transition_to(Archived));
+        assert!(walk(&[Reserved, Available, Archived]));
+        assert!(walk(&[Reserved, Sold, Archived]));
+    }
+
+    #[test]
+    fn archived_pieces_go_back_on_sale_before_they_are_sold() {
+        assert!(!Archived.can_transition_to(Sold));
+        assert!(!Archived.can_transition_to(Reserved));
+        assert!(walk(&[Archived, Available, Sold]));
+    }
+
+    #[test]
+    fn a_sold_piece_is_not_reserved_again() {
+        assert!(!Sold.can_transition_to(Reserved));
+    }
+
+    #[test]
+    fn moving_to_the_same_status_is_refused() {
+        for status in [Available, Reserved, Sold, Archived] {
+            assert!(!status.can_transition_to(status), "{}", status);
+        }
+    }
 }
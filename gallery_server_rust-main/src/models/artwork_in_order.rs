@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use crate::models::money::Money;
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct ArtworkInOrder {
@@ -7,4 +8,14 @@ pub struct ArtworkInOrder {
     pub id_order: String,
     pub id_artwork: String,
     pub amount: i32,
+    // Snapshot of the artwork when the line was created; set by the server only.
+    #[serde(default, skip_deserializing)]
+    #[sqlx(flatten)]
+    pub unit_price: Money,
+    #[serde(default, skip_deserializing)]
+    pub title: String,
+    #[serde(default, skip_deserializing)]
+    pub id_artist: String,
+    #[serde(default, skip_deserializing)]
+    pub artist_name: String,
 }
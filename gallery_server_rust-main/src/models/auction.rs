@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use std::fmt;
+use crate::models::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum AuctionStatus {
+    /// Scheduled or running; bids are taken between `starts_at` and `ends_at`.
+    Open,
+    /// Closed with a winning bid; the winner has an order to pay.
+    Sold,
+    /// Closed without bids, or below the reserve price.
+    Unsold,
+    Cancelled,
+}
+
+impl AuctionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuctionStatus::Open => "open",
+            AuctionStatus::Sold => "sold",
+            AuctionStatus::Unsold => "unsold",
+            AuctionStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for AuctionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Staff input for putting an artwork up for auction. Without `starts_at` the
+/// auction opens straight away.
+#[derive(Debug, Deserialize)]
+pub struct NewAuction {
+    pub id_artwork: String,
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: DateTime<Utc>,
+    pub starting_price: Money,
+    /// The lowest price the piece sells for; kept from bidders.
+    #[serde(default)]
+    pub reserve_price: Option<Money>,
+    pub bid_increment: Money,
+    /// A bid placed this close to the end pushes the end back to this long after
+    /// the bid, so nobody can win by bidding in the last second.
+    #[serde(default = "default_extension_seconds")]
+    pub extension_seconds: i64,
+}
+
+fn default_extension_seconds() -> i64 {
+    120
+}
+
+impl NewAuction {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        let currency = &self.starting_price.currency;
+        if self.bid_increment.currency != *currency || self.reserve_price.as_ref().is_some_and(|reserve| reserve.currency != *currency) {
+            return Err("All prices of an auction must be in the same currency".to_string());
+        }
+        if self.starting_price.minor <= 0 || self.bid_increment.minor <= 0 {
+            return Err("starting_price and bid_increment must be positive".to_string());
+        }
+        if self.reserve_price.as_ref().is_some_and(|reserve| reserve.minor < self.starting_price.minor) {
+            return Err("reserve_price cannot be below starting_price".to_string());
+        }
+        if self.ends_at <= self.starts_at.unwrap_or(now) || self.ends_at <= now {
+            return Err("ends_at must be in the future and after starts_at".to_string());
+        }
+        if !(0..=3600).contains(&self.extension_seconds) {
+            return Err("extension_seconds must be between 0 and 3600".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Auction {
+    pub id_auction: String,
+    pub id_artwork: String,
+    pub status: AuctionStatus,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub starting_price: Money,
+    // Shown to staff only; bidders just see whether it has been met.
+    pub reserve_price: Option<Money>,
+    pub reserve_met: bool,
+    pub bid_increment: Money,
+    pub extension_seconds: i64,
+    pub bid_count: i64,
+    pub highest_bid: Option<Money>,
+    /// What the next bid has to be at least.
+    pub minimum_bid: Money,
+    // The winner's order, once the auction is sold.
+    pub id_order: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// Reads an `auctions` row joined with its highest bid as `highest_minor`.
+impl<'r> FromRow<'r, SqliteRow> for Auction {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let currency: String = row.try_get("currency")?;
+        let money = |minor: i64| -> Result<Money, sqlx::Error> { Money::new(minor, &currency).map_err(|e| sqlx::Error::Decode(Box::new(e))) };
+        let starting_price = money(row.try_get("starting_minor")?)?;
+        let bid_increment = money(row.try_get("increment_minor")?)?;
+        let reserve_price = row.try_get::<Option<i64>, _>("reserve_minor")?.map(money).transpose()?;
+        let highest_bid = row.try_get::<Option<i64>, _>("highest_minor")?.map(money).transpose()?;
+        let minimum_bid = match &highest_bid {
+            Some(highest) => money(highest.minor + bid_increment.minor)?,
+            None => starting_price.clone(),
+        };
+        let reserve_met = match (&highest_bid, &reserve_price) {
+            (Some(highest), Some(reserve)) => highest.minor >= reserve.minor,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        Ok(Auction {
+            id_auction: row.try_get("id_auction")?,
+            id_artwork: row.try_get("id_artwork")?,
+            status: row.try_get("status")?,
+            starts_at: row.try_get("starts_at")?,
+            ends_at: row.try_get("ends_at")?,
+            starting_price,
+            reserve_price,
+            reserve_met,
+            bid_increment,
+            extension_seconds: row.try_get("extension_seconds")?,
+            bid_count: row.try_get("bid_count")?,
+            highest_bid,
+            minimum_bid,
+            id_order: row.try_get("id_order")?,
+            created_at: row.try_get("created_at")?,
+            closed_at: row.try_get("closed_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuctionListQuery {
+    #[serde(default)]
+    pub status: Option<AuctionStatus>,
+    #[serde(default)]
+    pub id_artwork: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BidRequest {
+    pub amount: Money,
+}
+
+/// One bid as shown in the bid history. Bidders appear as "Bidder 1", "Bidder 2"…
+/// in the order they joined the auction; only staff see who they are.
+#[derive(Debug, Serialize)]
+pub struct Bid {
+    pub id_bid: String,
+    pub id_auction: String,
+    pub bidder: String,
+    pub customer_id: Option<String>,
+    pub is_mine: bool,
+    pub amount: Money,
+    pub placed_at: DateTime<Utc>,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use crate::models::money::Money;
+
+/// An artwork held in a customer's cart until `reserved_until`.
+#[derive(Debug, Serialize, FromRow)]
+pub struct CartItem {
+    pub id_artwork: String,
+    pub title: String,
+    #[sqlx(flatten)]
+    pub price: Money,
+    pub id_artist: String,
+    pub art_type: String,
+    pub added_at: DateTime<Utc>,
+    pub reserved_until: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cart {
+    pub id_cart: String,
+    pub items: Vec<CartItem>,
+    pub total: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CartAddition {
+    pub id_artwork: String,
+}
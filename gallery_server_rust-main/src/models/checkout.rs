@@ -0,0 +1,13 @@
+use serde::Deserialize;
+use crate::models::shipping::ShipmentRequest;
+
+/// The artworks to buy, each a single piece. Left empty, the customer's server cart is bought.
+/// An optional discount code is applied to the order as it is placed, and shipping
+/// can be chosen right away or later while the order is pending.
+#[derive(Debug, Deserialize)]
+pub struct CheckoutRequest {
+    #[serde(default)]
+    pub artwork_ids: Vec<String>,
+    pub discount_code: Option<String>,
+    pub shipping: Option<ShipmentRequest>,
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
 use sqlx::FromRow; // חובה: ודא ששורה זו קיימת!
+use crate::models::role::Role;
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)] // חובה: ודא ש-FromRow כאן!
 pub struct Customer {
@@ -9,4 +11,31 @@ pub struct Customer {
     pub email: String,
     pub phone: String,
     pub address: String,
-}
\ No newline at end of file
+    // Assigned by admins only; ignored when sent by clients.
+    #[serde(default, skip_deserializing)]
+    pub role: Role,
+    // Set once the customer follows the link in the verification email.
+    #[serde(default, skip_deserializing)]
+    pub email_verified_at: Option<DateTime<Utc>>,
+    // Set when the personal fields were anonymised on an erasure request.
+    #[serde(default, skip_deserializing)]
+    pub erased_at: Option<DateTime<Utc>>,
+    // The artist this account manages, for accounts with the artist role.
+    #[serde(default, skip_deserializing)]
+    pub artist_id: Option<String>,
+    // Set by staff for buyers who do not pay VAT, such as foreign buyers exporting the piece.
+    #[serde(default, skip_deserializing)]
+    pub tax_exempt: bool,
+    #[serde(default, skip_deserializing)]
+    pub tax_exempt_reason: Option<String>,
+    // Only accepted on registration; the stored hash is never read into this struct.
+    #[serde(default, skip_serializing)]
+    #[sqlx(skip)]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomerLogin {
+    pub email: String,
+    pub password: String,
+}
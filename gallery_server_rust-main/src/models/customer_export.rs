@@ -0,0 +1,67 @@
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use crate::invoices::InvoiceDocument;
+use crate::models::account_token::TokenPurpose;
+use crate::models::cart::CartItem;
+use crate::models::customer::Customer;
+use crate::models::money::Money;
+use crate::models::offer::Offer;
+use crate::models::order_return::OrderReturn;
+use crate::models::payment::Payment;
+use crate::models::shipping::Shipment;
+
+/// Everything the gallery holds about one customer, as handed out on a data export request.
+#[derive(Debug, Serialize)]
+pub struct CustomerExport {
+    pub exported_at: DateTime<Utc>,
+    pub customer: Customer,
+    pub orders: Vec<Value>,
+    pub payments: Vec<Payment>,
+    pub returns: Vec<OrderReturn>,
+    pub shipments: Vec<Shipment>,
+    pub invoices: Vec<InvoiceDocument>,
+    pub offers: Vec<Offer>,
+    pub bids: Vec<BidRecord>,
+    pub cart_items: Vec<CartItem>,
+    pub sessions: Vec<SessionRecord>,
+    pub account_tokens: Vec<AccountTokenRecord>,
+}
+
+/// One of the customer's own bids.
+#[derive(Debug, Serialize)]
+pub struct BidRecord {
+    pub id_bid: String,
+    pub id_auction: String,
+    pub id_artwork: String,
+    pub amount: Money,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for BidRecord {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(BidRecord {
+            id_bid: row.try_get("id_bid")?,
+            id_auction: row.try_get("id_auction")?,
+            id_artwork: row.try_get("id_artwork")?,
+            amount: Money::new(row.try_get("amount_minor")?, row.try_get("currency")?).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            placed_at: row.try_get("placed_at")?,
+        })
+    }
+}
+
+// Token hashes stay out of the export; only when they were issued and used.
+#[derive(Debug, Serialize, FromRow)]
+pub struct SessionRecord {
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AccountTokenRecord {
+    pub purpose: TokenPurpose,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
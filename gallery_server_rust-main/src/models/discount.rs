@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use crate::models::money::{Money, MoneyError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum DiscountKind {
+    Percentage,
+    Fixed,
+}
+
+/// A coupon code. `percent_off` is set for percentage codes, `amount_off` for
+/// fixed ones. `id_artist` and `art_type` narrow the artworks it applies to;
+/// `min_order` is checked against the whole order before any discount.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiscountCode {
+    #[serde(default, skip_deserializing)]
+    pub id_discount: String,
+    pub code: String,
+    pub kind: DiscountKind,
+    pub percent_off: Option<i64>,
+    pub amount_off: Option<Money>,
+    pub min_order: Option<Money>,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub max_uses: Option<i64>,
+    #[serde(default, skip_deserializing)]
+    pub times_used: i64,
+    pub id_artist: Option<String>,
+    pub art_type: Option<String>,
+    #[serde(default = "active_by_default")]
+    pub is_active: bool,
+}
+
+fn active_by_default() -> bool {
+    true
+}
+
+impl DiscountCode {
+    /// Checks the code's own settings, independent of any order.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.code.trim().is_empty() {
+            return Err("The code cannot be empty".to_string());
+        }
+        match (self.kind, self.percent_off, &self.amount_off) {
+            (DiscountKind::Percentage, Some(percent), None) if (1..=100).contains(&percent) => {}
+            (DiscountKind::Percentage, _, _) => return Err("Percentage codes need a percent_off between 1 and 100 and no amount_off".to_string()),
+            (DiscountKind::Fixed, None, Some(amount)) if amount.minor > 0 => {}
+            (DiscountKind::Fixed, _, _) => return Err("Fixed codes need a positive amount_off and no percent_off".to_string()),
+        }
+        if let (Some(amount), Some(min_order)) = (&self.amount_off, &self.min_order) {
+            if amount.currency != min_order.currency {
+                return Err("amount_off and min_order must be in the same currency".to_string());
+            }
+        }
+        if self.min_order.as_ref().is_some_and(Money::is_negative) {
+            return Err("min_order cannot be negative".to_string());
+        }
+        if let (Some(from), Some(until)) = (self.valid_from, self.valid_until) {
+            if until <= from {
+                return Err("valid_until must be after valid_from".to_string());
+            }
+        }
+        if self.max_uses.is_some_and(|uses| uses < 1) {
+            return Err("max_uses must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// The currency of the code's amounts, if it has any.
+    pub fn currency(&self) -> Option<&str> {
+        self.amount_off.as_ref().or(self.min_order.as_ref()).map(|money| money.currency.as_str())
+    }
+}
+
+impl<'r> FromRow<'r, SqliteRow> for DiscountCode {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let currency: Option<String> = row.try_get("currency")?;
+        let money = |column: &str| -> Result<Option<Money>, sqlx::Error> {
+            match (row.try_get::<Option<i64>, _>(column)?, &currency) {
+                (Some(minor), Some(currency)) => Money::new(minor, currency).map(Some).map_err(|e| sqlx::Error::Decode(Box::new(e))),
+                (Some(_), None) => Err(sqlx::Error::Decode(Box::new(MoneyError::InvalidCurrency(String::new())))),
+                (None, _) => Ok(None),
+            }
+        };
+        Ok(DiscountCode {
+            id_discount: row.try_get("id_discount")?,
+            code: row.try_get("code")?,
+            kind: row.try_get("kind")?,
+            percent_off: row.try_get("percent_off")?,
+            amount_off: money("amount_off_minor")?,
+            min_order: money("min_order_minor")?,
+            valid_from: row.try_get("valid_from")?,
+            valid_until: row.try_get("valid_until")?,
+            max_uses: row.try_get("max_uses")?,
+            times_used: row.try_get("times_used")?,
+            id_artist: row.try_get("id_artist")?,
+            art_type: row.try_get("art_type")?,
+            is_active: row.try_get("is_active")?,
+        })
+    }
+}
@@ -2,4 +2,19 @@ pub mod customer;
 pub mod artist;
 pub mod artwork;
 pub mod artwork_in_order;
-pub mod order;
\ No newline at end of file
+pub mod order;
+pub mod session;
+pub mod role;
+pub mod account_token;
+pub mod api_key;
+pub mod customer_export;
+pub mod artist_portal;
+pub mod checkout;
+pub mod cart;
+pub mod money;pub mod payment;
+pub mod order_return;
+pub mod discount;
+pub mod tax;
+pub mod shipping;
+pub mod auction;
+pub mod offer;
@@ -0,0 +1,244 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::FromRow;
+use std::fmt;
+
+/// The currency the gallery prices in by default, and the one legacy `REAL` prices were in.
+pub const CATALOG_CURRENCY: &str = "ILS";
+
+/// An exact amount of money: integer minor units (agorot, cents) plus an ISO 4217
+/// currency code. In JSON it reads `{"amount": "1500.00", "currency": "ILS"}`; in
+/// the database it is stored as the `price_minor` and `currency` columns.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Money {
+    #[sqlx(rename = "price_minor")]
+    pub minor: i64,
+    pub currency: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MoneyError {
+    InvalidAmount(String),
+    InvalidCurrency(String),
+    CurrencyMismatch(String, String),
+    Overflow,
+}
+
+impl std::error::Error for MoneyError {}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::InvalidAmount(amount) => write!(f, "'{}' is not a valid amount", amount),
+            MoneyError::InvalidCurrency(code) => write!(f, "'{}' is not a three-letter currency code", code),
+            MoneyError::CurrencyMismatch(a, b) => write!(f, "cannot combine amounts in {} and {}", a, b),
+            MoneyError::Overflow => f.write_str("amount is too large"),
+        }
+    }
+}
+
+/// Digits after the decimal point for a currency. Most have two.
+pub fn minor_digits(currency: &str) -> u32 {
+    match currency {
+        "JPY" | "KRW" => 0,
+        _ => 2,
+    }
+}
+
+fn normalize_currency(code: &str) -> Result<String, MoneyError> {
+    let code = code.trim().to_ascii_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(code)
+    } else {
+        Err(MoneyError::InvalidCurrency(code))
+    }
+}
+
+impl Money {
+    pub fn new(minor: i64, currency: &str) -> Result<Money, MoneyError> {
+        Ok(Money { minor, currency: normalize_currency(currency)? })
+    }
+
+    pub fn zero(currency: &str) -> Money {
+        Money { minor: 0, currency: currency.to_string() }
+    }
+
+    /// Parses a decimal string such as "1500", "1500.5" or "-12.30" without going through floats.
+    pub fn parse(amount: &str, currency: &str) -> Result<Money, MoneyError> {
+        let currency = normalize_currency(currency)?;
+        let digits = minor_digits(&currency);
+        let invalid = || MoneyError::InvalidAmount(amount.to_string());
+
+        let trimmed = amount.trim();
+        let (negative, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if whole.is_empty()
+            || !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+            || fraction.len() > digits as usize
+            || (unsigned.contains('.') && fraction.is_empty())
+        {
+            return Err(invalid());
+        }
+
+        let scale = 10i64.pow(digits);
+        let whole: i64 = whole.parse().map_err(|_| MoneyError::Overflow)?;
+        let fraction: i64 = if fraction.is_empty() {
+            0
+        } else {
+            format!("{:0<width$}", fraction, width = digits as usize).parse().map_err(|_| invalid())?
+        };
+        let minor = whole
+            .checked_mul(scale)
+            .and_then(|m| m.checked_add(fraction))
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money { minor: if negative { -minor } else { minor }, currency })
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Money, MoneyError> {
+        if self.currency != other.currency {
+            return Err(MoneyError::CurrencyMismatch(self.currency.clone(), other.currency.clone()));
+        }
+        let minor = self.minor.checked_add(other.minor).ok_or(MoneyError::Overflow)?;
+        Ok(Money { minor, currency: self.currency.clone() })
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.checked_add(&Money { minor: other.minor.checked_neg().ok_or(MoneyError::Overflow)?, currency: other.currency.clone() })
+    }
+
+    pub fn checked_mul(&self, quantity: i64) -> Result<Money, MoneyError> {
+        let minor = self.minor.checked_mul(quantity).ok_or(MoneyError::Overflow)?;
+        Ok(Money { minor, currency: self.currency.clone() })
+    }
+
+    /// Adds up amounts that must all be in `currency`.
+    pub fn sum<'a>(currency: &str, amounts: impl IntoIterator<Item = &'a Money>) -> Result<Money, MoneyError> {
+        amounts.into_iter().try_fold(Money::zero(currency), |total, amount| total.checked_add(amount))
+    }
+
+    /// The amount as a plain decimal string, e.g. "1500.00".
+    pub fn amount(&self) -> String {
+        let digits = minor_digits(&self.currency);
+        if digits == 0 {
+            return self.minor.to_string();
+        }
+        let scale = 10u64.pow(digits);
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let abs = self.minor.unsigned_abs();
+        format!("{}{}.{:0width$}", sign, abs / scale, abs % scale, width = digits as usize)
+    }
+}
+
+impl Default for Money {
+    fn default() -> Self {
+        Money::zero(CATALOG_CURRENCY)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount(), self.currency)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MoneyJson {
+    amount: String,
+    currency: String,
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MoneyJson { amount: self.amount(), currency: self.currency.clone() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = MoneyJson::deserialize(deserializer)?;
+        Money::parse(&json.amount, &json.currency).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Money, MoneyError};
+
+    fn ils(minor: i64) -> Money {
+        Money::new(minor, "ILS").unwrap()
+    }
+
+    #[test]
+    fn parses_whole_and_fractional_amounts() {
+        assert_eq!(Money::parse("1500", "ILS"), Ok(ils(150_000)));
+        assert_eq!(Money::parse("1500.5", "ILS"), Ok(ils(150_050)));
+        assert_eq!(Money::parse("1500.05", "ILS"), Ok(ils(150_005)));
+        assert_eq!(Money::parse("-12.30", "ILS"), Ok(ils(-1230)));
+        assert_eq!(Money::parse(" 0.01 ", "ils"), Ok(ils(1)));
+    }
+
+    #[test]
+    fn respects_the_currency_minor_digits() {
+        assert_eq!(Money::parse("1500", "JPY"), Ok(Money::new(1500, "JPY").unwrap()));
+        assert_eq!(Money::parse("15.0", "JPY"), Err(MoneyError::InvalidAmount("15.0".to_string())));
+        assert_eq!(Money::parse("1.234", "ILS"), Err(MoneyError::InvalidAmount("1.234".to_string())));
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for amount in ["", "-", ".5", "12.", "+5", "1e3", "1,5", "abc", "--1", "1.2.3"] {
+            assert_eq!(Money::parse(amount, "ILS"), Err(MoneyError::InvalidAmount(amount.to_string())), "{:?}", amount);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_currency_codes() {
+        assert_eq!(Money::parse("1", "IL"), Err(MoneyError::InvalidCurrency("IL".to_string())));
+        assert_eq!(Money::parse("1", "IL5"), Err(MoneyError::InvalidCurrency("IL5".to_string())));
+    }
+
+    #[test]
+    fn rejects_amounts_beyond_i64() {
+        assert_eq!(Money::parse("92233720368547758.08", "ILS"), Err(MoneyError::Overflow));
+        assert_eq!(Money::parse("99999999999999999999", "ILS"), Err(MoneyError::Overflow));
+        assert_eq!(Money::parse("92233720368547758.07", "ILS"), Ok(ils(i64::MAX)));
+    }
+
+    #[test]
+    fn formats_back_to_a_decimal_string() {
+        assert_eq!(ils(150_050).amount(), "1500.50");
+        assert_eq!(ils(-5).amount(), "-0.05");
+        assert_eq!(Money::new(1500, "JPY").unwrap().amount(), "1500");
+    }
+
+    #[test]
+    fn totals_stay_in_one_currency() {
+        assert_eq!(ils(100).checked_add(&ils(250)), Ok(ils(350)));
+        assert_eq!(ils(125).checked_mul(3), Ok(ils(375)));
+        assert_eq!(
+            ils(100).checked_add(&Money::new(100, "USD").unwrap()),
+            Err(MoneyError::CurrencyMismatch("ILS".to_string(), "USD".to_string()))
+        );
+        assert_eq!(Money::sum("ILS", &[ils(1), ils(2), ils(3)]), Ok(ils(6)));
+        assert!(Money::sum("ILS", &[ils(1), Money::new(2, "EUR").unwrap()]).is_err());
+    }
+
+    #[test]
+    fn totals_report_overflow_instead_of_wrapping() {
+        assert_eq!(ils(i64::MAX).checked_add(&ils(1)), Err(MoneyError::Overflow));
+        assert_eq!(ils(i64::MAX / 2 + 1).checked_mul(2), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn subtracting_may_go_negative_but_not_overflow() {
+        assert_eq!(ils(100).checked_sub(&ils(250)), Ok(ils(-150)));
+        assert_eq!(ils(0).checked_sub(&ils(i64::MIN)), Err(MoneyError::Overflow));
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use std::fmt;
+use crate::models::money::Money;
+
+/// Where a price negotiation stands. Staff answer a pending offer; the customer
+/// answers a counter. Acceptance holds the piece for the customer until
+/// `checkout_until`, and checking it out completes the offer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum OfferStatus {
+    Pending,
+    Countered,
+    Accepted,
+    Rejected,
+    Withdrawn,
+    Expired,
+    Completed,
+}
+
+impl OfferStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OfferStatus::Pending => "pending",
+            OfferStatus::Countered => "countered",
+            OfferStatus::Accepted => "accepted",
+            OfferStatus::Rejected => "rejected",
+            OfferStatus::Withdrawn => "withdrawn",
+            OfferStatus::Expired => "expired",
+            OfferStatus::Completed => "completed",
+        }
+    }
+
+    pub fn can_transition_to(self, next: OfferStatus) -> bool {
+        use OfferStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Countered | Accepted | Rejected | Withdrawn | Expired)
+                | (Countered, Accepted | Rejected | Withdrawn | Expired)
+                | (Accepted, Completed | Withdrawn | Expired)
+        )
+    }
+}
+
+impl fmt::Display for OfferStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A customer's offer on one artwork. `agreed_price` is set on acceptance and is
+/// what the order line is priced at when the piece is checked out.
+#[derive(Debug, Serialize)]
+pub struct Offer {
+    pub id_offer: String,
+    pub id_artwork: String,
+    pub customer_id: String,
+    pub status: OfferStatus,
+    pub amount: Money,
+    pub message: Option<String>,
+    pub counter_amount: Option<Money>,
+    pub note: Option<String>,
+    pub agreed_price: Option<Money>,
+    /// Until when the other side may answer a pending or countered offer.
+    pub expires_at: DateTime<Utc>,
+    pub checkout_until: Option<DateTime<Utc>>,
+    pub id_order: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Offer {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let currency: String = row.try_get("currency")?;
+        let money = |minor: i64| -> Result<Money, sqlx::Error> { Money::new(minor, &currency).map_err(|e| sqlx::Error::Decode(Box::new(e))) };
+        Ok(Offer {
+            id_offer: row.try_get("id_offer")?,
+            id_artwork: row.try_get("id_artwork")?,
+            customer_id: row.try_get("customer_id")?,
+            status: row.try_get("status")?,
+            amount: money(row.try_get("amount_minor")?)?,
+            message: row.try_get("message")?,
+            counter_amount: row.try_get::<Option<i64>, _>("counter_minor")?.map(money).transpose()?,
+            note: row.try_get("note")?,
+            agreed_price: row.try_get::<Option<i64>, _>("agreed_minor")?.map(money).transpose()?,
+            expires_at: row.try_get("expires_at")?,
+            checkout_until: row.try_get("checkout_until")?,
+            id_order: row.try_get("id_order")?,
+            created_at: row.try_get("created_at")?,
+            decided_by: row.try_get("decided_by")?,
+            decided_at: row.try_get("decided_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewOffer {
+    pub id_artwork: String,
+    pub amount: Money,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CounterOffer {
+    pub amount: Money,
+    pub note: Option<String>,
+}
+
+/// The optional body of a rejection.
+#[derive(Debug, Deserialize)]
+pub struct OfferResponse {
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OfferListQuery {
+    pub status: Option<OfferStatus>,
+    pub id_artwork: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OfferStatus::{self, *};
+
+    /// Whether every step along `path` is an allowed move.
+    fn walk(path: &[OfferStatus]) -> bool {
+        path.windows(2).all(|step| step[0].can_transition_to(step[1]))
+    }
+
+    #[test]
+    fn a_countered_offer_can_still_be_accepted_and_checked_out() {
+        assert!(walk(&[Pending, Countered, Accepted, Completed]));
+        assert!(walk(&[Pending, Accepted, Completed]));
+    }
+
+    #[test]
+    fn only_an_accepted_offer_is_checked_out() {
+        assert!(!Pending.can_transition_to(Completed));
+        assert!(!Countered.can_transition_to(Completed));
+    }
+
+    #[test]
+    fn a_counter_is_answered_rather_than_countered_again() {
+        assert!(!Countered.can_transition_to(Countered));
+        assert!(!Countered.can_transition_to(Pending));
+    }
+
+    #[test]
+    fn an_accepted_offer_can_lapse_but_not_be_renegotiated() {
+        assert!(Accepted.can_transition_to(Expired));
+        assert!(Accepted.can_transition_to(Withdrawn));
+        assert!(!Accepted.can_transition_to(Rejected));
+        assert!(!Accepted.can_transition_to(Countered));
+    }
+
+    #[test]
+    fn closed_offers_are_final() {
+        for closed in [Rejected, Withdrawn, Expired, Completed] {
+            for next in [Pending, Countered, Accepted, Rejected, Withdrawn, Expired, Completed] {
+                assert!(!closed.can_transition_to(next), "{} -> {}", closed, next);
+            }
+        }
+    }
+}
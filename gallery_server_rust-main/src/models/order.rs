@@ -1,10 +1,155 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::fmt;
+
+/// Where an order stands between checkout and delivery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum OrderStatus {
+    #[default]
+    Pending,
+    Paid,
+    Shipped,
+    Delivered,
+    Cancelled,
+    PartiallyRefunded,
+    Refunded,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::PartiallyRefunded => "partially_refunded",
+            OrderStatus::Refunded => "refunded",
+        }
+    }
+
+    /// The allowed moves. Unpaid orders are cancelled; once money has been taken
+    /// the way out is a refund, in full or for some of the pieces. A partly
+    /// refunded order may still be delivered or refunded again, but never goes
+    /// back to shipped. Cancelled and refunded orders are final.
+    pub fn can_transition_to(self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Paid)
+                | (Pending, Cancelled)
+                | (Paid, Shipped)
+                | (Paid, Refunded)
+                | (Paid, PartiallyRefunded)
+                | (Shipped, Delivered)
+                | (Shipped, Refunded)
+                | (Shipped, PartiallyRefunded)
+                | (Delivered, Refunded)
+                | (Delivered, PartiallyRefunded)
+                | (PartiallyRefunded, Delivered)
+                | (PartiallyRefunded, PartiallyRefunded)
+                | (PartiallyRefunded, Refunded)
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 #[derive(Debug, FromRow, Deserialize, Serialize)]
 pub struct Order {
     pub id_order: String,
     pub id_customer: String,
     pub order_date: NaiveDate,
-}
\ No newline at end of file
+    // Changed only through the status endpoint, so every move lands in the history.
+    #[serde(default, skip_deserializing)]
+    pub status: OrderStatus,
+    // Set by checkout when the customer used a discount code.
+    #[serde(default, skip_deserializing)]
+    pub discount_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderStatusUpdate {
+    pub status: OrderStatus,
+    pub note: Option<String>,
+}
+
+/// One entry in an order's status history. `from_status` is empty for the entry
+/// written when the order was placed; `changed_by` is empty for changes the
+/// server made on its own.
+#[derive(Debug, Serialize, FromRow)]
+pub struct OrderStatusChange {
+    pub id_status_change: String,
+    pub id_order: String,
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    pub changed_at: DateTime<Utc>,
+    pub changed_by: Option<String>,
+    pub note: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderStatus::{self, *};
+
+    /// Whether every step along `path` is an allowed move.
+    fn walk(path: &[OrderStatus]) -> bool {
+        path.windows(2).all(|step| step[0].can_transition_to(step[1]))
+    }
+
+    #[test]
+    fn an_order_is_paid_before_it_ships() {
+        assert!(walk(&[Pending, Paid, Shipped, Delivered]));
+        assert!(!Pending.can_transition_to(Shipped));
+        assert!(!Pending.can_transition_to(Delivered));
+    }
+
+    #[test]
+    fn unpaid_orders_are_cancelled_rather_than_refunded() {
+        assert!(Pending.can_transition_to(Cancelled));
+        assert!(!Pending.can_transition_to(Refunded));
+    }
+
+    #[test]
+    fn paid_orders_are_refunded_rather_than_cancelled() {
+        for paid in [Paid, Shipped, Delivered] {
+            assert!(paid.can_transition_to(Refunded), "{}", paid);
+            assert!(!paid.can_transition_to(Cancelled), "{}", paid);
+        }
+    }
+
+    #[test]
+    fn fulfilment_does_not_go_backwards() {
+        assert!(!Paid.can_transition_to(Pending));
+        assert!(!Shipped.can_transition_to(Paid));
+        assert!(!Delivered.can_transition_to(Shipped));
+        assert!(!PartiallyRefunded.can_transition_to(Shipped));
+    }
+
+    #[test]
+    fn a_partly_refunded_order_can_be_refunded_again() {
+        assert!(walk(&[Pending, Paid, Shipped, Delivered, PartiallyRefunded, PartiallyRefunded, Refunded]));
+        assert!(walk(&[Pending, Paid, PartiallyRefunded, Delivered]));
+    }
+
+    #[test]
+    fn a_refunded_order_refuses_shipping() {
+        assert!(!Refunded.can_transition_to(Shipped));
+        assert!(!Refunded.can_transition_to(Delivered));
+    }
+
+    #[test]
+    fn cancelled_and_refunded_orders_are_final() {
+        for next in [Pending, Paid, Shipped, Delivered, Cancelled, PartiallyRefunded, Refunded] {
+            assert!(!Cancelled.can_transition_to(next), "cancelled -> {}", next);
+            assert!(!Refunded.can_transition_to(next), "refunded -> {}", next);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use std::fmt;
+use crate::models::money::Money;
+
+/// Where a return request stands. Approval fixes the refund amount; the
+/// refund itself sends the money back and puts the piece on sale again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum ReturnStatus {
+    Requested,
+    Approved,
+    Rejected,
+    Refunded,
+}
+
+impl ReturnStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReturnStatus::Requested => "requested",
+            ReturnStatus::Approved => "approved",
+            ReturnStatus::Rejected => "rejected",
+            ReturnStatus::Refunded => "refunded",
+        }
+    }
+
+    pub fn can_transition_to(self, next: ReturnStatus) -> bool {
+        use ReturnStatus::*;
+        matches!((self, next), (Requested, Approved) | (Requested, Rejected) | (Approved, Rejected) | (Approved, Refunded))
+    }
+}
+
+impl fmt::Display for ReturnStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A request to return one order line. `refund_amount` is set on approval.
+#[derive(Debug, Serialize)]
+pub struct OrderReturn {
+    pub id_return: String,
+    pub id_order: String,
+    pub id_artwork_in_order: String,
+    pub status: ReturnStatus,
+    pub reason: Option<String>,
+    pub refund_amount: Option<Money>,
+    pub requested_by: String,
+    pub requested_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+    pub id_refund: Option<String>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for OrderReturn {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let refund_amount = match row.try_get::<Option<i64>, _>("refund_minor")? {
+            Some(minor) => Some(
+                Money::new(minor, &row.try_get::<String, _>("currency")?).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            ),
+            None => None,
+        };
+        Ok(OrderReturn {
+            id_return: row.try_get("id_return")?,
+            id_order: row.try_get("id_order")?,
+            id_artwork_in_order: row.try_get("id_artwork_in_order")?,
+            status: row.try_get("status")?,
+            reason: row.try_get("reason")?,
+            refund_amount,
+            requested_by: row.try_get("requested_by")?,
+            requested_at: row.try_get("requested_at")?,
+            decided_by: row.try_get("decided_by")?,
+            decided_at: row.try_get("decided_at")?,
+            note: row.try_get("note")?,
+            id_refund: row.try_get("id_refund")?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnRequest {
+    pub id_artwork_in_order: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnDecision {
+    pub status: ReturnStatus,
+    // Only read on approval; defaults to the full price paid for the line.
+    pub refund_amount: Option<Money>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnListQuery {
+    pub status: Option<ReturnStatus>,
+}
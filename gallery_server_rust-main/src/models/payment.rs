@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use std::fmt;
+use crate::models::money::Money;
+
+/// Where a payment stands with its provider. `Pending` payments wait for the
+/// provider to confirm them through the webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum PaymentStatus {
+    Pending,
+    Authorized,
+    Captured,
+    Declined,
+    Refunded,
+    Cancelled,
+}
+
+impl PaymentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Authorized => "authorized",
+            PaymentStatus::Captured => "captured",
+            PaymentStatus::Declined => "declined",
+            PaymentStatus::Refunded => "refunded",
+            PaymentStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A payment taken (or attempted) for an order. `amount` and `refunded` share
+/// the payment's currency; `refunded` grows with every refund issued against it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Payment {
+    pub id_payment: String,
+    pub id_order: String,
+    pub provider: String,
+    pub provider_reference: Option<String>,
+    pub status: PaymentStatus,
+    pub amount: Money,
+    pub refunded: Money,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Payment {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let currency: String = row.try_get("currency")?;
+        let money = |column: &str| -> Result<Money, sqlx::Error> {
+            Money::new(row.try_get(column)?, &currency).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        };
+        Ok(Payment {
+            id_payment: row.try_get("id_payment")?,
+            id_order: row.try_get("id_order")?,
+            provider: row.try_get("provider")?,
+            provider_reference: row.try_get("provider_reference")?,
+            status: row.try_get("status")?,
+            amount: money("amount_minor")?,
+            refunded: money("refunded_minor")?,
+            failure_reason: row.try_get("failure_reason")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentRequest {
+    // Provider-specific, e.g. a card token; the mock provider reads `mock_success`,
+    // `mock_decline` or `mock_async`.
+    pub payment_method: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefundRequest {
+    // Defaults to whatever is left unrefunded on the payment.
+    pub amount: Option<Money>,
+    pub reason: Option<String>,
+}
@@ -0,0 +1,10 @@
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use crate::models::customer::Customer;
+
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub customer: Customer,
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use std::fmt;
+use crate::models::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum ShippingKind {
+    /// Collected at the gallery; needs no address.
+    Pickup,
+    Courier,
+    CratedFreight,
+}
+
+impl ShippingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShippingKind::Pickup => "pickup",
+            ShippingKind::Courier => "courier",
+            ShippingKind::CratedFreight => "crated_freight",
+        }
+    }
+}
+
+impl fmt::Display for ShippingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A way of getting artworks to the buyer, priced as `base_rate` plus
+/// `per_kg_rate` for every started kilogram of billable weight. Billable weight
+/// is the heavier of a piece's weight and its volumetric weight.
+/// `destination_country` limits the method to one country; left empty it ships anywhere.
+/// `max_weight_kg` and `max_side_cm` apply to each piece.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShippingMethod {
+    #[serde(default, skip_deserializing)]
+    pub id_shipping_method: String,
+    pub name: String,
+    pub kind: ShippingKind,
+    pub destination_country: Option<String>,
+    pub base_rate: Money,
+    pub per_kg_rate: Money,
+    pub max_weight_kg: Option<f64>,
+    pub max_side_cm: Option<f64>,
+    #[serde(default = "active_by_default")]
+    pub is_active: bool,
+}
+
+fn active_by_default() -> bool {
+    true
+}
+
+/// Volumetric weight in kg is length × width × height in cm divided by this.
+pub const VOLUMETRIC_DIVISOR: f64 = 5000.0;
+
+impl ShippingMethod {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("A shipping method needs a name".to_string());
+        }
+        if self.base_rate.currency != self.per_kg_rate.currency {
+            return Err("base_rate and per_kg_rate must be in the same currency".to_string());
+        }
+        if self.base_rate.is_negative() || self.per_kg_rate.is_negative() {
+            return Err("Shipping rates cannot be negative".to_string());
+        }
+        if self.destination_country.as_deref().is_some_and(|country| !is_country_code(country)) {
+            return Err("destination_country must be a two-letter country code".to_string());
+        }
+        if self.max_weight_kg.is_some_and(|kg| kg <= 0.0) || self.max_side_cm.is_some_and(|cm| cm <= 0.0) {
+            return Err("Limits must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether the price depends on how big or heavy the pieces are.
+    pub fn needs_measurements(&self) -> bool {
+        self.per_kg_rate.minor > 0 || self.max_weight_kg.is_some() || self.max_side_cm.is_some()
+    }
+}
+
+impl<'r> FromRow<'r, SqliteRow> for ShippingMethod {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let currency: String = row.try_get("currency")?;
+        let money = |column: &str| -> Result<Money, sqlx::Error> {
+            Money::new(row.try_get(column)?, &currency).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        };
+        Ok(ShippingMethod {
+            id_shipping_method: row.try_get("id_shipping_method")?,
+            name: row.try_get("name")?,
+            kind: row.try_get("kind")?,
+            destination_country: row.try_get("destination_country")?,
+            base_rate: money("base_minor")?,
+            per_kg_rate: money("per_kg_minor")?,
+            max_weight_kg: row.try_get("max_weight_kg")?,
+            max_side_cm: row.try_get("max_side_cm")?,
+            is_active: row.try_get("is_active")?,
+        })
+    }
+}
+
+pub fn is_country_code(country: &str) -> bool {
+    country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShippingAddress {
+    pub recipient: String,
+    pub street: String,
+    pub city: String,
+    #[serde(default)]
+    pub postal_code: Option<String>,
+    /// ISO 3166-1 alpha-2, e.g. "IL".
+    pub country: String,
+    #[serde(default)]
+    pub phone: Option<String>,
+}
+
+impl ShippingAddress {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.recipient.trim().is_empty() || self.street.trim().is_empty() || self.city.trim().is_empty() {
+            return Err("The shipping address needs a recipient, street and city".to_string());
+        }
+        if !is_country_code(&self.country) {
+            return Err("country must be a two-letter country code".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The buyer's choice of method and, unless picking up, where to deliver.
+#[derive(Debug, Deserialize)]
+pub struct ShipmentRequest {
+    pub id_shipping_method: String,
+    #[serde(default)]
+    pub address: Option<ShippingAddress>,
+}
+
+/// How an order is delivered. Method name, kind and cost are copied from the
+/// shipping method when chosen, so later rate changes leave the order alone.
+#[derive(Debug, Serialize)]
+pub struct Shipment {
+    pub id_order: String,
+    pub id_shipping_method: Option<String>,
+    pub method_name: String,
+    pub kind: ShippingKind,
+    pub address: Option<ShippingAddress>,
+    pub cost: Money,
+    pub tracking_number: Option<String>,
+    pub carrier: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Shipment {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let country: Option<String> = row.try_get("country")?;
+        let address = match country {
+            Some(country) => Some(ShippingAddress {
+                recipient: row.try_get("recipient")?,
+                street: row.try_get("street")?,
+                city: row.try_get("city")?,
+                postal_code: row.try_get("postal_code")?,
+                country,
+                phone: row.try_get("phone")?,
+            }),
+            None => None,
+        };
+        Ok(Shipment {
+            id_order: row.try_get("id_order")?,
+            id_shipping_method: row.try_get("id_shipping_method")?,
+            method_name: row.try_get("method_name")?,
+            kind: row.try_get("kind")?,
+            address,
+            cost: Money::new(row.try_get("shipping_minor")?, row.try_get("currency")?).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            tracking_number: row.try_get("tracking_number")?,
+            carrier: row.try_get("carrier")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+/// One step of a shipment's journey, as reported by the carrier or staff.
+#[derive(Debug, Serialize, FromRow)]
+pub struct ShipmentEvent {
+    pub id_shipment_event: String,
+    pub id_order: String,
+    pub status: String,
+    pub location: Option<String>,
+    pub tracking_number: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    pub recorded_by: Option<String>,
+}
+
+/// Staff input for a tracking update. A new tracking number or carrier replaces the old one.
+#[derive(Debug, Deserialize)]
+pub struct TrackingUpdate {
+    pub status: String,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub tracking_number: Option<String>,
+    #[serde(default)]
+    pub carrier: Option<String>,
+}
+
+/// What one shipping method would cost for an order, or why it cannot be used.
+#[derive(Debug, Serialize)]
+pub struct ShippingOption {
+    pub id_shipping_method: String,
+    pub name: String,
+    pub kind: ShippingKind,
+    pub cost: Option<Money>,
+    pub unavailable_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShippingOptionsQuery {
+    pub country: String,
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A tax rate artworks can be charged at. Rates are kept in basis points
+/// (1800 = 18%) so tax is computed in integers, like prices.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaxRate {
+    #[serde(default, skip_deserializing)]
+    pub id_tax_rate: String,
+    pub name: String,
+    pub rate_basis_points: i64,
+    // Applies to every artwork without a rate of its own. Exactly one rate is the default.
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl TaxRate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("A tax rate needs a name".to_string());
+        }
+        if !(0..=10_000).contains(&self.rate_basis_points) {
+            return Err("rate_basis_points must be between 0 and 10000".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Marks a customer as buying without VAT, e.g. a foreign buyer exporting the piece.
+#[derive(Debug, Deserialize)]
+pub struct TaxExemptionUpdate {
+    pub tax_exempt: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// The tax on one order line and what the customer pays for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineTax {
+    pub tax_minor: i64,
+    pub total_minor: i64,
+}
+
+impl LineTax {
+    /// `base_minor` is the line after discounts. A price that includes tax has the
+    /// tax carved out of it; one that does not has the tax added on top. Exempt
+    /// buyers pay no tax, so a tax-inclusive price is reduced by the tax it contains.
+    /// Halves of a minor unit round up.
+    pub fn compute(base_minor: i64, rate_basis_points: i64, price_includes_tax: bool, tax_exempt: bool) -> LineTax {
+        let base = i128::from(base_minor);
+        let rate = i128::from(rate_basis_points);
+        let divisor = if price_includes_tax { 10_000 + rate } else { 10_000 };
+        let tax = ((base * rate * 2 + divisor) / (divisor * 2)) as i64;
+        match (price_includes_tax, tax_exempt) {
+            (true, false) => LineTax { tax_minor: tax, total_minor: base_minor },
+            (false, false) => LineTax { tax_minor: tax, total_minor: base_minor + tax },
+            (true, true) => LineTax { tax_minor: 0, total_minor: base_minor - tax },
+            (false, true) => LineTax { tax_minor: 0, total_minor: base_minor },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineTax;
+
+    #[test]
+    fn exclusive_price_adds_tax_on_top() {
+        assert_eq!(LineTax::compute(10_000, 1800, false, false), LineTax { tax_minor: 1800, total_minor: 11_800 });
+    }
+
+    #[test]
+    fn inclusive_price_carves_tax_out() {
+        assert_eq!(LineTax::compute(11_800, 1800, true, false), LineTax { tax_minor: 1800, total_minor: 11_800 });
+        // 100 * 1700 / 11700 = 14.53
+        assert_eq!(LineTax::compute(100, 1700, true, false), LineTax { tax_minor: 15, total_minor: 100 });
+    }
+
+    #[test]
+    fn halves_of_a_minor_unit_round_up() {
+        // 25 * 18% = 4.5, 24 * 18% = 4.32
+        assert_eq!(LineTax::compute(25, 1800, false, false).tax_minor, 5);
+        assert_eq!(LineTax::compute(24, 1800, false, false).tax_minor, 4);
+        // 5 / 2 = 2.5 at a 100% rate
+        assert_eq!(LineTax::compute(5, 10_000, true, false).tax_minor, 3);
+    }
+
+    #[test]
+    fn exempt_buyer_pays_no_tax_on_an_exclusive_price() {
+        assert_eq!(LineTax::compute(10_000, 1800, false, true), LineTax { tax_minor: 0, total_minor: 10_000 });
+    }
+
+    #[test]
+    fn exempt_buyer_pays_the_inclusive_price_less_its_rounded_tax() {
+        assert_eq!(LineTax::compute(11_800, 1800, true, true), LineTax { tax_minor: 0, total_minor: 10_000 });
+        assert_eq!(LineTax::compute(100, 1700, true, true), LineTax { tax_minor: 0, total_minor: 85 });
+        assert_eq!(LineTax::compute(5, 10_000, true, true), LineTax { tax_minor: 0, total_minor: 2 });
+    }
+
+    #[test]
+    fn zero_rate_charges_nothing() {
+        assert_eq!(LineTax::compute(12_345, 0, false, false), LineTax { tax_minor: 0, total_minor: 12_345 });
+        assert_eq!(LineTax::compute(12_345, 0, true, true), LineTax { tax_minor: 0, total_minor: 12_345 });
+    }
+}
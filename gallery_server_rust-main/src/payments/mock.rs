@@ -0,0 +1,120 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+use crate::models::money::Money;
+use crate::models::payment::PaymentStatus;
+use super::{PaymentError, PaymentIntent, PaymentProvider, ProviderPayment, WebhookEvent};
+
+/// Built-in offline gateway. The payment method picks the outcome:
+/// `mock_success` authorizes at once, `mock_decline` is declined, and
+/// `mock_async` stays pending until a webhook settles it. Webhook bodies are
+/// `{"reference": "...", "outcome": "succeeded" | "declined"}`, signed in the
+/// `X-Payment-Signature` header as hex SHA-256 of the secret followed by the
+/// body (`MOCK_PAYMENT_WEBHOOK_SECRET`, default `mock-webhook-secret`).
+/// Intents live in memory and are forgotten on restart.
+pub struct MockPaymentProvider {
+    webhook_secret: String,
+    intents: Mutex<HashMap<String, MockIntent>>,
+}
+
+struct MockIntent {
+    amount: Money,
+    status: PaymentStatus,
+    refunded: i64,
+}
+
+#[derive(Deserialize)]
+struct MockWebhook {
+    reference: String,
+    outcome: String,
+}
+
+impl MockPaymentProvider {
+    pub fn from_env() -> Self {
+        let webhook_secret = std::env::var("MOCK_PAYMENT_WEBHOOK_SECRET").unwrap_or_else(|_| "mock-webhook-secret".to_string());
+        MockPaymentProvider { webhook_secret, intents: Mutex::new(HashMap::new()) }
+    }
+
+    fn sign(&self, body: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.webhook_secret.as_bytes());
+        hasher.update(body);
+        hex::encode(hasher.finalize())
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for MockPaymentProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn create_intent(&self, intent: &PaymentIntent) -> Result<ProviderPayment, PaymentError> {
+        let (status, failure_reason) = match intent.payment_method.as_str() {
+            "mock_success" => (PaymentStatus::Authorized, None),
+            "mock_decline" => (PaymentStatus::Declined, Some("Card declined by the mock gateway".to_string())),
+            "mock_async" => (PaymentStatus::Pending, None),
+            other => return Err(PaymentError::Provider(format!("unknown mock payment method '{}'", other))),
+        };
+        let reference = format!("mock_pi_{}", Uuid::new_v4().simple());
+        self.intents
+            .lock()
+            .unwrap()
+            .insert(reference.clone(), MockIntent { amount: intent.amount.clone(), status, refunded: 0 });
+        println!("💳 Mock payment {} ({}) for order {}: {} {}", reference, intent.id_payment, intent.id_order, intent.amount, status);
+        Ok(ProviderPayment { reference, status, failure_reason })
+    }
+
+    async fn capture(&self, reference: &str, amount: &Money) -> Result<ProviderPayment, PaymentError> {
+        let mut intents = self.intents.lock().unwrap();
+        let intent = intents
+            .get_mut(reference)
+            .ok_or_else(|| PaymentError::Provider(format!("no such payment '{}'", reference)))?;
+        if intent.status != PaymentStatus::Authorized {
+            return Err(PaymentError::Provider(format!("payment '{}' is {}, not authorized", reference, intent.status)));
+        }
+        if &intent.amount != amount {
+            return Err(PaymentError::Provider(format!("cannot capture {} on a payment of {}", amount, intent.amount)));
+        }
+        intent.status = PaymentStatus::Captured;
+        Ok(ProviderPayment { reference: reference.to_string(), status: PaymentStatus::Captured, failure_reason: None })
+    }
+
+    async fn refund(&self, reference: &str, amount: &Money) -> Result<String, PaymentError> {
+        let mut intents = self.intents.lock().unwrap();
+        let intent = intents
+            .get_mut(reference)
+            .ok_or_else(|| PaymentError::Provider(format!("no such payment '{}'", reference)))?;
+        if intent.amount.currency != amount.currency || intent.refunded + amount.minor > intent.amount.minor {
+            return Err(PaymentError::Provider(format!("cannot refund {} on a payment of {}", amount, intent.amount)));
+        }
+        intent.refunded += amount.minor;
+        Ok(format!("mock_re_{}", Uuid::new_v4().simple()))
+    }
+
+    async fn handle_webhook(&self, signature: Option<&str>, body: &[u8]) -> Result<WebhookEvent, PaymentError> {
+        if signature != Some(self.sign(body).as_str()) {
+            return Err(PaymentError::InvalidWebhook("bad signature".to_string()));
+        }
+        let event: MockWebhook = serde_json::from_slice(body).map_err(|e| PaymentError::InvalidWebhook(e.to_string()))?;
+        let (status, failure_reason) = match event.outcome.as_str() {
+            "succeeded" => (PaymentStatus::Authorized, None),
+            "declined" => (PaymentStatus::Declined, Some("Declined after confirmation by the mock gateway".to_string())),
+            other => return Err(PaymentError::InvalidWebhook(format!("unknown outcome '{}'", other))),
+        };
+
+        let mut intents = self.intents.lock().unwrap();
+        let intent = intents
+            .get_mut(&event.reference)
+            .ok_or_else(|| PaymentError::InvalidWebhook(format!("no such payment '{}'", event.reference)))?;
+        // Redelivered events report the state the payment already reached.
+        if intent.status != PaymentStatus::Pending {
+            return Ok(WebhookEvent { reference: event.reference, status: intent.status, failure_reason: None });
+        }
+        intent.status = status;
+        Ok(WebhookEvent { reference: event.reference, status, failure_reason })
+    }
+}
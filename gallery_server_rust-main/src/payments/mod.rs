@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use std::fmt;
+use crate::models::money::Money;
+use crate::models::payment::PaymentStatus;
+
+mod mock;
+
+pub use mock::MockPaymentProvider;
+
+/// What the server asks a provider to charge.
+#[derive(Debug, Clone)]
+pub struct PaymentIntent {
+    pub id_payment: String,
+    pub id_order: String,
+    pub amount: Money,
+    pub payment_method: String,
+}
+
+/// A provider's answer about one payment: `Authorized` payments are ready to
+/// capture, `Pending` ones will be settled later through the webhook.
+#[derive(Debug, Clone)]
+pub struct ProviderPayment {
+    pub reference: String,
+    pub status: PaymentStatus,
+    pub failure_reason: Option<String>,
+}
+
+/// A verified notification from the provider about an earlier payment.
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub reference: String,
+    pub status: PaymentStatus,
+    pub failure_reason: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum PaymentError {
+    /// The webhook was not signed by the provider or could not be read.
+    InvalidWebhook(String),
+    /// The provider refused the call or could not be reached.
+    Provider(String),
+}
+
+impl std::error::Error for PaymentError {}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentError::InvalidWebhook(reason) => write!(f, "invalid webhook: {}", reason),
+            PaymentError::Provider(reason) => write!(f, "payment provider error: {}", reason),
+        }
+    }
+}
+
+/// Talks to a payment gateway. Swap the implementation registered in `main.rs`
+/// to plug in a real provider. A declined card is a normal outcome (a
+/// `ProviderPayment` with status `Declined`), not an error.
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Stored on each payment so webhooks and refunds go back to the same provider.
+    fn name(&self) -> &'static str;
+
+    async fn create_intent(&self, intent: &PaymentIntent) -> Result<ProviderPayment, PaymentError>;
+
+    async fn capture(&self, reference: &str, amount: &Money) -> Result<ProviderPayment, PaymentError>;
+
+    /// Returns the provider's reference for the refund.
+    async fn refund(&self, reference: &str, amount: &Money) -> Result<String, PaymentError>;
+
+    /// Verifies and decodes a webhook call from the provider.
+    async fn handle_webhook(&self, signature: Option<&str>, body: &[u8]) -> Result<WebhookEvent, PaymentError>;
+}
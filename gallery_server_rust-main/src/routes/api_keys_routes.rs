@@ -0,0 +1,11 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, ADMIN};
+use crate::controllers::api_key_controller;
+
+pub fn api_keys_routes() -> impl HttpServiceFactory {
+    web::scope("/api-keys")
+        .wrap(ScopeGuard::new("api-keys", Access::Roles(ADMIN), Access::Roles(ADMIN)))
+        .service(api_key_controller::get_api_keys)
+        .service(api_key_controller::create_api_key)
+        .service(api_key_controller::revoke_api_key)
+}
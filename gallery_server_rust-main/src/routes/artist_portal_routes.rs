@@ -0,0 +1,13 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, ARTIST};
+use crate::controllers::artist_portal_controller;
+
+pub fn artist_portal_routes() -> impl HttpServiceFactory {
+    web::scope("/artist")
+        .wrap(ScopeGuard::new("artist", Access::Roles(ARTIST), Access::Roles(ARTIST)))
+        .service(artist_portal_controller::get_my_artworks)
+        .service(artist_portal_controller::submit_artwork)
+        .service(artist_portal_controller::edit_my_artwork)
+        .service(artist_portal_controller::withdraw_draft)
+        .service(artist_portal_controller::get_my_sales)
+}
@@ -1,12 +1,14 @@
-use actix_web::{web, Scope};
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
 use crate::controllers::artist_controller;
 
-pub fn artist_routes() -> Scope {
+pub fn artist_routes() -> impl HttpServiceFactory {
     web::scope("/artists")
+        .wrap(ScopeGuard::new("catalog", Access::Public, Access::Roles(STAFF)))
         .service(artist_controller::get_artists)
         .service(artist_controller::get_artists_born_after_1980)
         //.service(artist_controller::get_artist_by_id)
         .service(artist_controller::create_artist)  
         .service(artist_controller::delete_artist)
         .service(artist_controller::update_artist)
-}
\ No newline at end of file
+}
@@ -1,10 +1,16 @@
-use actix_web::{web, Scope};
+use actix_web::{dev::HttpServiceFactory, http::Method, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
 use crate::controllers::artwork_in_order_controller;
 
-pub fn artworks_in_order_routes() -> Scope {
+pub fn artworks_in_order_routes() -> impl HttpServiceFactory {
     web::scope("/artworks_in_order")
+        .wrap(
+            // customers see and add lines of their own orders only; handlers filter by owner
+            ScopeGuard::new("orders", Access::Authenticated, Access::Roles(STAFF))
+                .route(Method::POST, "/artworks_in_order/", Access::Authenticated),
+        )
         .service(artwork_in_order_controller::get_artworks_in_order)
         .service(artwork_in_order_controller::create_artwork_in_order)
         .service(artwork_in_order_controller::update_artwork_in_order)  
         .service(artwork_in_order_controller::delete_artwork_in_order)
-}
\ No newline at end of file
+}
@@ -1,12 +1,16 @@
-use actix_web::{web, Scope};
-use crate::controllers::artwork_controller;
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::{artwork_controller, auction_controller};
 
-pub fn artworks_routes() -> Scope {
+pub fn artworks_routes() -> impl HttpServiceFactory {
     web::scope("/artworks")
+        .wrap(ScopeGuard::new("catalog", Access::Public, Access::Roles(STAFF)))
         .service(artwork_controller::get_all_artworks)
         .service(artwork_controller::get_artwork_by_id)
         .service(artwork_controller::get_artworks_by_type)
+        .service(auction_controller::get_artwork_bids)
         .service(artwork_controller::create_artwork)  
         .service(artwork_controller::delete_artwork)
+        .service(artwork_controller::update_artwork_status)
         .service(artwork_controller::update_artwork)
 }
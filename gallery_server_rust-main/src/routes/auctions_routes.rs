@@ -0,0 +1,18 @@
+use actix_web::{dev::HttpServiceFactory, http::Method, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::auction_controller;
+
+pub fn auctions_routes() -> impl HttpServiceFactory {
+    web::scope("/auctions")
+        // a piece's full bid history is also at /artworks/{id}/bids
+        .wrap(
+            ScopeGuard::new("catalog", Access::Public, Access::Roles(STAFF))
+                .route(Method::POST, "/auctions/{id_auction}/bids", Access::Authenticated),
+        )
+        .service(auction_controller::get_auctions)
+        .service(auction_controller::create_auction)
+        .service(auction_controller::get_auction_bids)
+        .service(auction_controller::create_bid)
+        .service(auction_controller::cancel_auction)
+        .service(auction_controller::get_auction)
+}
@@ -0,0 +1,13 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard};
+use crate::controllers::cart_controller;
+
+pub fn cart_routes() -> impl HttpServiceFactory {
+    web::scope("/cart")
+        // every customer works on their own cart only
+        .wrap(ScopeGuard::new("cart", Access::Authenticated, Access::Authenticated))
+        .service(cart_controller::get_cart)
+        .service(cart_controller::add_to_cart)
+        .service(cart_controller::remove_from_cart)
+        .service(cart_controller::clear_cart)
+}
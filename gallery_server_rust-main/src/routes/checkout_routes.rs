@@ -0,0 +1,10 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard};
+use crate::controllers::checkout_controller;
+
+pub fn checkout_routes() -> impl HttpServiceFactory {
+    web::scope("/checkout")
+        // the order is always placed for the signed-in customer
+        .wrap(ScopeGuard::new("orders", Access::Authenticated, Access::Authenticated))
+        .service(checkout_controller::checkout)
+}
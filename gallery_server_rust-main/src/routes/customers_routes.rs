@@ -1,12 +1,39 @@
-use actix_web::{web, Scope};
-use crate::controllers::customer_controller;
+use actix_web::{dev::HttpServiceFactory, http::Method, web};
+use crate::auth::guard::{Access, ScopeGuard, ADMIN, STAFF};
+use crate::controllers::{customer_controller, email_verification_controller, login_throttle_controller, password_reset_controller, privacy_controller, session_controller};
 
-pub fn customer_routes() -> Scope {
+pub fn customer_routes() -> impl HttpServiceFactory {
     web::scope("/customers")
+        .wrap(
+            ScopeGuard::new("customers", Access::Roles(STAFF), Access::Roles(STAFF))
+                .route(Method::POST, "/customers/", Access::Public)
+                .route(Method::POST, "/customers/login", Access::Public)
+                .route(Method::POST, "/customers/logout", Access::Authenticated)
+                .route(Method::POST, "/customers/password-reset/request", Access::Public)
+                .route(Method::POST, "/customers/password-reset/confirm", Access::Public)
+                .route(Method::GET, "/customers/verify", Access::Public)
+                .route(Method::POST, "/customers/{customer_id}/verification-email", Access::Roles(ADMIN))
+                .route(Method::GET, "/customers/login-lockouts", Access::Roles(ADMIN))
+                .route(Method::POST, "/customers/{customer_id}/unlock", Access::Roles(ADMIN))
+                .route(Method::PUT, "/customers/{customer_id}/role", Access::Roles(ADMIN))
+                // owners may edit themselves; the handler checks ownership
+                .route(Method::PUT, "/customers/{customer_id}", Access::Authenticated),
+        )
         .service(customer_controller::get_customers)
         .service(customer_controller::get_customers_in_jerusalem)
         // .service(customer_controller::get_customers_by_id)
         .service(customer_controller::create_customer)
+        .service(customer_controller::login_customer)
+        .service(session_controller::logout)
+        .service(password_reset_controller::request_password_reset)
+        .service(password_reset_controller::confirm_password_reset)
+        .service(email_verification_controller::verify_email)
+        .service(email_verification_controller::resend_verification_email)
+        .service(login_throttle_controller::get_login_lockouts)
+        .service(login_throttle_controller::unlock_customer)
+        .service(privacy_controller::export_customer)
         .service(customer_controller::delete_customer)
+        .service(customer_controller::update_customer_role)
+        .service(customer_controller::update_customer_tax_exemption)
         .service(customer_controller::update_customer)
 }
@@ -0,0 +1,14 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::discount_controller;
+
+pub fn discounts_routes() -> impl HttpServiceFactory {
+    web::scope("/discounts")
+        // customers never list codes; they only type one in at checkout
+        .wrap(ScopeGuard::new("discounts", Access::Roles(STAFF), Access::Roles(STAFF)))
+        .service(discount_controller::get_discount_codes)
+        .service(discount_controller::get_discount_code)
+        .service(discount_controller::create_discount_code)
+        .service(discount_controller::update_discount_code)
+        .service(discount_controller::delete_discount_code)
+}
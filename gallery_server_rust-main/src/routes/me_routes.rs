@@ -0,0 +1,13 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard};
+use crate::controllers::me_controller;
+
+pub fn me_routes() -> impl HttpServiceFactory {
+    web::scope("/me")
+        .wrap(ScopeGuard::new("me", Access::Authenticated, Access::Authenticated))
+        .service(me_controller::get_me)
+        .service(me_controller::get_my_orders)
+        .service(me_controller::get_my_order)
+        .service(me_controller::export_my_data)
+        .service(me_controller::erase_me)
+}
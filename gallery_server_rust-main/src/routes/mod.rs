@@ -3,3 +3,15 @@ pub mod customers_routes;
 pub mod artists_routes;
 pub mod orders_routes;
 pub mod artworks_routes;
+pub mod api_keys_routes;
+pub mod me_routes;
+pub mod artist_portal_routes;
+pub mod checkout_routes;
+pub mod cart_routes;
+pub mod payments_routes;
+pub mod returns_routes;
+pub mod discounts_routes;
+pub mod tax_rates_routes;
+pub mod shipping_routes;
+pub mod auctions_routes;
+pub mod offers_routes;
\ No newline at end of file
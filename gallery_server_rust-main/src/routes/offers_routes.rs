@@ -0,0 +1,19 @@
+use actix_web::{dev::HttpServiceFactory, http::Method, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::offer_controller;
+
+pub fn offers_routes() -> impl HttpServiceFactory {
+    web::scope("/offers")
+        // customers work on their own offers; who may accept or reject depends on whose turn it is
+        .wrap(
+            ScopeGuard::new("orders", Access::Authenticated, Access::Authenticated)
+                .route(Method::POST, "/offers/{id_offer}/counter", Access::Roles(STAFF)),
+        )
+        .service(offer_controller::get_offers)
+        .service(offer_controller::create_offer)
+        .service(offer_controller::get_offer)
+        .service(offer_controller::counter_offer)
+        .service(offer_controller::accept_offer)
+        .service(offer_controller::reject_offer)
+        .service(offer_controller::withdraw_offer)
+}
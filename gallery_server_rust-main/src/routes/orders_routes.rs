@@ -1,13 +1,37 @@
-use actix_web::{web, Scope};
-use crate::controllers::order_controller::{get_orders, create_order, get_order_by_id, update_order, delete_order, get_orders_after_2025_01_01, get_detailed_orders};
+use actix_web::{dev::HttpServiceFactory, http::Method, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::invoice_controller::get_order_invoice;
+use crate::controllers::payment_controller::{create_payment, get_order_payments};
+use crate::controllers::return_controller::{create_return, get_order_returns};
+use crate::controllers::shipping_controller::{add_tracking_event, get_order_shipment, get_shipping_options, put_order_shipment};
+use crate::controllers::order_controller::{get_orders, create_order, get_order_by_id, update_order, delete_order, get_orders_after_2025_01_01, get_detailed_orders, update_order_status, get_order_history};
 
-pub fn orders_routes() -> Scope {
+pub fn orders_routes() -> impl HttpServiceFactory {
     web::scope("/orders")
+        .wrap(
+            // customers see and place their own orders only; handlers filter by owner
+            ScopeGuard::new("orders", Access::Authenticated, Access::Roles(STAFF))
+                .route(Method::POST, "/orders/", Access::Authenticated)
+                .route(Method::POST, "/orders/{id_order}/payments", Access::Authenticated)
+                .route(Method::POST, "/orders/{id_order}/returns", Access::Authenticated)
+                .route(Method::PUT, "/orders/{id_order}/shipment", Access::Authenticated),
+        )
         .service(get_orders)
         .service(create_order)
         .service(get_detailed_orders)
         .service(get_orders_after_2025_01_01)
+        .service(get_order_history)
+        .service(get_order_payments)
+        .service(create_payment)
+        .service(get_order_returns)
+        .service(create_return)
+        .service(get_shipping_options)
+        .service(get_order_shipment)
+        .service(put_order_shipment)
+        .service(add_tracking_event)
+        .service(get_order_invoice)
         .service(get_order_by_id)
+        .service(update_order_status)
         .service(update_order)
         .service(delete_order)
-}
\ No newline at end of file
+}
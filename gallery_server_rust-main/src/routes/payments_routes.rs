@@ -0,0 +1,14 @@
+use actix_web::{dev::HttpServiceFactory, http::Method, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::payment_controller;
+
+pub fn payments_routes() -> impl HttpServiceFactory {
+    web::scope("/payments")
+        .wrap(
+            // the provider calls the webhook without a login; it proves itself with a signature
+            ScopeGuard::new("payments", Access::Roles(STAFF), Access::Roles(STAFF))
+                .route(Method::POST, "/payments/webhook", Access::Public),
+        )
+        .service(payment_controller::payment_webhook)
+        .service(payment_controller::refund_payment)
+}
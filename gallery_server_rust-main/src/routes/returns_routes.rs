@@ -0,0 +1,11 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::return_controller;
+
+pub fn returns_routes() -> impl HttpServiceFactory {
+    web::scope("/returns")
+        // customers request returns under /orders/{id_order}/returns; deciding them is staff work
+        .wrap(ScopeGuard::new("orders", Access::Roles(STAFF), Access::Roles(STAFF)))
+        .service(return_controller::get_returns)
+        .service(return_controller::update_return_status)
+}
@@ -0,0 +1,13 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::shipping_controller;
+
+pub fn shipping_routes() -> impl HttpServiceFactory {
+    web::scope("/shipping-methods")
+        // quotes for a specific order live under /orders/{id_order}/shipping-options
+        .wrap(ScopeGuard::new("shipping", Access::Public, Access::Roles(STAFF)))
+        .service(shipping_controller::get_shipping_methods)
+        .service(shipping_controller::create_shipping_method)
+        .service(shipping_controller::update_shipping_method)
+        .service(shipping_controller::delete_shipping_method)
+}
@@ -0,0 +1,13 @@
+use actix_web::{dev::HttpServiceFactory, web};
+use crate::auth::guard::{Access, ScopeGuard, STAFF};
+use crate::controllers::tax_controller;
+
+pub fn tax_rates_routes() -> impl HttpServiceFactory {
+    web::scope("/tax-rates")
+        // shop fronts may show which rate is charged
+        .wrap(ScopeGuard::new("tax_rates", Access::Public, Access::Roles(STAFF)))
+        .service(tax_controller::get_tax_rates)
+        .service(tax_controller::create_tax_rate)
+        .service(tax_controller::update_tax_rate)
+        .service(tax_controller::delete_tax_rate)
+}